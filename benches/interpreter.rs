@@ -0,0 +1,256 @@
+//! Benchmarks of the interpreter, on adapters that are typically
+//! invoked in a hot loop.
+//!
+//! To compare two revisions, save a baseline on the first one, and
+//! compare the second one against it, on the same machine:
+//!
+//! ```sh
+//! git checkout <before> && cargo bench --bench interpreter -- --save-baseline before
+//! git checkout <after> && cargo bench --bench interpreter -- --baseline before
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::{cell::Cell, convert::TryInto, ops::Deref};
+use wasmer_interface_types::{
+    ast,
+    errors::HostError,
+    interpreter::{
+        wasm::structures::{self, LocalImportIndex, TypedIndex},
+        ExecutionContext, Instruction, Interpreter,
+    },
+    types::InterfaceType,
+    values::InterfaceValue,
+};
+
+/// A local or import function, which sums two `i32`.
+struct LocalImport {
+    inputs: Vec<InterfaceType>,
+    outputs: Vec<InterfaceType>,
+}
+
+impl structures::LocalImport for LocalImport {
+    fn inputs_cardinality(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn outputs_cardinality(&self) -> usize {
+        self.outputs.len()
+    }
+
+    fn inputs(&self) -> &[InterfaceType] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[InterfaceType] {
+        &self.outputs
+    }
+
+    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> {
+        let a: i32 = (&arguments[0]).try_into()?;
+        let b: i32 = (&arguments[1]).try_into()?;
+
+        Ok(vec![InterfaceValue::I32(a.wrapping_add(b))])
+    }
+}
+
+/// A memory view, with no bytes.
+struct MemoryView;
+
+impl structures::MemoryView for MemoryView {}
+
+impl Deref for MemoryView {
+    type Target = [Cell<u8>];
+
+    fn deref(&self) -> &Self::Target {
+        &[]
+    }
+}
+
+/// A memory, with no bytes.
+struct Memory;
+
+impl structures::Memory<MemoryView> for Memory {
+    fn view(&self) -> MemoryView {
+        MemoryView
+    }
+}
+
+/// An instance with a single local or import function, at index 0,
+/// and a single adapter calling it, at index 0.
+struct Instance {
+    sum: LocalImport,
+    wit_types: Vec<ast::Type>,
+    wit_adapters: Vec<ast::Adapter>,
+}
+
+impl structures::Instance<(), LocalImport, Memory, MemoryView> for Instance {
+    fn export(&self, _export_name: &str) -> Option<&()> {
+        None
+    }
+
+    fn local_or_import<I: TypedIndex + LocalImportIndex>(
+        &mut self,
+        index: I,
+    ) -> Option<&LocalImport> {
+        if index.index() == 0 {
+            Some(&self.sum)
+        } else {
+            None
+        }
+    }
+
+    fn memory(&self, _index: usize) -> Option<&Memory> {
+        None
+    }
+
+    fn wit_type(&self, index: u32) -> Option<&ast::Type> {
+        self.wit_types.get(index as usize)
+    }
+
+    fn wit_adapter(&self, index: u32) -> Option<&ast::Adapter> {
+        self.wit_adapters.get(index as usize)
+    }
+}
+
+fn instance() -> Instance {
+    Instance {
+        sum: LocalImport {
+            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+            outputs: vec![InterfaceType::I32],
+        },
+        wit_types: vec![ast::Type::Function {
+            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+            outputs: vec![InterfaceType::I32],
+        }],
+        wit_adapters: vec![ast::Adapter {
+            function_type: 0,
+            instructions: vec![
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallCore { function_index: 0 },
+            ],
+        }],
+    }
+}
+
+/// Benchmarks an adapter, with an interpreter resolved against the
+/// instance, and with an interpreter that isn't. The resolved
+/// interpreter is benchmarked with a reused execution context too.
+fn bench_adapter(
+    criterion: &mut Criterion,
+    name: &str,
+    instructions: Vec<Instruction>,
+    inputs: Vec<InterfaceValue>,
+) {
+    let mut instance = instance();
+    let interpreter: Interpreter<Instance, (), LocalImport, Memory, MemoryView> =
+        (&instructions).try_into().unwrap();
+    let resolved_interpreter = Interpreter::with_instance(&instructions, &mut instance).unwrap();
+
+    criterion.bench_function(name, |bencher| {
+        bencher.iter(|| interpreter.run(black_box(&inputs), &mut instance).unwrap())
+    });
+
+    criterion.bench_function(&format!("{} (resolved)", name), |bencher| {
+        bencher.iter(|| {
+            resolved_interpreter
+                .run(black_box(&inputs), &mut instance)
+                .unwrap()
+        })
+    });
+
+    let mut context = ExecutionContext::new();
+    let mut outputs = Vec::new();
+
+    criterion.bench_function(&format!("{} (resolved, reused context)", name), |bencher| {
+        bencher.iter(|| {
+            resolved_interpreter
+                .run_into(
+                    black_box(&inputs),
+                    &mut instance,
+                    &mut context,
+                    &mut outputs,
+                )
+                .unwrap()
+        })
+    });
+}
+
+/// Lowers the arguments, calls a core function, and lifts its result.
+fn call_core(criterion: &mut Criterion) {
+    bench_adapter(
+        criterion,
+        "call-core",
+        vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::I32FromS32,
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::I32FromS32,
+            Instruction::CallCore { function_index: 0 },
+            Instruction::S32FromI32,
+        ],
+        vec![InterfaceValue::S32(3), InterfaceValue::S32(4)],
+    );
+}
+
+/// Calls an adapter, which is compiled and executed by a nested
+/// runtime.
+fn call_adapter(criterion: &mut Criterion) {
+    bench_adapter(
+        criterion,
+        "call-adapter",
+        vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallAdapter { adapter_index: 0 },
+        ],
+        vec![InterfaceValue::I32(3), InterfaceValue::I32(4)],
+    );
+}
+
+/// Chains numeric conversions, which are the cheapest instructions,
+/// so the cost of the dispatch dominates.
+fn numeric_conversions(criterion: &mut Criterion) {
+    let mut instructions = vec![Instruction::ArgumentGet { index: 0 }];
+
+    for _ in 0..16 {
+        instructions.push(Instruction::I32FromU8);
+        instructions.push(Instruction::U8FromI32);
+    }
+
+    bench_adapter(
+        criterion,
+        "numeric conversions",
+        instructions,
+        vec![InterfaceValue::U8(42)],
+    );
+}
+
+/// Lifts a list whose elements are computed by a core function with
+/// `list.lift`, which executes the control flow instructions in a
+/// loop.
+fn list_lift(criterion: &mut Criterion) {
+    bench_adapter(
+        criterion,
+        "list.lift",
+        vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ListLift {
+                element_type: InterfaceType::I32,
+            },
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::CallCore { function_index: 0 },
+            Instruction::End,
+        ],
+        vec![InterfaceValue::I32(64)],
+    );
+}
+
+criterion_group!(
+    benches,
+    call_core,
+    call_adapter,
+    numeric_conversions,
+    list_lift
+);
+criterion_main!(benches);
@@ -2,10 +2,11 @@
 //! representation of the language.
 
 use crate::{
+    errors::{BuildError, BuildResult},
     interpreter::Instruction,
     types::{InterfaceType, RecordType},
 };
-use std::str;
+use std::{borrow::Cow, str};
 
 /// Represents the kind of type.
 #[derive(PartialEq, Debug)]
@@ -45,25 +46,48 @@ pub enum Type {
 #[derive(PartialEq, Debug)]
 pub struct Import<'input> {
     /// The function namespace.
-    pub namespace: &'input str,
+    pub namespace: Cow<'input, str>,
 
     /// The function name.
-    pub name: &'input str,
+    pub name: Cow<'input, str>,
 
     /// The type signature.
     pub signature_type: u32,
 }
 
+impl Import<'_> {
+    /// Converts the import into an import owning its namespace and
+    /// its name, independent of the parsed input.
+    pub fn into_owned(self) -> Import<'static> {
+        Import {
+            namespace: Cow::Owned(self.namespace.into_owned()),
+            name: Cow::Owned(self.name.into_owned()),
+            signature_type: self.signature_type,
+        }
+    }
+}
+
 /// Represents an exported function signature.
 #[derive(PartialEq, Debug)]
 pub struct Export<'input> {
     /// The export name.
-    pub name: &'input str,
+    pub name: Cow<'input, str>,
 
     /// The WIT function type being exported.
     pub function_type: u32,
 }
 
+impl Export<'_> {
+    /// Converts the export into an export owning its name,
+    /// independent of the parsed input.
+    pub fn into_owned(self) -> Export<'static> {
+        Export {
+            name: Cow::Owned(self.name.into_owned()),
+            function_type: self.function_type,
+        }
+    }
+}
+
 /// Represents an adapter.
 #[derive(PartialEq, Debug)]
 pub struct Adapter {
@@ -122,3 +146,453 @@ pub struct Interfaces<'input> {
     /// All the implementations.
     pub implementations: Vec<Implementation>,
 }
+
+impl Interfaces<'_> {
+    /// Converts the interfaces into interfaces owning all their
+    /// strings, independent of the parsed input, so that they can be
+    /// kept after the input is dropped.
+    pub fn into_owned(self) -> OwnedInterfaces {
+        Interfaces {
+            types: self.types,
+            imports: self.imports.into_iter().map(Import::into_owned).collect(),
+            adapters: self.adapters,
+            exports: self.exports.into_iter().map(Export::into_owned).collect(),
+            implementations: self.implementations,
+        }
+    }
+
+    /// Returns the index of the adapter of an implementation, i.e. of
+    /// the first adapter of its adapter function type, if any.
+    pub fn implementation_adapter(&self, implementation: &Implementation) -> Option<u32> {
+        self.adapters
+            .iter()
+            .position(|adapter| adapter.function_type == implementation.adapter_function_type)
+            .map(|position| position as u32)
+    }
+
+    /// Returns the index of the adapter implementing an export, i.e.
+    /// of the adapter of the first implementation of its function
+    /// type, if any.
+    pub fn export_adapter(&self, export: &Export) -> Option<u32> {
+        self.implementations
+            .iter()
+            .find(|implementation| implementation.core_function_type == export.function_type)
+            .and_then(|implementation| self.implementation_adapter(implementation))
+    }
+
+    /// Returns the index of the adapter implementing an import, i.e.
+    /// of the adapter of the first implementation of its signature
+    /// type, if any.
+    pub fn import_adapter(&self, import: &Import) -> Option<u32> {
+        self.implementations
+            .iter()
+            .find(|implementation| implementation.core_function_type == import.signature_type)
+            .and_then(|implementation| self.implementation_adapter(implementation))
+    }
+
+    /// Returns the name of the export implemented by the adapter at
+    /// `adapter_index`, see [`Interfaces::export_adapter`]. It returns
+    /// `None` if the adapter implements no export, or several.
+    pub fn adapter_export_name(&self, adapter_index: u32) -> Option<&str> {
+        let mut exports = self
+            .exports
+            .iter()
+            .filter(|export| self.export_adapter(export) == Some(adapter_index));
+
+        match (exports.next(), exports.next()) {
+            (Some(export), None) => Some(&export.name),
+            _ => None,
+        }
+    }
+}
+
+/// Represents a set of interfaces independent of any parsed input,
+/// see [`Interfaces::into_owned`].
+pub type OwnedInterfaces = Interfaces<'static>;
+
+macro_rules! handle {
+    ($(#[$documentation:meta])* $handle:ident) => {
+        $(#[$documentation])*
+        #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+        pub struct $handle(u32);
+
+        impl $handle {
+            /// Returns the index designated by the handle.
+            pub fn index(self) -> u32 {
+                self.0
+            }
+        }
+    };
+}
+
+handle!(
+    /// A handle to a function type added to an [`InterfacesBuilder`].
+    /// Its index is the one used by the adapters, the imports, the
+    /// exports and the implementations.
+    FunctionTypeHandle
+);
+
+handle!(
+    /// A handle to a record type added to an [`InterfacesBuilder`].
+    /// Its index is the one used by the `record.lift` and
+    /// `record.lower` instructions.
+    RecordTypeHandle
+);
+
+handle!(
+    /// A handle to an import added to an [`InterfacesBuilder`]. Its
+    /// index is the function index used by the `call-core`
+    /// instructions, since the imports occupy the first function
+    /// indices.
+    ImportHandle
+);
+
+handle!(
+    /// A handle to an adapter added to an [`InterfacesBuilder`]. Its
+    /// index is the one used by the `call-adapter` instructions.
+    AdapterHandle
+);
+
+/// Builds a set of interfaces programmatically. The builder returns
+/// a handle for each added type, import and adapter, which is then
+/// given to the other methods instead of a raw index, so that the
+/// built interfaces are consistent. Identical types are added once.
+///
+/// ```rust
+/// use wasmer_interface_types::{
+///     ast::{Interfaces, InterfacesBuilder, Type},
+///     interpreter::Instruction,
+///     types::InterfaceType,
+/// };
+///
+/// let mut builder = InterfacesBuilder::new();
+/// let core_type = builder.add_function_type(vec![InterfaceType::I32], vec![InterfaceType::I32]);
+/// let adapter_type = builder.add_function_type(vec![InterfaceType::S32], vec![InterfaceType::S32]);
+/// let double = builder.add_import("env", "double", core_type);
+/// let adapter = builder.add_adapter(
+///     adapter_type,
+///     vec![
+///         Instruction::ArgumentGet { index: 0 },
+///         Instruction::I32FromS32,
+///         Instruction::CallCore { function_index: double.index() },
+///         Instruction::S32FromI32,
+///     ],
+/// );
+/// builder.add_export("double", core_type);
+/// builder.add_implementation(core_type, adapter_type);
+///
+/// // The function types are added once.
+/// assert_eq!(
+///     builder.add_function_type(vec![InterfaceType::I32], vec![InterfaceType::I32]),
+///     core_type,
+/// );
+/// assert_eq!(adapter.index(), 0);
+///
+/// let interfaces: Interfaces = builder.build().unwrap();
+///
+/// assert_eq!(interfaces.types.len(), 2);
+/// assert_eq!(interfaces.exports[0].function_type, core_type.index());
+/// ```
+#[derive(Default, Debug)]
+pub struct InterfacesBuilder<'input> {
+    /// The interfaces being built.
+    interfaces: Interfaces<'input>,
+}
+
+impl<'input> InterfacesBuilder<'input> {
+    /// Creates a builder of empty interfaces.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a function type, unless an identical function type has
+    /// already been added, and returns its handle.
+    pub fn add_function_type(
+        &mut self,
+        inputs: Vec<InterfaceType>,
+        outputs: Vec<InterfaceType>,
+    ) -> FunctionTypeHandle {
+        FunctionTypeHandle(self.add_type(Type::Function { inputs, outputs }))
+    }
+
+    /// Adds a record type, unless an identical record type has
+    /// already been added, and returns its handle.
+    pub fn add_record_type(&mut self, record_type: RecordType) -> RecordTypeHandle {
+        RecordTypeHandle(self.add_type(Type::Record(record_type)))
+    }
+
+    /// Adds an import of the given function type, and returns its
+    /// handle.
+    pub fn add_import<N, M>(
+        &mut self,
+        namespace: N,
+        name: M,
+        function_type: FunctionTypeHandle,
+    ) -> ImportHandle
+    where
+        N: Into<Cow<'input, str>>,
+        M: Into<Cow<'input, str>>,
+    {
+        self.interfaces.imports.push(Import {
+            namespace: namespace.into(),
+            name: name.into(),
+            signature_type: function_type.index(),
+        });
+
+        ImportHandle(self.interfaces.imports.len() as u32 - 1)
+    }
+
+    /// Adds an adapter of the given function type, and returns its
+    /// handle.
+    pub fn add_adapter(
+        &mut self,
+        function_type: FunctionTypeHandle,
+        instructions: Vec<Instruction>,
+    ) -> AdapterHandle {
+        self.interfaces.adapters.push(Adapter {
+            function_type: function_type.index(),
+            instructions,
+        });
+
+        AdapterHandle(self.interfaces.adapters.len() as u32 - 1)
+    }
+
+    /// Adds an export of the given function type.
+    pub fn add_export<N>(&mut self, name: N, function_type: FunctionTypeHandle) -> &mut Self
+    where
+        N: Into<Cow<'input, str>>,
+    {
+        self.interfaces.exports.push(Export {
+            name: name.into(),
+            function_type: function_type.index(),
+        });
+
+        self
+    }
+
+    /// Adds an implementation of the core function type by the
+    /// adapter function type.
+    pub fn add_implementation(
+        &mut self,
+        core_function_type: FunctionTypeHandle,
+        adapter_function_type: FunctionTypeHandle,
+    ) -> &mut Self {
+        self.interfaces.implementations.push(Implementation {
+            core_function_type: core_function_type.index(),
+            adapter_function_type: adapter_function_type.index(),
+        });
+
+        self
+    }
+
+    /// Validates and returns the built interfaces. The handles already
+    /// keep the types, the imports and the adapters consistent, but
+    /// the indices used by the instructions, and the implementations,
+    /// are checked here:
+    ///
+    ///   * `arg.get` must read an input of its adapter,
+    ///   * `call-adapter` must call an added adapter,
+    ///   * `record.lift` and `record.lower` must refer to an added
+    ///     record type,
+    ///   * each implementation must have an adapter of its adapter
+    ///     function type.
+    ///
+    /// The function indices of `call-core` and `defer-call-core`
+    /// aren't checked, since they also address the functions of the
+    /// core module, which are unknown to the builder.
+    pub fn build(self) -> BuildResult<Interfaces<'input>> {
+        let interfaces = self.interfaces;
+
+        for (adapter_index, adapter) in interfaces.adapters.iter().enumerate() {
+            let adapter_index = adapter_index as u32;
+            let inputs_count = match interfaces.types.get(adapter.function_type as usize) {
+                Some(Type::Function { inputs, .. }) => inputs.len(),
+                _ => 0,
+            };
+
+            for (instruction_index, instruction) in adapter.instructions.iter().enumerate() {
+                match *instruction {
+                    Instruction::ArgumentGet { index } if index as usize >= inputs_count => {
+                        return Err(BuildError::ArgumentIsMissing {
+                            adapter_index,
+                            instruction_index,
+                            index,
+                        })
+                    }
+
+                    Instruction::CallAdapter {
+                        adapter_index: called_adapter_index,
+                    } if called_adapter_index as usize >= interfaces.adapters.len() => {
+                        return Err(BuildError::CalledAdapterIsMissing {
+                            adapter_index,
+                            instruction_index,
+                            called_adapter_index,
+                        })
+                    }
+
+                    Instruction::RecordLift { type_index }
+                    | Instruction::RecordLower { type_index }
+                        if !matches!(
+                            interfaces.types.get(type_index as usize),
+                            Some(Type::Record(_))
+                        ) =>
+                    {
+                        return Err(BuildError::RecordTypeIsMissing {
+                            adapter_index,
+                            instruction_index,
+                            type_index,
+                        })
+                    }
+
+                    _ => (),
+                }
+            }
+        }
+
+        if let Some(implementation) = interfaces
+            .implementations
+            .iter()
+            .find(|implementation| interfaces.implementation_adapter(implementation).is_none())
+        {
+            return Err(BuildError::ImplementationAdapterIsMissing {
+                core_function_type: implementation.core_function_type,
+                adapter_function_type: implementation.adapter_function_type,
+            });
+        }
+
+        Ok(interfaces)
+    }
+
+    /// Adds a type, unless an identical type has already been added,
+    /// and returns its index.
+    fn add_type(&mut self, new_type: Type) -> u32 {
+        let types = &mut self.interfaces.types;
+
+        match types.iter().position(|type_| *type_ == new_type) {
+            Some(index) => index as u32,
+            None => {
+                types.push(new_type);
+
+                types.len() as u32 - 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::wat::{parse, Buffer};
+
+    #[test]
+    fn test_builder() {
+        let mut builder = InterfacesBuilder::new();
+        let record_type = builder.add_record_type(RecordType {
+            fields: crate::vec1![InterfaceType::String, InterfaceType::I32],
+        });
+        let function_type = builder.add_function_type(vec![InterfaceType::I32], vec![]);
+        let import = builder.add_import("ns", "foo", function_type);
+        let adapter = builder.add_adapter(
+            function_type,
+            vec![
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::CallCore {
+                    function_index: import.index(),
+                },
+            ],
+        );
+        builder
+            .add_export("bar", function_type)
+            .add_implementation(function_type, function_type);
+
+        assert_eq!(
+            builder.add_record_type(RecordType {
+                fields: crate::vec1![InterfaceType::String, InterfaceType::I32],
+            }),
+            record_type,
+        );
+        assert_eq!(
+            builder.add_function_type(vec![InterfaceType::I32], vec![]),
+            function_type,
+        );
+        assert_eq!((record_type.index(), function_type.index()), (0, 1));
+        assert_eq!((import.index(), adapter.index()), (0, 0));
+
+        let interfaces = builder.build().unwrap();
+        let wat = (&interfaces).to_string();
+        let buffer = Buffer::new(&wat).unwrap();
+
+        assert_eq!(parse(&buffer).unwrap(), interfaces);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_builder__invalid() {
+        let build = |instruction: Instruction| {
+            let mut builder = InterfacesBuilder::new();
+            let function_type = builder.add_function_type(vec![InterfaceType::I32], vec![]);
+            builder.add_adapter(function_type, vec![instruction]);
+
+            builder
+                .build()
+                .map(|_| ())
+                .map_err(|error| error.to_string())
+        };
+
+        assert_eq!(build(Instruction::ArgumentGet { index: 0 }), Ok(()));
+        assert_eq!(
+            build(Instruction::ArgumentGet { index: 1 }),
+            Err("the instruction `0` of the adapter `0` reads the argument `1` but it doesn't exist".into()),
+        );
+        assert_eq!(
+            build(Instruction::CallAdapter { adapter_index: 1 }),
+            Err(
+                "the instruction `0` of the adapter `0` calls the adapter `1` but it doesn't exist"
+                    .into()
+            ),
+        );
+        assert_eq!(
+            build(Instruction::RecordLift { type_index: 0 }),
+            Err("the instruction `0` of the adapter `0` refers to the type `0` but it isn't a record type".into()),
+        );
+
+        let mut builder = InterfacesBuilder::new();
+        let core_type = builder.add_function_type(vec![InterfaceType::I32], vec![]);
+        let adapter_type = builder.add_function_type(vec![InterfaceType::S32], vec![]);
+        builder.add_adapter(core_type, vec![]);
+        builder.add_implementation(core_type, adapter_type);
+
+        assert_eq!(
+            builder.build().err(),
+            Some(BuildError::ImplementationAdapterIsMissing {
+                core_function_type: 0,
+                adapter_function_type: 1,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_adapter_export_name() {
+        let buffer = Buffer::new(
+            r#"(@interface type (func (param) (result)))
+(@interface type (func (param i32) (result)))
+(@interface type (func (param s32) (result)))
+(@interface func (type 0))
+(@interface func (type 1))
+(@interface func (type 2))
+(@interface export "foo" (func 0))
+(@interface export "bar" (func 1))
+(@interface export "baz" (func 1))
+(@interface implement (func 0) (func 0))
+(@interface implement (func 1) (func 2))"#,
+        )
+        .unwrap();
+        let interfaces = parse(&buffer).unwrap();
+
+        assert_eq!(interfaces.export_adapter(&interfaces.exports[0]), Some(0));
+        assert_eq!(interfaces.export_adapter(&interfaces.exports[1]), Some(2));
+        assert_eq!(interfaces.adapter_export_name(0), Some("foo"));
+        assert_eq!(interfaces.adapter_export_name(1), None);
+        assert_eq!(interfaces.adapter_export_name(2), None);
+    }
+}
@@ -1,6 +1,11 @@
 //! Parse the WIT binary representation into an [AST](crate::ast).
 
-use crate::{ast::*, interpreter::Instruction, types::*, vec1::Vec1};
+use crate::{
+    ast::*,
+    interpreter::{BlockType, Instruction},
+    types::*,
+    vec1::Vec1,
+};
 use nom::{
     error::{make_error, ErrorKind, ParseError},
     Err, IResult,
@@ -100,6 +105,11 @@ fn ty<'input, E: ParseError<&'input [u8]>>(
 
             InterfaceType::Record(record_type)
         }
+        0x0f => {
+            consume!((input, element_type) = ty(input)?);
+
+            InterfaceType::List(Box::new(element_type))
+        }
         _ => return Err(Err::Error(make_error(input, ErrorKind::ParseTo))),
     };
 
@@ -120,6 +130,16 @@ fn record_type<'input, E: ParseError<&'input [u8]>>(
     ))
 }
 
+/// Parse a block type.
+fn block_type<'input, E: ParseError<&'input [u8]>>(
+    mut input: &'input [u8],
+) -> IResult<&'input [u8], BlockType, E> {
+    consume!((input, inputs) = list(input, ty)?);
+    consume!((input, outputs) = list(input, ty)?);
+
+    Ok((input, BlockType { inputs, outputs }))
+}
+
 /// Parse a UTF-8 string.
 fn string<'input, E: ParseError<&'input [u8]>>(
     input: &'input [u8],
@@ -256,6 +276,93 @@ fn instruction<'input, E: ParseError<&'input [u8]>>(
             )
         }
 
+        0x27 => {
+            consume!((input, argument_0) = uleb(input)?);
+
+            (
+                input,
+                Instruction::CallAdapter {
+                    adapter_index: argument_0 as u32,
+                },
+            )
+        }
+
+        0x28 => {
+            consume!((input, argument_0) = string(input)?);
+
+            (
+                input,
+                Instruction::CallExport {
+                    export_name: argument_0.to_string(),
+                },
+            )
+        }
+
+        0x29 => {
+            consume!((input, argument_0) = uleb(input)?);
+
+            (
+                input,
+                Instruction::DeferCallCore {
+                    function_index: argument_0 as u32,
+                },
+            )
+        }
+
+        0x2a => {
+            consume!((input, argument_0) = block_type(input)?);
+
+            (
+                input,
+                Instruction::Block {
+                    block_type: argument_0,
+                },
+            )
+        }
+        0x2b => {
+            consume!((input, argument_0) = block_type(input)?);
+
+            (
+                input,
+                Instruction::If {
+                    block_type: argument_0,
+                },
+            )
+        }
+        0x2c => (input, Instruction::Else),
+        0x2d => (input, Instruction::End),
+        0x2e => {
+            consume!((input, argument_0) = uleb(input)?);
+
+            (
+                input,
+                Instruction::BrIf {
+                    relative_depth: argument_0 as u32,
+                },
+            )
+        }
+
+        0x2f => {
+            consume!((input, argument_0) = ty(input)?);
+
+            (
+                input,
+                Instruction::ListLift {
+                    element_type: argument_0,
+                },
+            )
+        }
+        0x30 => {
+            consume!((input, argument_0) = ty(input)?);
+
+            (
+                input,
+                Instruction::ForEach {
+                    element_type: argument_0,
+                },
+            )
+        }
+
         _ => return Err(Err::Error(make_error(input, ErrorKind::ParseTo))),
     })
 }
@@ -307,8 +414,8 @@ fn imports<'input, E: ParseError<&'input [u8]>>(
         consume!((input, signature_type) = uleb(input)?);
 
         imports.push(Import {
-            namespace,
-            name,
+            namespace: namespace.into(),
+            name: name.into(),
             signature_type: signature_type as u32,
         });
     }
@@ -350,7 +457,7 @@ fn exports<'input, E: ParseError<&'input [u8]>>(
         consume!((input, function_type) = uleb(input)?);
 
         exports.push(Export {
-            name,
+            name: name.into(),
             function_type: function_type as u32,
         });
     }
@@ -493,8 +600,8 @@ fn interfaces<'input, E: ParseError<&'input [u8]>>(
 ///             outputs: vec![InterfaceType::S16],
 ///         }],
 ///         imports: vec![Import {
-///             namespace: "ab",
-///             name: "c",
+///             namespace: "ab".into(),
+///             name: "c".into(),
 ///             signature_type: 0,
 ///         }],
 ///         adapters: vec![Adapter {
@@ -502,7 +609,7 @@ fn interfaces<'input, E: ParseError<&'input [u8]>>(
 ///             instructions: vec![Instruction::ArgumentGet { index: 1 }],
 ///         }],
 ///         exports: vec![Export {
-///             name: "ab",
+///             name: "ab".into(),
 ///             function_type: 1,
 ///         }],
 ///         implementations: vec![Implementation {
@@ -601,7 +708,7 @@ mod tests {
     #[test]
     fn test_ty() {
         let input = &[
-            0x0f, // list of 15 items
+            0x10, // list of 16 items
             0x00, // S8
             0x01, // S16
             0x02, // S32
@@ -617,6 +724,7 @@ mod tests {
             0x0c, // I32
             0x0d, // I64
             0x0e, 0x01, 0x02, // Record
+            0x0f, 0x0c, // List
             0x01,
         ];
         let output = Ok((
@@ -639,6 +747,7 @@ mod tests {
                 InterfaceType::Record(RecordType {
                     fields: vec1![InterfaceType::S32],
                 }),
+                InterfaceType::List(Box::new(InterfaceType::I32)),
             ],
         ));
 
@@ -720,7 +829,7 @@ mod tests {
     #[test]
     fn test_instructions() {
         let input = &[
-            0x27, // list of 39 items
+            0x31, // list of 49 items
             0x00, 0x01, // ArgumentGet { index: 1 }
             0x01, 0x01, // CallCore { function_index: 1 }
             0x02, // S8FromI32
@@ -760,6 +869,17 @@ mod tests {
             0x24, // StringSize
             0x25, 0x01, // RecordLift { type_index: 1 },
             0x26, 0x01, // RecordLower { type_index: 1 },
+            0x27, 0x01, // CallAdapter { adapter_index: 1 },
+            0x28, 0x03, 0x61, 0x62, 0x63, // CallExport { export_name: "abc" },
+            0x29, 0x01, // DeferCallCore { function_index: 1 },
+            0x2a, 0x00, 0x00, // Block { block_type: BlockType { inputs: [], outputs: [] } },
+            0x2b, 0x01, 0x0c, 0x02, 0x00,
+            0x0a, // If { block_type: BlockType { inputs: [I32], outputs: [S8, String] } },
+            0x2c, // Else,
+            0x2d, // End,
+            0x2e, 0x01, // BrIf { relative_depth: 1 },
+            0x2f, 0x0a, // ListLift { element_type: String },
+            0x30, 0x0f, 0x0c, // ForEach { element_type: List(I32) },
             0x0a,
         ];
         let output = Ok((
@@ -804,6 +924,29 @@ mod tests {
                 Instruction::StringSize,
                 Instruction::RecordLift { type_index: 1 },
                 Instruction::RecordLower { type_index: 1 },
+                Instruction::CallAdapter { adapter_index: 1 },
+                Instruction::CallExport {
+                    export_name: "abc".into(),
+                },
+                Instruction::DeferCallCore { function_index: 1 },
+                Instruction::Block {
+                    block_type: BlockType::default(),
+                },
+                Instruction::If {
+                    block_type: BlockType {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::S8, InterfaceType::String],
+                    },
+                },
+                Instruction::Else,
+                Instruction::End,
+                Instruction::BrIf { relative_depth: 1 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::String,
+                },
+                Instruction::ForEach {
+                    element_type: InterfaceType::List(Box::new(InterfaceType::I32)),
+                },
             ],
         ));
 
@@ -825,11 +968,11 @@ mod tests {
             &[] as &[u8],
             vec![
                 Export {
-                    name: "ab",
+                    name: "ab".into(),
                     function_type: 1,
                 },
                 Export {
-                    name: "cd",
+                    name: "cd".into(),
                     function_type: 2,
                 },
             ],
@@ -888,13 +1031,13 @@ mod tests {
             &[] as &[u8],
             vec![
                 Import {
-                    namespace: "a",
-                    name: "b",
+                    namespace: "a".into(),
+                    name: "b".into(),
                     signature_type: 1,
                 },
                 Import {
-                    namespace: "c",
-                    name: "d",
+                    namespace: "c".into(),
+                    name: "d".into(),
                     signature_type: 2,
                 },
             ],
@@ -966,8 +1109,8 @@ mod tests {
                     outputs: vec![InterfaceType::S16],
                 }],
                 imports: vec![Import {
-                    namespace: "ab",
-                    name: "c",
+                    namespace: "ab".into(),
+                    name: "c".into(),
                     signature_type: 0,
                 }],
                 adapters: vec![Adapter {
@@ -975,7 +1118,7 @@ mod tests {
                     instructions: vec![Instruction::ArgumentGet { index: 1 }],
                 }],
                 exports: vec![Export {
-                    name: "ab",
+                    name: "ab".into(),
                     function_type: 1,
                 }],
                 implementations: vec![Implementation {
@@ -1,6 +1,11 @@
 //! Parse the WIT textual representation into an [AST](crate::ast).
 
-use crate::{ast::*, interpreter::Instruction, types::*, vec1::Vec1};
+use crate::{
+    ast::*,
+    interpreter::{BlockType, Instruction},
+    types::*,
+    vec1::Vec1,
+};
 pub use wast::parser::ParseBuffer as Buffer;
 use wast::parser::{self, Cursor, Parse, Parser, Peek, Result};
 
@@ -26,6 +31,7 @@ mod keyword {
     custom_keyword!(u32);
     custom_keyword!(u64);
     custom_keyword!(string);
+    custom_keyword!(list);
 
     // Instructions.
     custom_keyword!(argument_get = "arg.get");
@@ -67,6 +73,16 @@ mod keyword {
     custom_keyword!(string_size = "string.size");
     custom_keyword!(record_lift = "record.lift");
     custom_keyword!(record_lower = "record.lower");
+    custom_keyword!(call_adapter = "call-adapter");
+    custom_keyword!(call_export = "call-export");
+    custom_keyword!(defer_call_core = "defer-call-core");
+    custom_keyword!(block);
+    custom_keyword!(r#if = "if");
+    custom_keyword!(r#else = "else");
+    custom_keyword!(end);
+    custom_keyword!(br_if);
+    custom_keyword!(list_lift = "list.lift");
+    custom_keyword!(for_each = "for-each");
 }
 
 impl Parse<'_> for InterfaceType {
@@ -131,6 +147,10 @@ impl Parse<'_> for InterfaceType {
             Ok(InterfaceType::I64)
         } else if lookahead.peek::<keyword::record>() {
             Ok(InterfaceType::Record(parser.parse()?))
+        } else if lookahead.peek::<keyword::list>() {
+            parser.parse::<keyword::list>()?;
+
+            Ok(InterfaceType::List(Box::new(parser.parse()?)))
         } else {
             Err(lookahead.error())
         }
@@ -143,7 +163,10 @@ impl Parse<'_> for RecordType {
 
         let mut fields = vec![];
 
-        while !parser.is_empty() {
+        // Stop at the first token that is not a field, so that a
+        // record type can be followed by other tokens, like in an
+        // instruction immediate.
+        while parser.peek2::<keyword::field>() {
             fields.push(parser.parens(|parser| {
                 parser.parse::<keyword::field>()?;
 
@@ -326,12 +349,83 @@ impl<'a> Parse<'a> for Instruction {
             Ok(Instruction::RecordLower {
                 type_index: parser.parse()?,
             })
+        } else if lookahead.peek::<keyword::call_adapter>() {
+            parser.parse::<keyword::call_adapter>()?;
+
+            Ok(Instruction::CallAdapter {
+                adapter_index: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::call_export>() {
+            parser.parse::<keyword::call_export>()?;
+
+            Ok(Instruction::CallExport {
+                export_name: parser.parse::<&str>()?.to_string(),
+            })
+        } else if lookahead.peek::<keyword::defer_call_core>() {
+            parser.parse::<keyword::defer_call_core>()?;
+
+            Ok(Instruction::DeferCallCore {
+                function_index: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::block>() {
+            parser.parse::<keyword::block>()?;
+
+            Ok(Instruction::Block {
+                block_type: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::r#if>() {
+            parser.parse::<keyword::r#if>()?;
+
+            Ok(Instruction::If {
+                block_type: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::r#else>() {
+            parser.parse::<keyword::r#else>()?;
+
+            Ok(Instruction::Else)
+        } else if lookahead.peek::<keyword::end>() {
+            parser.parse::<keyword::end>()?;
+
+            Ok(Instruction::End)
+        } else if lookahead.peek::<keyword::br_if>() {
+            parser.parse::<keyword::br_if>()?;
+
+            Ok(Instruction::BrIf {
+                relative_depth: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::list_lift>() {
+            parser.parse::<keyword::list_lift>()?;
+
+            Ok(Instruction::ListLift {
+                element_type: parser.parse()?,
+            })
+        } else if lookahead.peek::<keyword::for_each>() {
+            parser.parse::<keyword::for_each>()?;
+
+            Ok(Instruction::ForEach {
+                element_type: parser.parse()?,
+            })
         } else {
             Err(lookahead.error())
         }
     }
 }
 
+impl Parse<'_> for BlockType {
+    fn parse(parser: Parser<'_>) -> Result<Self> {
+        let mut block_type = BlockType::default();
+
+        while parser.peek2::<keyword::param>() || parser.peek2::<keyword::result>() {
+            match parser.parse::<FunctionType>()? {
+                FunctionType::Input(mut inputs) => block_type.inputs.append(&mut inputs),
+                FunctionType::Output(mut outputs) => block_type.outputs.append(&mut outputs),
+            }
+        }
+
+        Ok(block_type)
+    }
+}
+
 struct AtInterface;
 
 impl Peek for AtInterface {
@@ -474,8 +568,8 @@ impl<'a> Parse<'a> for Import<'a> {
     fn parse(parser: Parser<'a>) -> Result<Self> {
         parser.parse::<keyword::import>()?;
 
-        let namespace = parser.parse()?;
-        let name = parser.parse()?;
+        let namespace = parser.parse::<&str>()?.into();
+        let name = parser.parse::<&str>()?.into();
 
         let signature_type = parser.parens(|parser| {
             parser.parse::<keyword::func>()?;
@@ -499,7 +593,7 @@ impl<'a> Parse<'a> for Export<'a> {
     fn parse(parser: Parser<'a>) -> Result<Self> {
         parser.parse::<keyword::export>()?;
 
-        let name = parser.parse()?;
+        let name = parser.parse::<&str>()?.into();
 
         let function_type = parser.parens(|parser| {
             parser.parse::<keyword::func>()?;
@@ -614,8 +708,8 @@ impl<'a> Parse<'a> for Interfaces<'a> {
 ///         outputs: vec![InterfaceType::S8],
 ///     }],
 ///     imports: vec![Import {
-///         namespace: "ns",
-///         name: "foo",
+///         namespace: "ns".into(),
+///         name: "foo".into(),
 ///         signature_type: 0,
 ///     }],
 ///     adapters: vec![Adapter {
@@ -623,7 +717,7 @@ impl<'a> Parse<'a> for Interfaces<'a> {
 ///         instructions: vec![Instruction::ArgumentGet { index: 42 }],
 ///     }],
 ///     exports: vec![Export {
-///         name: "bar",
+///         name: "bar".into(),
 ///         function_type: 0,
 ///     }],
 ///     implementations: vec![Implementation {
@@ -665,6 +759,7 @@ mod tests {
             "i32",
             "i64",
             "record (field string)",
+            "list record (field string)",
         ];
         let outputs = vec![
             InterfaceType::S8,
@@ -684,6 +779,9 @@ mod tests {
             InterfaceType::Record(RecordType {
                 fields: vec1![InterfaceType::String],
             }),
+            InterfaceType::List(Box::new(InterfaceType::Record(RecordType {
+                fields: vec1![InterfaceType::String],
+            }))),
         ];
 
         assert_eq!(inputs.len(), outputs.len());
@@ -773,6 +871,16 @@ mod tests {
             "string.size",
             "record.lift 42",
             "record.lower 42",
+            "call-adapter 42",
+            r#"call-export "foo""#,
+            "defer-call-core 7",
+            "block",
+            "if (param i32 i64) (result string)",
+            "else",
+            "end",
+            "br_if 7",
+            "list.lift string",
+            "for-each list i32",
         ];
         let outputs = vec![
             Instruction::ArgumentGet { index: 7 },
@@ -814,6 +922,29 @@ mod tests {
             Instruction::StringSize,
             Instruction::RecordLift { type_index: 42 },
             Instruction::RecordLower { type_index: 42 },
+            Instruction::CallAdapter { adapter_index: 42 },
+            Instruction::CallExport {
+                export_name: "foo".into(),
+            },
+            Instruction::DeferCallCore { function_index: 7 },
+            Instruction::Block {
+                block_type: BlockType::default(),
+            },
+            Instruction::If {
+                block_type: BlockType {
+                    inputs: vec![InterfaceType::I32, InterfaceType::I64],
+                    outputs: vec![InterfaceType::String],
+                },
+            },
+            Instruction::Else,
+            Instruction::End,
+            Instruction::BrIf { relative_depth: 7 },
+            Instruction::ListLift {
+                element_type: InterfaceType::String,
+            },
+            Instruction::ForEach {
+                element_type: InterfaceType::List(Box::new(InterfaceType::I32)),
+            },
         ];
 
         assert_eq!(inputs.len(), outputs.len());
@@ -883,7 +1014,7 @@ mod tests {
     fn test_export() {
         let input = buffer(r#"(@interface export "foo" (func 0))"#);
         let output = Interface::Export(Export {
-            name: "foo",
+            name: "foo".into(),
             function_type: 0,
         });
 
@@ -894,7 +1025,7 @@ mod tests {
     fn test_export_escaped_name() {
         let input = buffer(r#"(@interface export "fo\"o" (func 0))"#);
         let output = Interface::Export(Export {
-            name: r#"fo"o"#,
+            name: r#"fo"o"#.into(),
             function_type: 0,
         });
 
@@ -905,8 +1036,8 @@ mod tests {
     fn test_import() {
         let input = buffer(r#"(@interface import "ns" "foo" (func (type 0)))"#);
         let output = Interface::Import(Import {
-            namespace: "ns",
-            name: "foo",
+            namespace: "ns".into(),
+            name: "foo".into(),
             signature_type: 0,
         });
 
@@ -924,6 +1055,35 @@ mod tests {
         assert_eq!(parser::parse::<Interface>(&input).unwrap(), output);
     }
 
+    #[test]
+    fn test_adapter_with_blocks() {
+        let input = buffer(
+            r#"(@interface func (type 0) arg.get 0 if (result i32) arg.get 1 else block (param) end arg.get 2 end)"#,
+        );
+        let output = Interface::Adapter(Adapter {
+            function_type: 0,
+            instructions: vec![
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::If {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::Else,
+                Instruction::Block {
+                    block_type: BlockType::default(),
+                },
+                Instruction::End,
+                Instruction::ArgumentGet { index: 2 },
+                Instruction::End,
+            ],
+        });
+
+        assert_eq!(parser::parse::<Interface>(&input).unwrap(), output);
+    }
+
     #[test]
     fn test_implementation() {
         let input = buffer(r#"(@interface implement (func 0) (func 1))"#);
@@ -954,8 +1114,8 @@ mod tests {
                 outputs: vec![InterfaceType::S8],
             }],
             imports: vec![Import {
-                namespace: "ns",
-                name: "foo",
+                namespace: "ns".into(),
+                name: "foo".into(),
                 signature_type: 0,
             }],
             adapters: vec![Adapter {
@@ -963,7 +1123,7 @@ mod tests {
                 instructions: vec![Instruction::ArgumentGet { index: 42 }],
             }],
             exports: vec![Export {
-                name: "bar",
+                name: "bar".into(),
                 function_type: 0,
             }],
             implementations: vec![Implementation {
@@ -974,4 +1134,20 @@ mod tests {
 
         assert_eq!(parser::parse::<Interfaces>(&input).unwrap(), output);
     }
+
+    #[test]
+    fn test_interfaces_into_owned() {
+        let owned: OwnedInterfaces = {
+            let input = buffer(
+                r#"(@interface import "ns" "foo" (func (type 0)))
+(@interface export "bar" (func 0))"#,
+            );
+
+            parser::parse::<Interfaces>(&input).unwrap().into_owned()
+        };
+
+        assert_eq!(owned.imports[0].namespace, "ns");
+        assert_eq!(owned.imports[0].name, "foo");
+        assert_eq!(owned.exports[0].name, "bar");
+    }
 }
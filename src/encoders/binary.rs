@@ -1,6 +1,10 @@
 //! Writes the AST into bytes representing WIT with its binary format.
 
-use crate::{ast::*, interpreter::Instruction, types::*};
+use crate::{
+    ast::*,
+    interpreter::{BlockType, Instruction},
+    types::*,
+};
 use std::io::{self, Write};
 
 /// A trait for converting a value to bytes.
@@ -112,6 +116,10 @@ where
                 0x0e_u8.to_bytes(writer)?;
                 record_type.to_bytes(writer)
             }
+            InterfaceType::List(element_type) => {
+                0x0f_u8.to_bytes(writer)?;
+                element_type.to_bytes(writer)
+            }
         }
     }
 }
@@ -180,6 +188,21 @@ where
     }
 }
 
+/// Encode a `BlockType` into bytes.
+///
+/// Decoder is in `decoders::binary::block_type`.
+impl<W> ToBytes<W> for BlockType
+where
+    W: Write,
+{
+    fn to_bytes(&self, writer: &mut W) -> io::Result<()> {
+        self.inputs.to_bytes(writer)?;
+        self.outputs.to_bytes(writer)?;
+
+        Ok(())
+    }
+}
+
 /// Encode an `Import` into bytes.
 ///
 /// Decoder is in `decoders::binary::imports`.
@@ -188,8 +211,8 @@ where
     W: Write,
 {
     fn to_bytes(&self, writer: &mut W) -> io::Result<()> {
-        self.namespace.to_bytes(writer)?;
-        self.name.to_bytes(writer)?;
+        self.namespace.as_ref().to_bytes(writer)?;
+        self.name.as_ref().to_bytes(writer)?;
         (self.signature_type as u64).to_bytes(writer)?;
 
         Ok(())
@@ -219,7 +242,7 @@ where
     W: Write,
 {
     fn to_bytes(&self, writer: &mut W) -> io::Result<()> {
-        self.name.to_bytes(writer)?;
+        self.name.as_ref().to_bytes(writer)?;
         (self.function_type as u64).to_bytes(writer)?;
 
         Ok(())
@@ -342,6 +365,45 @@ where
                 0x26_u8.to_bytes(writer)?;
                 (*type_index as u64).to_bytes(writer)?
             }
+
+            Instruction::CallAdapter { adapter_index } => {
+                0x27_u8.to_bytes(writer)?;
+                (*adapter_index as u64).to_bytes(writer)?
+            }
+
+            Instruction::CallExport { export_name } => {
+                0x28_u8.to_bytes(writer)?;
+                export_name.as_str().to_bytes(writer)?
+            }
+
+            Instruction::DeferCallCore { function_index } => {
+                0x29_u8.to_bytes(writer)?;
+                (*function_index as u64).to_bytes(writer)?
+            }
+
+            Instruction::Block { block_type } => {
+                0x2a_u8.to_bytes(writer)?;
+                block_type.to_bytes(writer)?
+            }
+            Instruction::If { block_type } => {
+                0x2b_u8.to_bytes(writer)?;
+                block_type.to_bytes(writer)?
+            }
+            Instruction::Else => 0x2c_u8.to_bytes(writer)?,
+            Instruction::End => 0x2d_u8.to_bytes(writer)?,
+            Instruction::BrIf { relative_depth } => {
+                0x2e_u8.to_bytes(writer)?;
+                (*relative_depth as u64).to_bytes(writer)?
+            }
+
+            Instruction::ListLift { element_type } => {
+                0x2f_u8.to_bytes(writer)?;
+                element_type.to_bytes(writer)?
+            }
+            Instruction::ForEach { element_type } => {
+                0x30_u8.to_bytes(writer)?;
+                element_type.to_bytes(writer)?
+            }
         }
 
         Ok(())
@@ -446,6 +508,10 @@ mod tests {
             }),
             &[0x0e, 0x01, 0x0a]
         );
+        assert_to_bytes!(
+            InterfaceType::List(Box::new(InterfaceType::String)),
+            &[0x0f, 0x0a]
+        );
     }
 
     #[test]
@@ -504,7 +570,7 @@ mod tests {
     fn test_export() {
         assert_to_bytes!(
             Export {
-                name: "abc",
+                name: "abc".into(),
                 function_type: 0,
             },
             &[
@@ -554,8 +620,8 @@ mod tests {
     fn test_import() {
         assert_to_bytes!(
             Import {
-                namespace: "a",
-                name: "b",
+                namespace: "a".into(),
+                name: "b".into(),
                 signature_type: 0,
             },
             &[
@@ -592,8 +658,8 @@ mod tests {
                     outputs: vec![InterfaceType::S16],
                 }],
                 imports: vec![Import {
-                    namespace: "ab",
-                    name: "c",
+                    namespace: "ab".into(),
+                    name: "c".into(),
                     signature_type: 0,
                 }],
                 adapters: vec![Adapter {
@@ -601,7 +667,7 @@ mod tests {
                     instructions: vec![Instruction::ArgumentGet { index: 1 }],
                 }],
                 exports: vec![Export {
-                    name: "ab",
+                    name: "ab".into(),
                     function_type: 1,
                 }],
                 implementations: vec![Implementation {
@@ -689,9 +755,32 @@ mod tests {
                 Instruction::StringSize,
                 Instruction::RecordLift { type_index: 1 },
                 Instruction::RecordLower { type_index: 1 },
+                Instruction::CallAdapter { adapter_index: 1 },
+                Instruction::CallExport {
+                    export_name: "abc".into(),
+                },
+                Instruction::DeferCallCore { function_index: 1 },
+                Instruction::Block {
+                    block_type: BlockType::default(),
+                },
+                Instruction::If {
+                    block_type: BlockType {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::S8, InterfaceType::String],
+                    },
+                },
+                Instruction::Else,
+                Instruction::End,
+                Instruction::BrIf { relative_depth: 1 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::String,
+                },
+                Instruction::ForEach {
+                    element_type: InterfaceType::List(Box::new(InterfaceType::I32)),
+                },
             ],
             &[
-                0x27, // list of 39 items
+                0x31, // list of 49 items
                 0x00, 0x01, // ArgumentGet { index: 1 }
                 0x01, 0x01, // CallCore { function_index: 1 }
                 0x02, // S8FromI32
@@ -731,6 +820,18 @@ mod tests {
                 0x24, // StringSize
                 0x025, 0x01, // RecordLift { type_index: 1 }
                 0x026, 0x01, // RecordLower { type_index: 1 }
+                0x27, 0x01, // CallAdapter { adapter_index: 1 }
+                0x28, 0x03, 0x61, 0x62, 0x63, // CallExport { export_name: "abc" }
+                0x29, 0x01, // DeferCallCore { function_index: 1 }
+                0x2a, 0x00,
+                0x00, // Block { block_type: BlockType { inputs: [], outputs: [] } }
+                0x2b, 0x01, 0x0c, 0x02, 0x00,
+                0x0a, // If { block_type: BlockType { inputs: [I32], outputs: [S8, String] } }
+                0x2c, // Else
+                0x2d, // End
+                0x2e, 0x01, // BrIf { relative_depth: 1 }
+                0x2f, 0x0a, // ListLift { element_type: String }
+                0x30, 0x0f, 0x0c, // ForEach { element_type: List(I32) }
             ]
         );
     }
@@ -16,8 +16,8 @@
 //!         outputs: vec![InterfaceType::S8],
 //!     }],
 //!     imports: vec![Import {
-//!         namespace: "ns",
-//!         name: "foo",
+//!         namespace: "ns".into(),
+//!         name: "foo".into(),
 //!         signature_type: 0,
 //!     }],
 //!     adapters: vec![Adapter {
@@ -25,7 +25,7 @@
 //!         instructions: vec![Instruction::ArgumentGet { index: 42 }],
 //!     }],
 //!     exports: vec![Export {
-//!         name: "bar",
+//!         name: "bar".into(),
 //!         function_type: 0,
 //!     }],
 //!     implementations: vec![Implementation {
@@ -55,7 +55,11 @@
 //! assert_eq!(input, output);
 //! ```
 
-use crate::{ast::*, interpreter::Instruction, types::*};
+use crate::{
+    ast::*,
+    interpreter::{BlockType, Instruction},
+    types::*,
+};
 use std::string::ToString;
 
 /// Encode an `InterfaceType` into a string.
@@ -77,6 +81,9 @@ impl ToString for &InterfaceType {
             InterfaceType::I32 => "i32".to_string(),
             InterfaceType::I64 => "i64".to_string(),
             InterfaceType::Record(record_type) => record_type.to_string(),
+            InterfaceType::List(element_type) => {
+                format!("list {}", element_type.as_ref().to_string())
+            }
         }
     }
 }
@@ -140,8 +147,52 @@ impl ToString for &Instruction {
             Instruction::StringSize => "string.size".into(),
             Instruction::RecordLift { type_index } => format!("record.lift {}", type_index),
             Instruction::RecordLower { type_index } => format!("record.lower {}", type_index),
+            Instruction::CallAdapter { adapter_index } => {
+                format!("call-adapter {}", adapter_index)
+            }
+            Instruction::CallExport { export_name } => format!(r#"call-export "{}""#, export_name),
+            Instruction::DeferCallCore { function_index } => {
+                format!("defer-call-core {}", function_index)
+            }
+            Instruction::Block { block_type } => {
+                format!("block{}", block_type_to_string(block_type))
+            }
+            Instruction::If { block_type } => format!("if{}", block_type_to_string(block_type)),
+            Instruction::Else => "else".into(),
+            Instruction::End => "end".into(),
+            Instruction::BrIf { relative_depth } => format!("br_if {}", relative_depth),
+            Instruction::ListLift { element_type } => {
+                format!("list.lift {}", element_type.to_string())
+            }
+            Instruction::ForEach { element_type } => {
+                format!("for-each {}", element_type.to_string())
+            }
+        }
+    }
+}
+
+/// Encode a `BlockType` into a string.
+fn block_type_to_string(block_type: &BlockType) -> String {
+    let mut output = String::new();
+
+    for (keyword, types) in &[
+        ("param", &block_type.inputs),
+        ("result", &block_type.outputs),
+    ] {
+        if !types.is_empty() {
+            output.push_str(" (");
+            output.push_str(keyword);
+
+            for interface_type in types.iter() {
+                output.push(' ');
+                output.push_str(&interface_type.to_string());
+            }
+
+            output.push(')');
         }
     }
+
+    output
 }
 
 /// Encode a list of `InterfaceType` representing inputs into a
@@ -370,6 +421,7 @@ mod tests {
                 fields: vec1![InterfaceType::String],
             }))
                 .to_string(),
+            (&InterfaceType::List(Box::new(InterfaceType::String))).to_string(),
         ];
         let outputs = vec![
             "s8",
@@ -387,6 +439,7 @@ mod tests {
             "i32",
             "i64",
             "record (field string)",
+            "list string",
         ];
 
         assert_eq!(inputs, outputs);
@@ -465,6 +518,34 @@ mod tests {
             (&Instruction::StringSize).to_string(),
             (&Instruction::RecordLift { type_index: 42 }).to_string(),
             (&Instruction::RecordLower { type_index: 42 }).to_string(),
+            (&Instruction::CallAdapter { adapter_index: 42 }).to_string(),
+            (&Instruction::CallExport {
+                export_name: "foo".into(),
+            })
+                .to_string(),
+            (&Instruction::DeferCallCore { function_index: 7 }).to_string(),
+            (&Instruction::Block {
+                block_type: BlockType::default(),
+            })
+                .to_string(),
+            (&Instruction::If {
+                block_type: BlockType {
+                    inputs: vec![InterfaceType::I32, InterfaceType::I64],
+                    outputs: vec![InterfaceType::String],
+                },
+            })
+                .to_string(),
+            (&Instruction::Else).to_string(),
+            (&Instruction::End).to_string(),
+            (&Instruction::BrIf { relative_depth: 7 }).to_string(),
+            (&Instruction::ListLift {
+                element_type: InterfaceType::String,
+            })
+                .to_string(),
+            (&Instruction::ForEach {
+                element_type: InterfaceType::List(Box::new(InterfaceType::I32)),
+            })
+                .to_string(),
         ];
         let outputs = vec![
             "arg.get 7",
@@ -506,6 +587,16 @@ mod tests {
             "string.size",
             "record.lift 42",
             "record.lower 42",
+            "call-adapter 42",
+            r#"call-export "foo""#,
+            "defer-call-core 7",
+            "block",
+            "if (param i32 i64) (result string)",
+            "else",
+            "end",
+            "br_if 7",
+            "list.lift string",
+            "for-each list i32",
         ];
 
         assert_eq!(inputs, outputs);
@@ -557,7 +648,7 @@ mod tests {
     #[test]
     fn test_exports() {
         let input = (&Export {
-            name: "foo",
+            name: "foo".into(),
             function_type: 0,
         })
             .to_string();
@@ -569,8 +660,8 @@ mod tests {
     #[test]
     fn test_imports() {
         let input = (&Import {
-            namespace: "ns",
-            name: "foo",
+            namespace: "ns".into(),
+            name: "foo".into(),
             signature_type: 0,
         })
             .to_string();
@@ -600,8 +691,8 @@ mod tests {
                 outputs: vec![InterfaceType::S8],
             }],
             imports: vec![Import {
-                namespace: "ns",
-                name: "foo",
+                namespace: "ns".into(),
+                name: "foo".into(),
                 signature_type: 0,
             }],
             adapters: vec![Adapter {
@@ -609,7 +700,7 @@ mod tests {
                 instructions: vec![Instruction::ArgumentGet { index: 42 }],
             }],
             exports: vec![Export {
-                name: "bar",
+                name: "bar".into(),
                 function_type: 0,
             }],
             implementations: vec![Implementation {
@@ -14,7 +14,18 @@ use std::{
 pub type InstructionResult<T> = Result<T, InstructionError>;
 
 /// A type alias for the interpreter result.
-pub type InterpreterResult<T> = Result<T, InstructionError>;
+pub type InterpreterResult<T> = Result<T, InterpreterError>;
+
+/// A type alias for the interface module result.
+pub type ModuleResult<T> = Result<T, ModuleError>;
+
+/// A type alias for the result of building interfaces.
+pub type BuildResult<T> = Result<T, BuildError>;
+
+/// A type alias for the errors raised by the host when a local or
+/// import function, or an exported function, is called, like a trap
+/// of the WebAssembly runtime.
+pub type HostError = Box<dyn Error + Send + Sync>;
 
 /// Structure to represent errors when casting from an `InterfaceType`
 /// to a native value.
@@ -44,20 +55,69 @@ pub struct InstructionError {
     /// The instruction that raises the error.
     pub instruction: Instruction,
 
-    /// The error kind.
-    pub error_kind: InstructionErrorKind,
+    /// The error kind. It is boxed to keep the results of the
+    /// instructions small, since it's usually the largest part of
+    /// the error.
+    pub error_kind: Box<InstructionErrorKind>,
+
+    /// The positions of the instructions being executed when the
+    /// error has been raised, from the instruction that raises the
+    /// error to the instruction given to the interpreter, through the
+    /// `call-adapter` instructions. It is empty if the positions are
+    /// unknown.
+    pub backtrace: Vec<ErrorFrame>,
 }
 
 impl InstructionError {
     pub(crate) fn new(instruction: Instruction, error_kind: InstructionErrorKind) -> Self {
         Self {
             instruction,
-            error_kind,
+            error_kind: Box::new(error_kind),
+            backtrace: Vec::new(),
+        }
+    }
+
+    /// Returns the offset of the instruction that raises the error in
+    /// its adapter, if known.
+    pub fn offset(&self) -> Option<usize> {
+        self.backtrace.first().map(|frame| frame.offset)
+    }
+
+    /// Returns the adapter of the instruction that raises the error,
+    /// if it has been called with `call-adapter`.
+    pub fn adapter(&self) -> Option<&ErrorAdapter> {
+        self.backtrace
+            .first()
+            .and_then(|frame| frame.adapter.as_ref())
+    }
+
+    /// Adds a frame to the backtrace, for the instruction at `offset`
+    /// that has been executed by the outer adapter.
+    pub(crate) fn at_offset(mut self, offset: usize) -> Self {
+        self.backtrace.push(ErrorFrame {
+            offset,
+            adapter: None,
+        });
+
+        self
+    }
+
+    /// Describes the adapter of the outermost frame of the backtrace,
+    /// once the error has been propagated out of this adapter.
+    pub(crate) fn in_adapter(mut self, adapter: ErrorAdapter) -> Self {
+        if let Some(frame) = self.backtrace.last_mut() {
+            frame.adapter = Some(adapter);
         }
+
+        self
     }
 }
 
-impl Error for InstructionError {}
+impl Error for InstructionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error_kind.source()
+    }
+}
 
 impl Display for InstructionError {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
@@ -66,7 +126,543 @@ impl Display for InstructionError {
             "`{}` {}",
             (&self.instruction).to_string(),
             self.error_kind
-        )
+        )?;
+
+        // Consecutive identical frames, like the ones of an adapter
+        // calling itself, are rendered once.
+        let mut frames = self.backtrace.iter().peekable();
+
+        while let Some(frame) = frames.next() {
+            let mut repetitions = 1;
+
+            while frames.peek() == Some(&frame) {
+                frames.next();
+                repetitions += 1;
+            }
+
+            write!(formatter, "\n  at {}", frame)?;
+
+            if repetitions > 1 {
+                write!(formatter, " ({} times)", repetitions)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Structure to represent the errors of an interpreter: the error of
+/// an instruction, or a mismatch with the signature of the adapter,
+/// if the interpreter knows it.
+#[derive(Debug)]
+pub enum InterpreterError {
+    /// An instruction has failed.
+    Instruction(InstructionError),
+
+    /// The invocation inputs don't match the inputs of the adapter.
+    InvocationInputsMismatch {
+        /// The expected input types.
+        expected: Vec<InterfaceType>,
+
+        /// The received input types.
+        received: Vec<InterfaceType>,
+    },
+
+    /// The adapter has left fewer values on the stack than its
+    /// outputs.
+    MissingOutputs {
+        /// The expected output types.
+        expected: Vec<InterfaceType>,
+
+        /// The types of the values left on the stack.
+        received: Vec<InterfaceType>,
+    },
+
+    /// The adapter has left more values on the stack than its
+    /// outputs.
+    LeftoverOutputs {
+        /// The expected output types.
+        expected: Vec<InterfaceType>,
+
+        /// The types of the values left on the stack.
+        received: Vec<InterfaceType>,
+    },
+
+    /// The adapter has left as many values on the stack as its
+    /// outputs, but not of the expected types.
+    OutputsMismatch {
+        /// The expected output types.
+        expected: Vec<InterfaceType>,
+
+        /// The types of the values left on the stack.
+        received: Vec<InterfaceType>,
+    },
+
+    /// The native types of a typed adapter don't match the signature
+    /// of the adapter.
+    TypedSignatureMismatch {
+        /// The signature of the adapter.
+        expected: (Vec<InterfaceType>, Vec<InterfaceType>),
+
+        /// The interface types of the native parameters and results.
+        received: (Vec<InterfaceType>, Vec<InterfaceType>),
+    },
+}
+
+impl From<InstructionError> for InterpreterError {
+    fn from(error: InstructionError) -> Self {
+        Self::Instruction(error)
+    }
+}
+
+impl Error for InterpreterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Instruction(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for InterpreterError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Instruction(error) => write!(formatter, "{}", error),
+
+            Self::InvocationInputsMismatch { expected, received } => write!(
+                formatter,
+                "the adapter expects invocation inputs of kind `{:?}` but it received values of kind `{:?}`",
+                expected, received,
+            ),
+
+            Self::MissingOutputs { expected, received } => write!(
+                formatter,
+                "the adapter must return values of kind `{:?}` but it has left fewer values on the stack, of kind `{:?}`",
+                expected, received,
+            ),
+
+            Self::LeftoverOutputs { expected, received } => write!(
+                formatter,
+                "the adapter must return values of kind `{:?}` but it has left more values on the stack, of kind `{:?}`",
+                expected, received,
+            ),
+
+            Self::OutputsMismatch { expected, received } => write!(
+                formatter,
+                "the adapter must return values of kind `{:?}` but it has left values of kind `{:?}` on the stack",
+                expected, received,
+            ),
+
+            Self::TypedSignatureMismatch { expected, received } => write!(
+                formatter,
+                "the adapter has the signature `{:?} -> {:?}` but it is typed as `{:?} -> {:?}`",
+                expected.0, expected.1, received.0, received.1,
+            ),
+        }
+    }
+}
+
+/// Structure to represent the errors of an interface module: the
+/// errors raised when the module is built from its interfaces, and
+/// the errors raised when one of its exports is called.
+#[derive(Debug)]
+pub enum ModuleError {
+    /// The function type of an adapter doesn't exist.
+    AdapterTypeIsMissing {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The function type index of the adapter.
+        function_type: u32,
+    },
+
+    /// The function type of an adapter isn't a function type.
+    AdapterTypeIsNotAFunction {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The function type index of the adapter.
+        function_type: u32,
+    },
+
+    /// The instructions of an adapter can't be compiled.
+    InvalidAdapter {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The compilation error.
+        error: InstructionError,
+    },
+
+    /// No implementation has the function type of an export as its
+    /// core function type.
+    ExportIsNotImplemented {
+        /// The export name.
+        export_name: String,
+    },
+
+    /// No adapter has the adapter function type of an implementation.
+    ImplementationAdapterIsMissing {
+        /// The core function type of the implementation.
+        core_function_type: u32,
+
+        /// The adapter function type of the implementation.
+        adapter_function_type: u32,
+    },
+
+    /// The type of an import doesn't exist, or isn't a function type.
+    ImportTypeIsInvalid {
+        /// The import namespace.
+        namespace: String,
+
+        /// The import name.
+        name: String,
+
+        /// The type index of the import.
+        signature_type: u32,
+    },
+
+    /// A host function is registered for an import, but no adapter
+    /// implements this import.
+    ImportAdapterIsMissing {
+        /// The import namespace.
+        namespace: String,
+
+        /// The import name.
+        name: String,
+    },
+
+    /// The adapter implementing an import doesn't call it with a
+    /// `call-core` instruction outside of any block.
+    ImportIsNotCalled {
+        /// The import namespace.
+        namespace: String,
+
+        /// The import name.
+        name: String,
+
+        /// The adapter index.
+        adapter_index: u32,
+    },
+
+    /// A host function is registered for an import that doesn't
+    /// exist.
+    HostImportIsUnknown {
+        /// The import namespace.
+        namespace: String,
+
+        /// The import name.
+        name: String,
+    },
+
+    /// The host function of an import has failed.
+    HostImportCall {
+        /// The import namespace.
+        namespace: String,
+
+        /// The import name.
+        name: String,
+
+        /// The error raised by the host function.
+        source: HostError,
+    },
+
+    /// The host function of an import has returned values that
+    /// aren't of the output types of the import.
+    HostImportOutputsMismatch {
+        /// The import namespace.
+        namespace: String,
+
+        /// The import name.
+        name: String,
+
+        /// The expected output types.
+        expected: Vec<InterfaceType>,
+
+        /// The types of the returned values.
+        received: Vec<InterfaceType>,
+    },
+
+    /// The called export doesn't exist.
+    ExportIsMissing {
+        /// The export name.
+        export_name: String,
+    },
+
+    /// The adapter of the called export has failed.
+    Interpreter(InterpreterError),
+}
+
+impl From<InterpreterError> for ModuleError {
+    fn from(error: InterpreterError) -> Self {
+        Self::Interpreter(error)
+    }
+}
+
+impl Error for ModuleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidAdapter { error, .. } => Some(error),
+            Self::HostImportCall { source, .. } => Some(source.as_ref()),
+            Self::Interpreter(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ModuleError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::AdapterTypeIsMissing {
+                adapter_index,
+                function_type,
+            } => write!(
+                formatter,
+                "the type `{}` of the adapter `{}` doesn't exist",
+                function_type, adapter_index,
+            ),
+
+            Self::AdapterTypeIsNotAFunction {
+                adapter_index,
+                function_type,
+            } => write!(
+                formatter,
+                "the type `{}` of the adapter `{}` isn't a function type",
+                function_type, adapter_index,
+            ),
+
+            Self::InvalidAdapter { adapter_index, .. } => {
+                write!(formatter, "the adapter `{}` is invalid", adapter_index)
+            }
+
+            Self::ExportIsNotImplemented { export_name } => write!(
+                formatter,
+                r#"the export "{}" isn't implemented by any adapter"#,
+                export_name,
+            ),
+
+            Self::ImplementationAdapterIsMissing {
+                core_function_type,
+                adapter_function_type,
+            } => write!(
+                formatter,
+                "the implementation of the type `{}` refers to the adapter type `{}` but no adapter has this type",
+                core_function_type, adapter_function_type,
+            ),
+
+            Self::ImportTypeIsInvalid {
+                namespace,
+                name,
+                signature_type,
+            } => write!(
+                formatter,
+                r#"the type `{}` of the import "{}"."{}" doesn't exist or isn't a function type"#,
+                signature_type, namespace, name,
+            ),
+
+            Self::ImportAdapterIsMissing { namespace, name } => write!(
+                formatter,
+                r#"no adapter implements the import "{}"."{}""#,
+                namespace, name,
+            ),
+
+            Self::ImportIsNotCalled {
+                namespace,
+                name,
+                adapter_index,
+            } => write!(
+                formatter,
+                r#"the adapter `{}` implementing the import "{}"."{}" doesn't call it outside of any block"#,
+                adapter_index, namespace, name,
+            ),
+
+            Self::HostImportIsUnknown { namespace, name } => write!(
+                formatter,
+                r#"a host function is registered for the import "{}"."{}" but it doesn't exist"#,
+                namespace, name,
+            ),
+
+            Self::HostImportCall {
+                namespace, name, ..
+            } => write!(
+                formatter,
+                r#"the host function of the import "{}"."{}" has failed"#,
+                namespace, name,
+            ),
+
+            Self::HostImportOutputsMismatch {
+                namespace,
+                name,
+                expected,
+                received,
+            } => write!(
+                formatter,
+                r#"the host function of the import "{}"."{}" must return values of kind `{:?}` but it returned values of kind `{:?}`"#,
+                namespace, name, expected, received,
+            ),
+
+            Self::ExportIsMissing { export_name } => {
+                write!(formatter, r#"the export "{}" doesn't exist"#, export_name)
+            }
+
+            Self::Interpreter(error) => write!(formatter, "{}", error),
+        }
+    }
+}
+
+/// Structure to represent the errors raised when interfaces are
+/// built with
+/// [`InterfacesBuilder::build`](crate::ast::InterfacesBuilder::build).
+#[derive(PartialEq, Debug)]
+pub enum BuildError {
+    /// An `arg.get` instruction reads an argument that isn't an input
+    /// of its adapter.
+    ArgumentIsMissing {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The index of the instruction in the adapter.
+        instruction_index: usize,
+
+        /// The argument index.
+        index: u32,
+    },
+
+    /// A `call-adapter` instruction calls an adapter that hasn't been
+    /// added.
+    CalledAdapterIsMissing {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The index of the instruction in the adapter.
+        instruction_index: usize,
+
+        /// The index of the called adapter.
+        called_adapter_index: u32,
+    },
+
+    /// A `record.lift` or `record.lower` instruction refers to a type
+    /// that isn't an added record type.
+    RecordTypeIsMissing {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The index of the instruction in the adapter.
+        instruction_index: usize,
+
+        /// The type index.
+        type_index: u32,
+    },
+
+    /// No adapter has the adapter function type of an implementation.
+    ImplementationAdapterIsMissing {
+        /// The core function type index.
+        core_function_type: u32,
+
+        /// The adapter function type index.
+        adapter_function_type: u32,
+    },
+}
+
+impl Error for BuildError {}
+
+impl Display for BuildError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::ArgumentIsMissing {
+                adapter_index,
+                instruction_index,
+                index,
+            } => write!(
+                formatter,
+                "the instruction `{}` of the adapter `{}` reads the argument `{}` but it doesn't exist",
+                instruction_index, adapter_index, index,
+            ),
+
+            Self::CalledAdapterIsMissing {
+                adapter_index,
+                instruction_index,
+                called_adapter_index,
+            } => write!(
+                formatter,
+                "the instruction `{}` of the adapter `{}` calls the adapter `{}` but it doesn't exist",
+                instruction_index, adapter_index, called_adapter_index,
+            ),
+
+            Self::RecordTypeIsMissing {
+                adapter_index,
+                instruction_index,
+                type_index,
+            } => write!(
+                formatter,
+                "the instruction `{}` of the adapter `{}` refers to the type `{}` but it isn't a record type",
+                instruction_index, adapter_index, type_index,
+            ),
+
+            Self::ImplementationAdapterIsMissing {
+                core_function_type,
+                adapter_function_type,
+            } => write!(
+                formatter,
+                "the implementation of the type `{}` refers to the adapter type `{}` but no adapter has this type",
+                core_function_type, adapter_function_type,
+            ),
+        }
+    }
+}
+
+/// Represents a frame of the backtrace of an instruction error, i.e.
+/// the position of an instruction in its adapter.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ErrorFrame {
+    /// The offset of the instruction in the instructions of its
+    /// adapter.
+    pub offset: usize,
+
+    /// The adapter of the instruction, if it has been called with
+    /// `call-adapter`. It is `None` for the instructions given to the
+    /// interpreter.
+    pub adapter: Option<ErrorAdapter>,
+}
+
+impl Display for ErrorFrame {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "instruction {}", self.offset)?;
+
+        if let Some(adapter) = &self.adapter {
+            write!(formatter, " of {}", adapter)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents an adapter in the backtrace of an instruction error.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ErrorAdapter {
+    /// The adapter index.
+    pub adapter_index: u32,
+
+    /// The function type index of the adapter.
+    pub function_type: u32,
+
+    /// The name of the export implemented by the adapter, if any.
+    pub export_name: Option<String>,
+}
+
+impl Display for ErrorAdapter {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "adapter {} (type {}",
+            self.adapter_index, self.function_type
+        )?;
+
+        if let Some(export_name) = &self.export_name {
+            write!(formatter, r#", export "{}""#, export_name)?;
+        }
+
+        write!(formatter, ")")
     }
 }
 
@@ -131,6 +727,9 @@ pub enum InstructionErrorKind {
     LocalOrImportCall {
         /// The local or import function index that has been called.
         function_index: u32,
+
+        /// The error raised by the host.
+        source: HostError,
     },
 
     /// The memory doesn't exist.
@@ -171,9 +770,149 @@ pub enum InstructionErrorKind {
         /// The received kind.
         received_kind: TypeKind,
     },
+
+    /// The adapter doesn't exist.
+    AdapterIsMissing {
+        /// The adapter index.
+        adapter_index: u32,
+    },
+
+    /// Values given to an adapter doesn't match the adapter
+    /// signature.
+    AdapterSignatureMismatch {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The expected input types.
+        expected: Vec<InterfaceType>,
+
+        /// The received input types.
+        received: Vec<InterfaceType>,
+    },
+
+    /// The values returned by an adapter called with `call-adapter`
+    /// don't match its outputs.
+    AdapterOutputsMismatch {
+        /// The adapter index.
+        adapter_index: u32,
+
+        /// The expected output types.
+        expected: Vec<InterfaceType>,
+
+        /// The received output types.
+        received: Vec<InterfaceType>,
+    },
+
+    /// Too many adapters are nested, i.e. too many `call-adapter`
+    /// instructions have been executed recursively.
+    AdapterCallDepthExceeded {
+        /// The maximum depth.
+        max_depth: usize,
+    },
+
+    /// The fuel given to the interpreter has been consumed, i.e. too
+    /// many instructions have been executed.
+    FuelExhausted {
+        /// The fuel that was given.
+        fuel: u64,
+    },
+
+    /// The stack contains too many values.
+    StackDepthExceeded {
+        /// The maximum number of values.
+        max_stack_depth: usize,
+    },
+
+    /// Too many bytes have been copied out of the memory.
+    MemoryBytesExceeded {
+        /// The maximum number of bytes.
+        max_memory_bytes: usize,
+    },
+
+    /// A string is too long.
+    StringLengthExceeded {
+        /// The length of the string, in bytes.
+        length: usize,
+
+        /// The maximum length, in bytes.
+        max_string_length: usize,
+    },
+
+    /// The exported function doesn't exist.
+    ExportIsMissing {
+        /// The exported function name.
+        export_name: String,
+    },
+
+    /// Values given to an exported function doesn't match the
+    /// function signature.
+    ExportSignatureMismatch {
+        /// The exported function name.
+        export_name: String,
+
+        /// The expected signature.
+        expected: (Vec<InterfaceType>, Vec<InterfaceType>),
+
+        /// The received signature.
+        received: (Vec<InterfaceType>, Vec<InterfaceType>),
+    },
+
+    /// Failed to call an exported function.
+    ExportCall {
+        /// The exported function name that has been called.
+        export_name: String,
+
+        /// The error raised by the host.
+        source: HostError,
+    },
+
+    /// A `block` or an `if` instruction isn't closed by an `end`
+    /// instruction.
+    BlockIsNotClosed,
+
+    /// An `else` instruction doesn't belong to an `if` block.
+    ElseWithoutIf,
+
+    /// An `end` instruction doesn't close any block.
+    EndWithoutBlock,
+
+    /// A branch targets a block that doesn't exist.
+    InvalidBranchDepth {
+        /// The relative depth of the targeted block.
+        relative_depth: u32,
+    },
+
+    /// An `if` block without an `else` branch doesn't leave the same
+    /// types as it reads.
+    IfWithoutElseSignatureMismatch {
+        /// The input types of the block.
+        inputs: Vec<InterfaceType>,
+
+        /// The output types of the block.
+        outputs: Vec<InterfaceType>,
+    },
+
+    /// Values on the stack don't match the block signature.
+    BlockSignatureMismatch {
+        /// The expected types.
+        expected: Vec<InterfaceType>,
+
+        /// The received types.
+        received: Vec<InterfaceType>,
+    },
 }
 
-impl Error for InstructionErrorKind {}
+impl Error for InstructionErrorKind {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::LocalOrImportCall { source, .. } | Self::ExportCall { source, .. } => {
+                Some(source.as_ref())
+            }
+
+            _ => None,
+        }
+    }
+}
 
 impl Display for InstructionErrorKind {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
@@ -221,7 +960,7 @@ impl Display for InstructionErrorKind {
                 function_index, expected.0, expected.1, received.0, received.1,
             ),
 
-            Self::LocalOrImportCall  { function_index } => write!(
+            Self::LocalOrImportCall  { function_index, .. } => write!(
                 formatter,
                 "failed while calling the local or import function `{}`",
                 function_index
@@ -258,6 +997,108 @@ impl Display for InstructionErrorKind {
                 "read a type of kind `{:?}`, but the kind `{:?}` was expected",
                 received_kind, expected_kind
             ),
+
+            Self::AdapterIsMissing { adapter_index } => write!(
+                formatter,
+                "the adapter `{}` doesn't exist",
+                adapter_index
+            ),
+
+            Self::AdapterSignatureMismatch { adapter_index, expected, received } => write!(
+                formatter,
+                "the adapter `{}` expects values of kind `{:?}` but it received values of kind `{:?}`",
+                adapter_index, expected, received,
+            ),
+
+            Self::AdapterOutputsMismatch { adapter_index, expected, received } => write!(
+                formatter,
+                "the adapter `{}` must return values of kind `{:?}` but it returned values of kind `{:?}`",
+                adapter_index, expected, received,
+            ),
+
+            Self::AdapterCallDepthExceeded { max_depth } => write!(
+                formatter,
+                "reached the maximum depth of nested adapter calls ({})",
+                max_depth
+            ),
+
+            Self::FuelExhausted { fuel } => write!(
+                formatter,
+                "exhausted the fuel ({} instructions)",
+                fuel
+            ),
+
+            Self::StackDepthExceeded { max_stack_depth } => write!(
+                formatter,
+                "reached the maximum stack depth ({} values)",
+                max_stack_depth
+            ),
+
+            Self::MemoryBytesExceeded { max_memory_bytes } => write!(
+                formatter,
+                "reached the maximum number of bytes copied out of the memory ({} bytes)",
+                max_memory_bytes
+            ),
+
+            Self::StringLengthExceeded {
+                length,
+                max_string_length,
+            } => write!(
+                formatter,
+                "the string length ({} bytes) exceeds the maximum string length ({} bytes)",
+                length, max_string_length
+            ),
+
+            Self::ExportIsMissing { export_name } => write!(
+                formatter,
+                "the exported function `{}` doesn't exist",
+                export_name
+            ),
+
+            Self::ExportSignatureMismatch { export_name, expected, received } => write!(
+                formatter,
+                "the exported function `{}` has the signature `{:?} -> {:?}` but it received values of kind `{:?} -> {:?}`",
+                export_name, expected.0, expected.1, received.0, received.1,
+            ),
+
+            Self::ExportCall { export_name, .. } => write!(
+                formatter,
+                "failed while calling the exported function `{}`",
+                export_name
+            ),
+
+            Self::BlockIsNotClosed => write!(
+                formatter,
+                "the block is not closed by an `end` instruction"
+            ),
+
+            Self::ElseWithoutIf => write!(
+                formatter,
+                "the `else` instruction doesn't belong to an `if` block"
+            ),
+
+            Self::EndWithoutBlock => write!(
+                formatter,
+                "the `end` instruction doesn't close any block"
+            ),
+
+            Self::InvalidBranchDepth { relative_depth } => write!(
+                formatter,
+                "cannot branch to the block at depth `{}` because it doesn't exist",
+                relative_depth
+            ),
+
+            Self::IfWithoutElseSignatureMismatch { inputs, outputs } => write!(
+                formatter,
+                "an `if` block without `else` must leave the types it reads, but its signature is `{:?} -> {:?}`",
+                inputs, outputs,
+            ),
+
+            Self::BlockSignatureMismatch { expected, received } => write!(
+                formatter,
+                "the block expects values of kind `{:?}` but it received values of kind `{:?}`",
+                expected, received,
+            ),
         }
     }
 }
@@ -0,0 +1,1034 @@
+//! Compiles the instructions of an adapter into a flat set of
+//! operations, which are executed by a dispatch loop.
+//!
+//! An operation is the compiled form of an instruction: its operands
+//! are resolved once, at compile time, like the jump targets of the
+//! control flow instructions, or the types of the values read and
+//! left by a block.
+
+use super::{
+    instructions,
+    stack::Stackable,
+    wasm::{
+        self,
+        structures::{FunctionIndex, TypedIndex},
+    },
+    Runtime,
+};
+use crate::{
+    errors::{InstructionError, InstructionErrorKind, InstructionResult},
+    interpreter::Instruction,
+    types::{InterfaceType, RecordType},
+};
+
+/// Represents an operation, i.e. a compiled instruction. There is one
+/// operation per instruction, at the same position.
+#[derive(Debug)]
+pub(crate) enum Operation {
+    /// The `arg.get` instruction.
+    ArgumentGet { index: u32 },
+
+    /// The `call-core` instruction.
+    CallCore { function_index: u32 },
+
+    /// The `s8.from_i32` instruction.
+    S8FromI32,
+
+    /// The `s8.from_i64` instruction.
+    S8FromI64,
+
+    /// The `s16.from_i32` instruction.
+    S16FromI32,
+
+    /// The `s16.from_i64` instruction.
+    S16FromI64,
+
+    /// The `s32.from_i32` instruction.
+    S32FromI32,
+
+    /// The `s32.from_i64` instruction.
+    S32FromI64,
+
+    /// The `s64.from_i32` instruction.
+    S64FromI32,
+
+    /// The `s64.from_i64` instruction.
+    S64FromI64,
+
+    /// The `i32.from_s8` instruction.
+    I32FromS8,
+
+    /// The `i32.from_s16` instruction.
+    I32FromS16,
+
+    /// The `i32.from_s32` instruction.
+    I32FromS32,
+
+    /// The `i32.from_s64` instruction.
+    I32FromS64,
+
+    /// The `i64.from_s8` instruction.
+    I64FromS8,
+
+    /// The `i64.from_s16` instruction.
+    I64FromS16,
+
+    /// The `i64.from_s32` instruction.
+    I64FromS32,
+
+    /// The `i64.from_s64` instruction.
+    I64FromS64,
+
+    /// The `u8.from_i32` instruction.
+    U8FromI32,
+
+    /// The `u8.from_i64` instruction.
+    U8FromI64,
+
+    /// The `u16.from_i32` instruction.
+    U16FromI32,
+
+    /// The `u16.from_i64` instruction.
+    U16FromI64,
+
+    /// The `u32.from_i32` instruction.
+    U32FromI32,
+
+    /// The `u32.from_i64` instruction.
+    U32FromI64,
+
+    /// The `u64.from_i32` instruction.
+    U64FromI32,
+
+    /// The `u64.from_i64` instruction.
+    U64FromI64,
+
+    /// The `i32.from_u8` instruction.
+    I32FromU8,
+
+    /// The `i32.from_u16` instruction.
+    I32FromU16,
+
+    /// The `i32.from_u32` instruction.
+    I32FromU32,
+
+    /// The `i32.from_u64` instruction.
+    I32FromU64,
+
+    /// The `i64.from_u8` instruction.
+    I64FromU8,
+
+    /// The `i64.from_u16` instruction.
+    I64FromU16,
+
+    /// The `i64.from_u32` instruction.
+    I64FromU32,
+
+    /// The `i64.from_u64` instruction.
+    I64FromU64,
+
+    /// The `string.lift_memory` instruction.
+    StringLiftMemory,
+
+    /// The `string.lower_memory` instruction.
+    StringLowerMemory,
+
+    /// The `string.size` instruction.
+    StringSize,
+
+    /// The `record.lift` instruction.
+    RecordLift { type_index: u32 },
+
+    /// The `record.lower` instruction.
+    RecordLower { type_index: u32 },
+
+    /// The `call-core` instruction, resolved against an instance:
+    /// the function inputs are known ahead of time.
+    ResolvedCallCore {
+        function_index: u32,
+        inputs: Vec<InterfaceType>,
+        outputs: Vec<InterfaceType>,
+    },
+
+    /// The `record.lift` instruction, resolved against an instance.
+    ResolvedRecordLift { record_type: RecordType },
+
+    /// The `record.lower` instruction, resolved against an instance.
+    ResolvedRecordLower { record_type: RecordType },
+
+    /// The `call-adapter` instruction, resolved against an instance:
+    /// the adapter is compiled ahead of time, at `compiled_index` in
+    /// the compiled adapters.
+    ResolvedCallAdapter {
+        adapter_index: u32,
+        compiled_index: usize,
+    },
+
+    /// The `call-adapter` instruction.
+    CallAdapter { adapter_index: u32 },
+
+    /// The `call-export` instruction.
+    CallExport { export_name: String },
+
+    /// The `defer-call-core` instruction.
+    DeferCallCore { function_index: u32 },
+
+    /// The `block` instruction, which reads values of the `inputs`
+    /// types.
+    Block { inputs: Vec<InterfaceType> },
+
+    /// The `if` instruction, which reads values of the `inputs`
+    /// types, and jumps to `false_target` when the condition is
+    /// false.
+    If {
+        inputs: Vec<InterfaceType>,
+        false_target: usize,
+    },
+
+    /// The `else` instruction, which jumps to the `end` of its block.
+    Else { end_index: usize },
+
+    /// The `end` instruction of a `block` or an `if`, which leaves
+    /// values of the `outputs` types.
+    End { outputs: Vec<InterfaceType> },
+
+    /// The `br_if` instruction, which jumps to the `end` of the
+    /// targeted block, leaving values of the `outputs` types.
+    BrIf {
+        relative_depth: u32,
+        end_index: usize,
+        outputs: Vec<InterfaceType>,
+    },
+
+    /// The `list.lift` instruction, which skips its body up to
+    /// `end_index` when the list is empty.
+    ListLift {
+        element_type: InterfaceType,
+        end_index: usize,
+    },
+
+    /// The `for-each` instruction, which skips its body up to
+    /// `end_index` when the list is empty.
+    ForEach {
+        element_type: InterfaceType,
+        end_index: usize,
+    },
+
+    /// The `end` instruction of a `list.lift` or a `for-each`, which
+    /// jumps back to `body_index` while there are inputs left. The
+    /// `element_type` is defined for `list.lift` only.
+    IterationEnd {
+        input_types: Vec<InterfaceType>,
+        element_type: Option<InterfaceType>,
+        body_index: usize,
+    },
+}
+
+/// Represents a compiled set of instructions, ready to be executed.
+#[derive(Debug, Default)]
+pub(crate) struct Program {
+    /// The original instructions. They are used to report errors.
+    instructions: Vec<Instruction>,
+
+    /// The operations, at the same positions as their instructions.
+    operations: Vec<Operation>,
+}
+
+impl Program {
+    /// Returns the number of instructions of the program.
+    pub(super) fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Returns the instruction at the given index, if any.
+    pub(super) fn instruction(&self, index: usize) -> Option<&Instruction> {
+        self.instructions.get(index)
+    }
+}
+
+/// Represents an adapter compiled ahead of time, when a program is
+/// resolved against an instance, or when it is called for the first
+/// time by an unresolved program.
+#[derive(Debug)]
+pub(crate) struct CompiledAdapter {
+    /// The adapter index in the instance.
+    pub(super) adapter_index: u32,
+
+    /// The types of the adapter inputs.
+    pub(super) inputs: Vec<InterfaceType>,
+
+    /// The types of the adapter outputs.
+    pub(super) outputs: Vec<InterfaceType>,
+
+    /// The compiled and resolved instructions of the adapter.
+    pub(super) program: Program,
+}
+
+impl CompiledAdapter {
+    /// Checks whether the adapter has been compiled from an adapter
+    /// with these types and instructions.
+    pub(super) fn is_compiled_from(
+        &self,
+        inputs: &[InterfaceType],
+        outputs: &[InterfaceType],
+        instructions: &[Instruction],
+    ) -> bool {
+        self.inputs == inputs
+            && self.outputs == outputs
+            && self.program.instructions == instructions
+    }
+}
+
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Executes the operations of a program from the program counter,
+    /// until it goes past the last operation. The fuel and the stack
+    /// depth are checked for each operation, and the observer, if
+    /// any, is notified.
+    pub(super) fn execute_operations(&mut self, program: &Program) -> InstructionResult<()> {
+        while self.step(program)? {}
+
+        Ok(())
+    }
+
+    /// Executes the operation at the program counter, if any. It
+    /// returns `false` if the program counter is past the last
+    /// operation, i.e. if there is nothing left to execute.
+    pub(super) fn step(&mut self, program: &Program) -> InstructionResult<bool> {
+        let index = self.program_counter;
+        let (operation, instruction) = match program.operations.get(index) {
+            Some(operation) => (operation, &program.instructions[index]),
+            None => return Ok(false),
+        };
+
+        self.consume_fuel(instruction)
+            .map_err(|error| error.at_offset(index))?;
+        self.program_counter += 1;
+
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.before_instruction(index, instruction, self.stack.as_slice());
+        }
+
+        let result = self
+            .execute_operation(operation, instruction)
+            .and_then(|()| self.check_stack_depth(instruction));
+
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.after_instruction(
+                index,
+                instruction,
+                self.stack.as_slice(),
+                result.as_ref().err(),
+            );
+        }
+
+        result
+            .map(|()| true)
+            .map_err(|error| error.at_offset(index))
+    }
+
+    /// Executes the operation at the program counter, if any, like
+    /// [`Runtime::step`], except that a `call-core` operation calling
+    /// an asynchronous local or import function suspends the runtime
+    /// until the call is done.
+    pub(super) async fn step_async(&mut self, program: &Program) -> InstructionResult<bool> {
+        let index = self.program_counter;
+        let (function_index, instruction) = match program.operations.get(index) {
+            Some(Operation::CallCore { function_index })
+            | Some(Operation::ResolvedCallCore { function_index, .. })
+                if self
+                    .wasm_instance
+                    .async_local_or_import(FunctionIndex::new(*function_index as usize))
+                    .is_some() =>
+            {
+                (*function_index, &program.instructions[index])
+            }
+            _ => return self.step(program),
+        };
+
+        self.consume_fuel(instruction)
+            .map_err(|error| error.at_offset(index))?;
+        self.program_counter += 1;
+
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.before_instruction(index, instruction, self.stack.as_slice());
+        }
+
+        let result = instructions::async_call_core(self, function_index, instruction)
+            .await
+            .and_then(|()| self.check_stack_depth(instruction));
+
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.after_instruction(
+                index,
+                instruction,
+                self.stack.as_slice(),
+                result.as_ref().err(),
+            );
+        }
+
+        result
+            .map(|()| true)
+            .map_err(|error| error.at_offset(index))
+    }
+
+    /// Executes one operation. The instruction is the source of the
+    /// operation, used to report errors.
+    fn execute_operation(
+        &mut self,
+        operation: &Operation,
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        match operation {
+            Operation::ArgumentGet { index } => {
+                instructions::argument_get(self, *index, instruction)
+            }
+            Operation::CallCore { function_index } => {
+                instructions::call_core(self, *function_index, instruction)
+            }
+
+            Operation::S8FromI32 => instructions::s8_from_i32(self, instruction),
+            Operation::S8FromI64 => instructions::s8_from_i64(self, instruction),
+            Operation::S16FromI32 => instructions::s16_from_i32(self, instruction),
+            Operation::S16FromI64 => instructions::s16_from_i64(self, instruction),
+            Operation::S32FromI32 => instructions::s32_from_i32(self, instruction),
+            Operation::S32FromI64 => instructions::s32_from_i64(self, instruction),
+            Operation::S64FromI32 => instructions::s64_from_i32(self, instruction),
+            Operation::S64FromI64 => instructions::s64_from_i64(self, instruction),
+            Operation::I32FromS8 => instructions::i32_from_s8(self, instruction),
+            Operation::I32FromS16 => instructions::i32_from_s16(self, instruction),
+            Operation::I32FromS32 => instructions::i32_from_s32(self, instruction),
+            Operation::I32FromS64 => instructions::i32_from_s64(self, instruction),
+            Operation::I64FromS8 => instructions::i64_from_s8(self, instruction),
+            Operation::I64FromS16 => instructions::i64_from_s16(self, instruction),
+            Operation::I64FromS32 => instructions::i64_from_s32(self, instruction),
+            Operation::I64FromS64 => instructions::i64_from_s64(self, instruction),
+            Operation::U8FromI32 => instructions::u8_from_i32(self, instruction),
+            Operation::U8FromI64 => instructions::u8_from_i64(self, instruction),
+            Operation::U16FromI32 => instructions::u16_from_i32(self, instruction),
+            Operation::U16FromI64 => instructions::u16_from_i64(self, instruction),
+            Operation::U32FromI32 => instructions::u32_from_i32(self, instruction),
+            Operation::U32FromI64 => instructions::u32_from_i64(self, instruction),
+            Operation::U64FromI32 => instructions::u64_from_i32(self, instruction),
+            Operation::U64FromI64 => instructions::u64_from_i64(self, instruction),
+            Operation::I32FromU8 => instructions::i32_from_u8(self, instruction),
+            Operation::I32FromU16 => instructions::i32_from_u16(self, instruction),
+            Operation::I32FromU32 => instructions::i32_from_u32(self, instruction),
+            Operation::I32FromU64 => instructions::i32_from_u64(self, instruction),
+            Operation::I64FromU8 => instructions::i64_from_u8(self, instruction),
+            Operation::I64FromU16 => instructions::i64_from_u16(self, instruction),
+            Operation::I64FromU32 => instructions::i64_from_u32(self, instruction),
+            Operation::I64FromU64 => instructions::i64_from_u64(self, instruction),
+
+            Operation::StringLiftMemory => instructions::string_lift_memory(self, instruction),
+            Operation::StringLowerMemory => instructions::string_lower_memory(self, instruction),
+            Operation::StringSize => instructions::string_size(self, instruction),
+
+            Operation::RecordLift { type_index } => {
+                instructions::record_lift(self, *type_index, instruction)
+            }
+            Operation::RecordLower { type_index } => {
+                instructions::record_lower(self, *type_index, instruction)
+            }
+
+            Operation::CallAdapter { adapter_index } => {
+                instructions::call_adapter(self, *adapter_index, instruction)
+            }
+
+            Operation::ResolvedCallCore {
+                function_index,
+                inputs,
+                outputs,
+            } => instructions::resolved_call_core(
+                self,
+                *function_index,
+                inputs,
+                outputs,
+                instruction,
+            ),
+            Operation::ResolvedRecordLift { record_type } => {
+                instructions::resolved_record_lift(self, record_type, instruction)
+            }
+            Operation::ResolvedRecordLower { record_type } => {
+                instructions::resolved_record_lower(self, record_type, instruction)
+            }
+            Operation::ResolvedCallAdapter {
+                adapter_index,
+                compiled_index,
+            } => instructions::resolved_call_adapter(
+                self,
+                *adapter_index,
+                *compiled_index,
+                instruction,
+            ),
+
+            Operation::CallExport { export_name } => {
+                instructions::call_export(self, export_name, instruction)
+            }
+
+            Operation::DeferCallCore { function_index } => {
+                instructions::defer_call_core(self, *function_index, instruction)
+            }
+
+            Operation::Block { inputs } => instructions::block(self, inputs, instruction),
+            Operation::If {
+                inputs,
+                false_target,
+            } => instructions::r#if(self, inputs, *false_target, instruction),
+            Operation::Else { end_index } => instructions::r#else(self, *end_index),
+            Operation::End { outputs } => instructions::end(self, outputs, instruction),
+            Operation::BrIf {
+                relative_depth,
+                end_index,
+                outputs,
+            } => instructions::br_if(self, *relative_depth, *end_index, outputs, instruction),
+
+            Operation::ListLift {
+                element_type,
+                end_index,
+            } => instructions::list_lift(self, element_type, *end_index, instruction),
+            Operation::ForEach {
+                element_type,
+                end_index,
+            } => instructions::for_each(self, element_type, *end_index, instruction),
+            Operation::IterationEnd {
+                input_types,
+                element_type,
+                body_index,
+            } => instructions::iteration_end(
+                self,
+                input_types,
+                element_type.as_ref(),
+                *body_index,
+                instruction,
+            ),
+        }
+    }
+}
+
+/// Represents a block (`block`, `if`, `list.lift` or `for-each`)
+/// found while compiling a set of instructions.
+struct CompiledBlock {
+    /// The types of the values left by the block.
+    outputs: Vec<InterfaceType>,
+
+    /// The index of the instruction starting the block.
+    start_index: usize,
+
+    /// The index of the `else` instruction, if any.
+    else_index: Option<usize>,
+
+    /// The index of the `end` instruction.
+    end_index: usize,
+}
+
+/// Returns, for each instruction, whether it is outside of any block
+/// (`block`, `if`, `list.lift` or `for-each`). The structure of the
+/// blocks is validated like when compiling.
+pub(super) fn outside_blocks(instructions: &[Instruction]) -> InstructionResult<Vec<bool>> {
+    let (blocks, _) = resolve_blocks(instructions)?;
+    let mut outside = vec![true; instructions.len()];
+
+    for block in &blocks {
+        for flag in &mut outside[block.start_index..=block.end_index] {
+            *flag = false;
+        }
+    }
+
+    Ok(outside)
+}
+
+/// Validates the structure of the blocks, the same way WebAssembly
+/// core validates them: blocks must be closed by `end`, `else` must
+/// belong to an `if` block, and branches must target an enclosing
+/// block.
+///
+/// The types of the values are not validated here, since the
+/// signatures of the functions and adapters called by the
+/// instructions may only be known at runtime. They are checked when
+/// the blocks are entered and left instead, and the instructions of a
+/// block can't consume the values below it, see the floor of
+/// [`Stack`](super::stack::Stack).
+///
+/// It returns the blocks, and for each instruction, the index of the
+/// block it belongs to (or the block it targets for a branch), if
+/// any.
+fn resolve_blocks(
+    instructions: &[Instruction],
+) -> InstructionResult<(Vec<CompiledBlock>, Vec<Option<usize>>)> {
+    let mut blocks: Vec<CompiledBlock> = Vec::new();
+    let mut open_blocks: Vec<usize> = Vec::new();
+    let mut owners = vec![None; instructions.len()];
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        match instruction {
+            Instruction::Block { .. }
+            | Instruction::If { .. }
+            | Instruction::ListLift { .. }
+            | Instruction::ForEach { .. } => {
+                let outputs = match instruction {
+                    Instruction::Block { block_type } | Instruction::If { block_type } => {
+                        block_type.outputs.clone()
+                    }
+
+                    // Each iteration of `list.lift` leaves one element.
+                    Instruction::ListLift { element_type } => vec![element_type.clone()],
+
+                    _ => vec![],
+                };
+
+                owners[index] = Some(blocks.len());
+                open_blocks.push(blocks.len());
+                blocks.push(CompiledBlock {
+                    outputs,
+                    start_index: index,
+                    else_index: None,
+                    end_index: index,
+                });
+            }
+
+            Instruction::Else => {
+                let block_index = open_blocks
+                    .last()
+                    .copied()
+                    .filter(|&block_index| {
+                        let block = &blocks[block_index];
+
+                        matches!(instructions[block.start_index], Instruction::If { .. })
+                            && block.else_index.is_none()
+                    })
+                    .ok_or_else(|| {
+                        InstructionError::new(
+                            instruction.clone(),
+                            InstructionErrorKind::ElseWithoutIf,
+                        )
+                        .at_offset(index)
+                    })?;
+
+                owners[index] = Some(block_index);
+                blocks[block_index].else_index = Some(index);
+            }
+
+            Instruction::End => {
+                let block_index = open_blocks.pop().ok_or_else(|| {
+                    InstructionError::new(
+                        instruction.clone(),
+                        InstructionErrorKind::EndWithoutBlock,
+                    )
+                    .at_offset(index)
+                })?;
+                let block = &mut blocks[block_index];
+                let start_instruction = &instructions[block.start_index];
+
+                // Like in WebAssembly core, an `if` without `else` must
+                // leave the stack as it was, since the `else` branch is
+                // implicitly empty.
+                if let Instruction::If { block_type } = start_instruction {
+                    if block.else_index.is_none() && block_type.inputs != block_type.outputs {
+                        return Err(InstructionError::new(
+                            start_instruction.clone(),
+                            InstructionErrorKind::IfWithoutElseSignatureMismatch {
+                                inputs: block_type.inputs.clone(),
+                                outputs: block_type.outputs.clone(),
+                            },
+                        )
+                        .at_offset(block.start_index));
+                    }
+                }
+
+                owners[index] = Some(block_index);
+                block.end_index = index;
+            }
+
+            Instruction::BrIf { relative_depth } => {
+                let block_index = open_blocks
+                    .len()
+                    .checked_sub(*relative_depth as usize + 1)
+                    .map(|nth| open_blocks[nth])
+                    .ok_or_else(|| {
+                        InstructionError::new(
+                            instruction.clone(),
+                            InstructionErrorKind::InvalidBranchDepth {
+                                relative_depth: *relative_depth,
+                            },
+                        )
+                        .at_offset(index)
+                    })?;
+
+                owners[index] = Some(block_index);
+            }
+
+            _ => (),
+        }
+    }
+
+    if let Some(&block_index) = open_blocks.last() {
+        let start_index = blocks[block_index].start_index;
+
+        return Err(InstructionError::new(
+            instructions[start_index].clone(),
+            InstructionErrorKind::BlockIsNotClosed,
+        )
+        .at_offset(start_index));
+    }
+
+    Ok((blocks, owners))
+}
+
+/// Compiles a set of instructions into a program. The structure of
+/// the blocks is validated, and the operands of the operations, like
+/// the jump targets of the control flow instructions, are resolved
+/// here.
+pub(crate) fn compile(instructions: &[Instruction]) -> InstructionResult<Program> {
+    let (blocks, owners) = resolve_blocks(instructions)?;
+    let block_of = |position: usize| -> &CompiledBlock {
+        &blocks[owners[position].expect("A control flow instruction always belongs to a block.")]
+    };
+
+    let operations = instructions
+        .iter()
+        .enumerate()
+        .map(|(position, instruction)| match instruction {
+            Instruction::ArgumentGet { index } => Operation::ArgumentGet { index: *index },
+            Instruction::CallCore { function_index } => Operation::CallCore {
+                function_index: *function_index,
+            },
+
+            Instruction::S8FromI32 => Operation::S8FromI32,
+            Instruction::S8FromI64 => Operation::S8FromI64,
+            Instruction::S16FromI32 => Operation::S16FromI32,
+            Instruction::S16FromI64 => Operation::S16FromI64,
+            Instruction::S32FromI32 => Operation::S32FromI32,
+            Instruction::S32FromI64 => Operation::S32FromI64,
+            Instruction::S64FromI32 => Operation::S64FromI32,
+            Instruction::S64FromI64 => Operation::S64FromI64,
+            Instruction::I32FromS8 => Operation::I32FromS8,
+            Instruction::I32FromS16 => Operation::I32FromS16,
+            Instruction::I32FromS32 => Operation::I32FromS32,
+            Instruction::I32FromS64 => Operation::I32FromS64,
+            Instruction::I64FromS8 => Operation::I64FromS8,
+            Instruction::I64FromS16 => Operation::I64FromS16,
+            Instruction::I64FromS32 => Operation::I64FromS32,
+            Instruction::I64FromS64 => Operation::I64FromS64,
+            Instruction::U8FromI32 => Operation::U8FromI32,
+            Instruction::U8FromI64 => Operation::U8FromI64,
+            Instruction::U16FromI32 => Operation::U16FromI32,
+            Instruction::U16FromI64 => Operation::U16FromI64,
+            Instruction::U32FromI32 => Operation::U32FromI32,
+            Instruction::U32FromI64 => Operation::U32FromI64,
+            Instruction::U64FromI32 => Operation::U64FromI32,
+            Instruction::U64FromI64 => Operation::U64FromI64,
+            Instruction::I32FromU8 => Operation::I32FromU8,
+            Instruction::I32FromU16 => Operation::I32FromU16,
+            Instruction::I32FromU32 => Operation::I32FromU32,
+            Instruction::I32FromU64 => Operation::I32FromU64,
+            Instruction::I64FromU8 => Operation::I64FromU8,
+            Instruction::I64FromU16 => Operation::I64FromU16,
+            Instruction::I64FromU32 => Operation::I64FromU32,
+            Instruction::I64FromU64 => Operation::I64FromU64,
+
+            Instruction::StringLiftMemory => Operation::StringLiftMemory,
+            Instruction::StringLowerMemory => Operation::StringLowerMemory,
+            Instruction::StringSize => Operation::StringSize,
+
+            Instruction::RecordLift { type_index } => Operation::RecordLift {
+                type_index: *type_index,
+            },
+            Instruction::RecordLower { type_index } => Operation::RecordLower {
+                type_index: *type_index,
+            },
+
+            Instruction::CallAdapter { adapter_index } => Operation::CallAdapter {
+                adapter_index: *adapter_index,
+            },
+
+            Instruction::CallExport { export_name } => Operation::CallExport {
+                export_name: export_name.clone(),
+            },
+
+            Instruction::DeferCallCore { function_index } => Operation::DeferCallCore {
+                function_index: *function_index,
+            },
+
+            Instruction::Block { block_type } => Operation::Block {
+                inputs: block_type.inputs.clone(),
+            },
+            Instruction::If { block_type } => {
+                let block = block_of(position);
+
+                // When the condition is false, the execution jumps
+                // after `else` if any, otherwise to `end`.
+                let false_target = block
+                    .else_index
+                    .map(|else_index| else_index + 1)
+                    .unwrap_or(block.end_index);
+
+                Operation::If {
+                    inputs: block_type.inputs.clone(),
+                    false_target,
+                }
+            }
+            Instruction::Else => Operation::Else {
+                end_index: block_of(position).end_index,
+            },
+            Instruction::End => {
+                let block = block_of(position);
+
+                match &instructions[block.start_index] {
+                    Instruction::ListLift { element_type } => Operation::IterationEnd {
+                        input_types: vec![InterfaceType::I32],
+                        element_type: Some(element_type.clone()),
+                        body_index: block.start_index + 1,
+                    },
+                    Instruction::ForEach { element_type } => Operation::IterationEnd {
+                        input_types: vec![element_type.clone()],
+                        element_type: None,
+                        body_index: block.start_index + 1,
+                    },
+                    _ => Operation::End {
+                        outputs: block.outputs.clone(),
+                    },
+                }
+            }
+            Instruction::BrIf { relative_depth } => {
+                let block = block_of(position);
+
+                Operation::BrIf {
+                    relative_depth: *relative_depth,
+                    end_index: block.end_index,
+                    outputs: block.outputs.clone(),
+                }
+            }
+
+            Instruction::ListLift { element_type } => Operation::ListLift {
+                element_type: element_type.clone(),
+                end_index: block_of(position).end_index,
+            },
+            Instruction::ForEach { element_type } => Operation::ForEach {
+                element_type: element_type.clone(),
+                end_index: block_of(position).end_index,
+            },
+        })
+        .collect();
+
+    Ok(Program {
+        instructions: instructions.to_vec(),
+        operations,
+    })
+}
+
+/// Resolves the types and the functions used by a program against a
+/// WebAssembly instance, so that they are not looked up anymore when
+/// the program is executed: the `call-core`, `record.lift`,
+/// `record.lower` and `call-adapter` operations are replaced by their
+/// resolved variants.
+///
+/// The adapters called by the program are compiled and resolved too,
+/// once per adapter, and are collected in `compiled_adapters`.
+pub(crate) fn resolve<Instance, Export, LocalImport, Memory, MemoryView>(
+    program: &mut Program,
+    wasm_instance: &mut Instance,
+    compiled_adapters: &mut Vec<CompiledAdapter>,
+) -> InstructionResult<()>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    let Program {
+        instructions,
+        operations,
+    } = program;
+
+    for (offset, (operation, instruction)) in
+        operations.iter_mut().zip(instructions.iter()).enumerate()
+    {
+        *operation = match operation {
+            Operation::CallCore { function_index } => {
+                let function_index = *function_index;
+                let local_or_import = wasm_instance
+                    .local_or_import(FunctionIndex::new(function_index as usize))
+                    .ok_or_else(|| {
+                        InstructionError::new(
+                            instruction.clone(),
+                            InstructionErrorKind::LocalOrImportIsMissing { function_index },
+                        )
+                        .at_offset(offset)
+                    })?;
+
+                Operation::ResolvedCallCore {
+                    function_index,
+                    inputs: local_or_import.inputs().to_vec(),
+                    outputs: local_or_import.outputs().to_vec(),
+                }
+            }
+
+            Operation::RecordLift { type_index } => Operation::ResolvedRecordLift {
+                record_type: instructions::resolve_record_type(
+                    &*wasm_instance,
+                    *type_index,
+                    instruction,
+                )
+                .map_err(|error| error.at_offset(offset))?
+                .clone(),
+            },
+
+            Operation::RecordLower { type_index } => Operation::ResolvedRecordLower {
+                record_type: instructions::resolve_record_type(
+                    &*wasm_instance,
+                    *type_index,
+                    instruction,
+                )
+                .map_err(|error| error.at_offset(offset))?
+                .clone(),
+            },
+
+            Operation::CallAdapter { adapter_index } => {
+                let adapter_index = *adapter_index;
+                let compiled_index =
+                    match compiled_adapters.iter().position(|compiled_adapter| {
+                        compiled_adapter.adapter_index == adapter_index
+                    }) {
+                        Some(compiled_index) => compiled_index,
+
+                        None => {
+                            let (inputs, outputs, adapter_instructions) =
+                                instructions::resolve_adapter(
+                                    &*wasm_instance,
+                                    adapter_index,
+                                    instruction,
+                                )
+                                .map_err(|error| error.at_offset(offset))?;
+                            let inputs = inputs.to_vec();
+                            let outputs = outputs.to_vec();
+                            let mut adapter_program =
+                                compile(adapter_instructions).map_err(|error| {
+                                    instructions::describe_adapter(
+                                        &*wasm_instance,
+                                        adapter_index,
+                                        error,
+                                    )
+                                    .at_offset(offset)
+                                })?;
+
+                            // The adapter is registered before being
+                            // resolved, so that an adapter calling itself
+                            // is compiled only once.
+                            let compiled_index = compiled_adapters.len();
+                            compiled_adapters.push(CompiledAdapter {
+                                adapter_index,
+                                inputs,
+                                outputs,
+                                program: Program::default(),
+                            });
+
+                            resolve(&mut adapter_program, wasm_instance, compiled_adapters)
+                                .map_err(|error| {
+                                    instructions::describe_adapter(
+                                        &*wasm_instance,
+                                        adapter_index,
+                                        error,
+                                    )
+                                    .at_offset(offset)
+                                })?;
+                            compiled_adapters[compiled_index].program = adapter_program;
+
+                            compiled_index
+                        }
+                    };
+
+                Operation::ResolvedCallAdapter {
+                    adapter_index,
+                    compiled_index,
+                }
+            }
+
+            _ => continue,
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Adapter, Type},
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            BlockType, Instruction, Interpreter,
+        },
+        values::InterfaceValue,
+    };
+    use std::convert::TryInto;
+
+    type TestInterpreter = Interpreter<Instance, Export, LocalImport, Memory, MemoryView>;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_resolve__missing_function_is_detected_ahead_of_time() {
+        let instructions = vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::If {
+                block_type: BlockType::default(),
+            },
+            Instruction::CallCore { function_index: 7 },
+            Instruction::End,
+        ];
+
+        // The `call-core` instruction is never executed by the
+        // interpreter that isn't resolved.
+        let interpreter: TestInterpreter = (&instructions).try_into().unwrap();
+
+        assert!(interpreter
+            .run(&[InterfaceValue::I32(0)], &mut Instance::new())
+            .is_ok());
+
+        let error = TestInterpreter::with_instance(&instructions, &mut Instance::new())
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "`call-core 7` the local or import function `7` doesn't exist\n  at instruction 2"
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_resolve__adapters_are_compiled_once() {
+        let mut instance = Instance::new();
+        instance.wit_types.push(Type::Function {
+            inputs: vec![],
+            outputs: vec![],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![Instruction::CallAdapter { adapter_index: 1 }],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![Instruction::CallAdapter { adapter_index: 0 }],
+        });
+
+        let interpreter = TestInterpreter::with_instance(
+            &[
+                Instruction::CallAdapter { adapter_index: 0 },
+                Instruction::CallAdapter { adapter_index: 1 },
+            ],
+            &mut instance,
+        )
+        .unwrap();
+
+        assert_eq!(interpreter.compiled_adapters.len(), 2);
+    }
+}
@@ -0,0 +1,212 @@
+//! An execution context holds the buffers used by the interpreter
+//! when it runs, so that they can be reused from one run to another.
+
+use super::{bytecode::CompiledAdapter, stack::Stack, DeferredCall, Iteration};
+use crate::values::InterfaceValue;
+use std::{collections::HashMap, sync::Arc};
+
+/// Represents the buffers used by an interpreter when it runs: the
+/// stack, the scratch buffers of the call arguments, the deferred
+/// calls, the blocks and the iterations, the buffers of the adapters
+/// called with the `call-adapter` instruction, and the adapters
+/// compiled by the unresolved `call-adapter` instructions.
+///
+/// A context can be kept, for instance per thread, and given to
+/// [`Interpreter::run_into`](super::Interpreter::run_into) or
+/// [`Interpreter::run_async_into`](super::Interpreter::run_async_into)
+/// for repeated runs. The buffers keep their capacity between the runs,
+/// so that the steady-state runs allocate nothing but the strings,
+/// the records and the lists (and their types, when they are
+/// checked), and the vectors of outputs returned by the called
+/// functions. A context can be used with any interpreter: an adapter
+/// compiled by a previous run is reused only if it is identical to
+/// the adapter of the instance.
+#[derive(Default)]
+pub struct ExecutionContext {
+    /// The stack of the runtime.
+    pub(super) stack: Stack<InterfaceValue>,
+
+    /// The arguments of the call being executed.
+    pub(super) arguments: Vec<InterfaceValue>,
+
+    /// The calls registered by the `defer-call-core` instruction.
+    pub(super) deferred_calls: Vec<DeferredCall>,
+
+    /// The arguments of the deferred calls, one after the other.
+    pub(super) deferred_arguments: Vec<InterfaceValue>,
+
+    /// The heights of the stack when the blocks have been entered.
+    pub(super) block_heights: Vec<usize>,
+
+    /// The iterations of the `list.lift` and `for-each` instructions.
+    pub(super) iterations: Vec<Iteration>,
+
+    /// The invocation inputs, when the context is used by an adapter
+    /// called with the `call-adapter` instruction.
+    pub(super) inputs: Vec<InterfaceValue>,
+
+    /// The contexts of the adapters called with the `call-adapter`
+    /// instruction, one per nesting level.
+    pub(super) nested_contexts: Vec<ExecutionContext>,
+
+    /// The adapters compiled by the unresolved `call-adapter`
+    /// instructions, by adapter index.
+    pub(super) called_adapters: HashMap<u32, Arc<CompiledAdapter>>,
+}
+
+impl ExecutionContext {
+    /// Creates a new context, with empty buffers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new context, whose stack can hold at least
+    /// `stack_capacity` values without reallocating.
+    pub fn with_stack_capacity(stack_capacity: usize) -> Self {
+        Self {
+            stack: Stack::with_capacity(stack_capacity),
+            ..Default::default()
+        }
+    }
+
+    /// Empties the buffers, while keeping their capacity. A previous
+    /// run may have failed and left values in them.
+    pub(super) fn clear(&mut self) {
+        self.stack.clear();
+        self.arguments.clear();
+        self.deferred_calls.clear();
+        self.deferred_arguments.clear();
+        self.block_heights.clear();
+        self.iterations.clear();
+        self.inputs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecutionContext;
+    use crate::{
+        ast::{Adapter, Type},
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            stack::Stackable,
+            Instruction, Interpreter,
+        },
+        types::InterfaceType,
+        values::InterfaceValue,
+    };
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_run_into() {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallCore { function_index: 42 },
+        ])
+            .try_into()
+            .unwrap();
+        let mut instance = Instance::new();
+        let mut context = ExecutionContext::with_stack_capacity(4);
+        let mut outputs = Vec::with_capacity(1);
+        let outputs_pointer = outputs.as_ptr();
+
+        for (a, b) in &[(3, 4), (5, 6)] {
+            interpreter
+                .run_into(
+                    &[InterfaceValue::I32(*a), InterfaceValue::I32(*b)],
+                    &mut instance,
+                    &mut context,
+                    &mut outputs,
+                )
+                .unwrap();
+
+            assert_eq!(outputs, vec![InterfaceValue::I32(a * b)]);
+        }
+
+        // The buffers have been reused.
+        assert_eq!(outputs.as_ptr(), outputs_pointer);
+        assert!(context.stack.is_empty());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_run_into__after_a_failure() {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+        ])
+            .try_into()
+            .unwrap();
+        let mut instance = Instance::new();
+        let mut context = ExecutionContext::new();
+        let mut outputs = Vec::new();
+
+        // The second argument is missing: the first one is left on the
+        // stack of the context.
+        assert!(interpreter
+            .run_into(
+                &[InterfaceValue::I32(1)],
+                &mut instance,
+                &mut context,
+                &mut outputs,
+            )
+            .is_err());
+
+        interpreter
+            .run_into(
+                &[InterfaceValue::I32(2), InterfaceValue::I32(3)],
+                &mut instance,
+                &mut context,
+                &mut outputs,
+            )
+            .unwrap();
+
+        assert_eq!(
+            outputs,
+            vec![InterfaceValue::I32(2), InterfaceValue::I32(3)]
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_run_into__with_another_adapter() {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallAdapter { adapter_index: 0 },
+        ])
+            .try_into()
+            .unwrap();
+        let instance_with_adapter = |index| {
+            let mut instance = Instance::new();
+            instance.wit_types.push(Type::Function {
+                inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                outputs: vec![InterfaceType::I32],
+            });
+            instance.wit_adapters.push(Adapter {
+                function_type: 1,
+                instructions: vec![Instruction::ArgumentGet { index }],
+            });
+
+            instance
+        };
+        let mut context = ExecutionContext::new();
+        let mut outputs = Vec::new();
+
+        // The adapter compiled by the first run is kept by the
+        // context, but the second instance has another adapter.
+        for index in 0..2 {
+            interpreter
+                .run_into(
+                    &[InterfaceValue::I32(3), InterfaceValue::I32(4)],
+                    &mut instance_with_adapter(index),
+                    &mut context,
+                    &mut outputs,
+                )
+                .unwrap();
+
+            assert_eq!(outputs, vec![InterfaceValue::I32(3 + index as i32)]);
+        }
+    }
+}
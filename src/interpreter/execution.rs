@@ -0,0 +1,343 @@
+//! An execution is a run of an interpreter that can be paused and
+//! resumed, instruction after instruction, to debug adapters.
+
+use super::{
+    bytecode::Program,
+    signature::Signature,
+    stack::{Stack, Stackable},
+    wasm, ExecutionContext, Instruction, Interpreter, Runtime,
+};
+use crate::{errors::InterpreterResult, values::InterfaceValue};
+use std::collections::BTreeSet;
+
+/// Represents the state of an execution after a step.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ExecutionState {
+    /// The execution is paused before the instruction at the given
+    /// index.
+    Paused(usize),
+
+    /// The execution has reached a breakpoint, and is paused before
+    /// the instruction at the given index.
+    Breakpoint(usize),
+
+    /// The execution is finished: all the instructions and the
+    /// deferred calls have been executed, or an instruction has
+    /// failed.
+    Finished,
+}
+
+/// Represents a resumable run of an interpreter, created with
+/// [`Interpreter::execution`].
+///
+/// The instructions are executed one by one with
+/// [`Execution::step`], or until the next breakpoint with
+/// [`Execution::resume`]. Between two steps, the stack can be
+/// inspected and modified. The `call-adapter` instruction is a single
+/// step: the called adapter is executed entirely.
+///
+/// The limits of the interpreter apply to the execution, as for
+/// [`Interpreter::run`]. The deferred calls are executed when the
+/// execution finishes, whether it succeeds or fails.
+pub struct Execution<'execution, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export + 'execution,
+    LocalImport: wasm::structures::LocalImport + 'execution,
+    Memory: wasm::structures::Memory<MemoryView> + 'execution,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView> + 'execution,
+{
+    /// The program being executed.
+    program: &'execution Program,
+
+    /// The runtime, holding the state of the execution between the
+    /// steps.
+    runtime: Runtime<'execution, 'execution, Instance, Export, LocalImport, Memory, MemoryView>,
+
+    /// The signature of the adapter, if known. The values left on the
+    /// stack are checked against it when the execution finishes.
+    signature: Option<&'execution Signature>,
+
+    /// The indices of the instructions the execution pauses before,
+    /// when it is resumed.
+    breakpoints: BTreeSet<usize>,
+
+    /// Whether the execution is finished.
+    finished: bool,
+}
+
+impl<'execution, Instance, Export, LocalImport, Memory, MemoryView>
+    Execution<'execution, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Creates a new execution of an interpreter, paused before its
+    /// first instruction.
+    pub(super) fn new(
+        interpreter: &'execution Interpreter<Instance, Export, LocalImport, Memory, MemoryView>,
+        invocation_inputs: &'execution [InterfaceValue],
+        wasm_instance: &'execution mut Instance,
+    ) -> Self {
+        Self {
+            program: &interpreter.program,
+            runtime: interpreter.runtime(
+                invocation_inputs,
+                wasm_instance,
+                &mut ExecutionContext::new(),
+                None,
+            ),
+            signature: interpreter.signature.as_ref(),
+            breakpoints: BTreeSet::new(),
+            finished: false,
+        }
+    }
+
+    /// Returns the state of the execution.
+    pub fn state(&self) -> ExecutionState {
+        if self.finished {
+            ExecutionState::Finished
+        } else {
+            ExecutionState::Paused(self.runtime.program_counter)
+        }
+    }
+
+    /// Executes the next instruction, and returns the new state of the
+    /// execution. When the last instruction has been executed, the
+    /// deferred calls are executed and the execution is finished.
+    ///
+    /// If the instruction fails, the deferred calls are executed, the
+    /// execution is finished and the error is returned. Stepping a
+    /// finished execution does nothing.
+    pub fn step(&mut self) -> InterpreterResult<ExecutionState> {
+        if self.finished {
+            return Ok(ExecutionState::Finished);
+        }
+
+        match self.runtime.step(self.program) {
+            Ok(true) if self.runtime.program_counter < self.program.len() => {
+                Ok(ExecutionState::Paused(self.runtime.program_counter))
+            }
+            Ok(_) => self.finish(Ok(())),
+            Err(error) => self.finish(Err(error.into())),
+        }
+    }
+
+    /// Executes the instructions until the execution reaches a
+    /// breakpoint or finishes. At least one instruction is executed,
+    /// so that an execution paused on a breakpoint can be resumed.
+    #[doc(alias = "continue")]
+    pub fn resume(&mut self) -> InterpreterResult<ExecutionState> {
+        loop {
+            match self.step()? {
+                ExecutionState::Paused(index) if self.breakpoints.contains(&index) => {
+                    return Ok(ExecutionState::Breakpoint(index))
+                }
+                ExecutionState::Paused(_) => continue,
+                state => return Ok(state),
+            }
+        }
+    }
+
+    /// Adds a breakpoint before the instruction at the given index. It
+    /// returns `false` if the breakpoint was already present.
+    pub fn add_breakpoint(&mut self, index: usize) -> bool {
+        self.breakpoints.insert(index)
+    }
+
+    /// Removes the breakpoint before the instruction at the given
+    /// index. It returns `false` if there was no such breakpoint.
+    pub fn remove_breakpoint(&mut self, index: usize) -> bool {
+        self.breakpoints.remove(&index)
+    }
+
+    /// Returns the indices of the breakpoints, in ascending order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Returns the next instruction to execute, if the execution
+    /// isn't finished.
+    pub fn next_instruction(&self) -> Option<&Instruction> {
+        if self.finished {
+            None
+        } else {
+            self.program.instruction(self.runtime.program_counter)
+        }
+    }
+
+    /// Returns the stack of the execution.
+    pub fn stack(&self) -> &Stack<InterfaceValue> {
+        &self.runtime.stack
+    }
+
+    /// Returns the stack of the execution, to modify it before the
+    /// next step.
+    pub fn stack_mut(&mut self) -> &mut Stack<InterfaceValue> {
+        &mut self.runtime.stack
+    }
+
+    /// Consumes the execution, and returns its stack.
+    pub fn into_stack(self) -> Stack<InterfaceValue> {
+        self.runtime.stack
+    }
+
+    /// Executes the deferred calls and marks the execution as
+    /// finished. The error of the instruction, if any, takes
+    /// precedence over the errors of the deferred calls. Then, the
+    /// values left on the stack are checked against the signature.
+    fn finish(&mut self, result: InterpreterResult<()>) -> InterpreterResult<ExecutionState> {
+        self.finished = true;
+
+        let deferred_result = self.runtime.execute_deferred_calls();
+
+        result.and(deferred_result.map_err(Into::into))?;
+
+        if let Some(signature) = self.signature {
+            signature.check_outputs(self.runtime.stack.as_slice())?;
+        }
+
+        Ok(ExecutionState::Finished)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecutionState;
+    use crate::{
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            stack::Stackable,
+            Instruction, Interpreter,
+        },
+        values::InterfaceValue,
+    };
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_step() {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::CallCore { function_index: 42 },
+        ])
+            .try_into()
+            .unwrap();
+        let invocation_inputs = vec![InterfaceValue::I32(3), InterfaceValue::I32(4)];
+        let mut instance = Instance::new();
+        let mut execution = interpreter
+            .execution(&invocation_inputs, &mut instance)
+            .unwrap();
+
+        assert_eq!(execution.state(), ExecutionState::Paused(0));
+        assert_eq!(
+            execution.next_instruction(),
+            Some(&Instruction::ArgumentGet { index: 1 })
+        );
+
+        assert_eq!(execution.step().unwrap(), ExecutionState::Paused(1));
+        assert_eq!(execution.stack().as_slice(), &[InterfaceValue::I32(4)]);
+
+        assert_eq!(execution.step().unwrap(), ExecutionState::Paused(2));
+        assert_eq!(
+            execution.stack().as_slice(),
+            &[InterfaceValue::I32(4), InterfaceValue::I32(3)]
+        );
+
+        assert_eq!(execution.step().unwrap(), ExecutionState::Finished);
+        assert_eq!(execution.next_instruction(), None);
+        assert_eq!(execution.step().unwrap(), ExecutionState::Finished);
+        assert_eq!(
+            execution.into_stack().as_slice(),
+            &[InterfaceValue::I32(12)]
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 0 },
+        ])
+            .try_into()
+            .unwrap();
+        let invocation_inputs = vec![InterfaceValue::I32(7)];
+        let mut instance = Instance::new();
+        let mut execution = interpreter
+            .execution(&invocation_inputs, &mut instance)
+            .unwrap();
+
+        assert!(execution.add_breakpoint(3));
+        assert!(execution.add_breakpoint(1));
+        assert!(!execution.add_breakpoint(1));
+        assert_eq!(execution.breakpoints().collect::<Vec<_>>(), vec![1, 3]);
+
+        assert_eq!(execution.resume().unwrap(), ExecutionState::Breakpoint(1));
+        assert_eq!(execution.stack().as_slice().len(), 1);
+
+        assert_eq!(execution.resume().unwrap(), ExecutionState::Breakpoint(3));
+        assert_eq!(execution.stack().as_slice().len(), 3);
+
+        assert!(execution.remove_breakpoint(3));
+        assert!(!execution.remove_breakpoint(3));
+
+        assert_eq!(execution.resume().unwrap(), ExecutionState::Finished);
+        assert_eq!(execution.stack().as_slice().len(), 4);
+    }
+
+    #[test]
+    fn test_stack_modification() {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::CallCore { function_index: 42 },
+        ])
+            .try_into()
+            .unwrap();
+        let invocation_inputs = vec![InterfaceValue::I32(3), InterfaceValue::I32(4)];
+        let mut instance = Instance::new();
+        let mut execution = interpreter
+            .execution(&invocation_inputs, &mut instance)
+            .unwrap();
+
+        execution.add_breakpoint(2);
+
+        assert_eq!(execution.resume().unwrap(), ExecutionState::Breakpoint(2));
+        assert_eq!(execution.stack_mut().pop1(), Some(InterfaceValue::I32(3)));
+        execution.stack_mut().push(InterfaceValue::I32(5));
+
+        assert_eq!(execution.resume().unwrap(), ExecutionState::Finished);
+        assert_eq!(execution.stack().as_slice(), &[InterfaceValue::I32(20)]);
+    }
+
+    #[test]
+    fn test_step_fails() {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+        ])
+            .try_into()
+            .unwrap();
+        let invocation_inputs = vec![InterfaceValue::I32(3)];
+        let mut instance = Instance::new();
+        let mut execution = interpreter
+            .execution(&invocation_inputs, &mut instance)
+            .unwrap();
+
+        assert_eq!(execution.step().unwrap(), ExecutionState::Paused(1));
+
+        let error = execution.step().unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "`arg.get 1` cannot access invocation inputs #1 because it doesn't exist\n  at instruction 1"
+        );
+        assert_eq!(execution.state(), ExecutionState::Finished);
+        assert_eq!(execution.step().unwrap(), ExecutionState::Finished);
+    }
+}
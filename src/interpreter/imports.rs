@@ -0,0 +1,321 @@
+//! Host imports are Rust functions exposed to the guest through the
+//! import adapters of an interface module.
+
+use super::{
+    bytecode::outside_blocks, stack::Stackable, wasm, ExecutionContext, Instruction, Interpreter,
+    IntoNativeFunction, NativeFunction,
+};
+use crate::{
+    errors::{HostError, InstructionResult, InterpreterError, ModuleError, ModuleResult},
+    types::InterfaceType,
+    values::InterfaceValue,
+};
+use std::{collections::HashMap, fmt};
+
+/// A host function, called with interface values and returning
+/// interface values.
+pub type HostFunction =
+    Box<dyn Fn(&[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> + Send + Sync>;
+
+/// Represents a registry of host functions, keyed by the namespace
+/// and the name of the WIT imports they implement.
+///
+/// ```rust
+/// use wasmer_interface_types::{interpreter::HostImports, values::InterfaceValue};
+///
+/// let mut host_imports = HostImports::new();
+/// host_imports.register("env", "double", |arguments| match arguments {
+///     [InterfaceValue::S32(value)] => Ok(vec![InterfaceValue::S32(value * 2)]),
+///     _ => Err("`double` expects one `s32`".into()),
+/// });
+///
+/// assert!(host_imports.contains("env", "double"));
+/// ```
+#[derive(Default)]
+pub struct HostImports {
+    /// The host functions, by namespace and name.
+    functions: HashMap<(String, String), HostFunction>,
+}
+
+impl HostImports {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the host function implementing the import
+    /// `namespace`.`name`. A function previously registered for this
+    /// import is replaced.
+    pub fn register<F>(&mut self, namespace: &str, name: &str, function: F) -> &mut Self
+    where
+        F: Fn(&[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> + Send + Sync + 'static,
+    {
+        self.functions.insert(
+            (namespace.to_string(), name.to_string()),
+            Box::new(function),
+        );
+
+        self
+    }
+
+    /// Registers a Rust closure with native arguments and results as
+    /// the host function implementing the import
+    /// `namespace`.`name`, see [`NativeFunction`].
+    pub fn register_native<Function, Signature>(
+        &mut self,
+        namespace: &str,
+        name: &str,
+        function: Function,
+    ) -> &mut Self
+    where
+        Function: IntoNativeFunction<Signature>,
+    {
+        self.functions.insert(
+            (namespace.to_string(), name.to_string()),
+            NativeFunction::new(function).into_host_function(),
+        );
+
+        self
+    }
+
+    /// Checks whether a host function implements the import
+    /// `namespace`.`name`.
+    pub fn contains(&self, namespace: &str, name: &str) -> bool {
+        self.functions
+            .contains_key(&(namespace.to_string(), name.to_string()))
+    }
+
+    /// Returns the namespaces and names of the registered imports, in
+    /// no particular order.
+    pub(crate) fn names(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.functions
+            .keys()
+            .map(|(namespace, name)| (namespace.as_str(), name.as_str()))
+    }
+
+    /// Removes the host function implementing the import
+    /// `namespace`.`name`, and returns it, if any.
+    pub(crate) fn remove(&mut self, namespace: &str, name: &str) -> Option<HostFunction> {
+        self.functions
+            .remove(&(namespace.to_string(), name.to_string()))
+    }
+}
+
+impl fmt::Debug for HostImports {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_set()
+            .entries(self.functions.keys())
+            .finish()
+    }
+}
+
+/// Represents an import adapter, split around the `call-core`
+/// instruction calling the import: the instructions before it lift
+/// the core arguments into the inputs of the host function, and the
+/// instructions after it lower the outputs of the host function into
+/// core results.
+pub(crate) struct ImportAdapter<Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// The import namespace.
+    pub(crate) namespace: String,
+
+    /// The import name.
+    pub(crate) name: String,
+
+    /// The core inputs of the adapter.
+    pub(crate) inputs: Vec<InterfaceType>,
+
+    /// The core outputs of the adapter.
+    pub(crate) outputs: Vec<InterfaceType>,
+
+    /// The outputs of the host function.
+    pub(crate) host_outputs: Vec<InterfaceType>,
+
+    /// The instructions lifting the core arguments.
+    pub(crate) lift: Interpreter<Instance, Export, LocalImport, Memory, MemoryView>,
+
+    /// The instructions lowering the outputs of the host function.
+    pub(crate) lower: Interpreter<Instance, Export, LocalImport, Memory, MemoryView>,
+
+    /// The host function.
+    pub(crate) function: HostFunction,
+}
+
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    ImportAdapter<Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Calls the import adapter with core arguments: the arguments
+    /// are lifted, the host function is called, and its outputs are
+    /// lowered into core results.
+    ///
+    /// Both halves are executed by the same runtime, so that the
+    /// calls deferred by the lifting instructions stay pending until
+    /// the lowering instructions finish, like for any adapter. They
+    /// are executed even if the host function fails.
+    pub(crate) fn call(
+        &self,
+        arguments: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+    ) -> ModuleResult<Vec<InterfaceValue>> {
+        if let Some(signature) = &self.lift.signature {
+            signature.check_inputs(arguments)?;
+        }
+
+        let mut context = ExecutionContext::new();
+        let mut runtime = self
+            .lift
+            .runtime(arguments, wasm_instance, &mut context, None);
+
+        let host_outputs = runtime
+            .execute_operations(&self.lift.program)
+            .map_err(Into::into)
+            .and_then(|()| match &self.lift.signature {
+                Some(signature) => signature.check_outputs(runtime.stack.as_slice()),
+                None => Ok(()),
+            })
+            .map_err(ModuleError::from)
+            .and_then(|()| {
+                let host_inputs = runtime.stack.drain().collect::<Vec<_>>();
+
+                self.call_function(&host_inputs)
+            });
+
+        let host_outputs = match host_outputs {
+            Ok(host_outputs) => host_outputs,
+            Err(error) => {
+                // The error of the lifting instructions or of the host
+                // function takes precedence over the errors of the
+                // deferred calls.
+                let _ = runtime.execute_deferred_calls();
+
+                return Err(error);
+            }
+        };
+
+        runtime.stack.clear();
+
+        for output in host_outputs {
+            runtime.stack.push(output);
+        }
+
+        runtime.program_counter = 0;
+        runtime.compiled_adapters = &self.lower.compiled_adapters;
+        runtime
+            .execute(&self.lower.program)
+            .map_err(InterpreterError::from)?;
+
+        if let Some(signature) = &self.lower.signature {
+            signature.check_outputs(runtime.stack.as_slice())?;
+        }
+
+        Ok(runtime.stack.drain().collect())
+    }
+
+    /// Calls the host function, and checks that its outputs are of the
+    /// output types of the import.
+    fn call_function(&self, host_inputs: &[InterfaceValue]) -> ModuleResult<Vec<InterfaceValue>> {
+        let host_outputs =
+            (self.function)(host_inputs).map_err(|source| ModuleError::HostImportCall {
+                namespace: self.namespace.clone(),
+                name: self.name.clone(),
+                source,
+            })?;
+
+        if !super::instructions::values_are_of_types(&host_outputs, &self.host_outputs) {
+            return Err(ModuleError::HostImportOutputsMismatch {
+                namespace: self.namespace.clone(),
+                name: self.name.clone(),
+                expected: self.host_outputs.clone(),
+                received: host_outputs.iter().map(Into::into).collect(),
+            });
+        }
+
+        Ok(host_outputs)
+    }
+}
+
+/// Splits the instructions of an import adapter around the first
+/// `call-core` instruction calling `function_index` outside of any
+/// block (`block`, `if`, `list.lift` or `for-each`). It returns `None`
+/// if the adapter doesn't call the function outside of any block, and
+/// an error if the structure of its blocks is invalid.
+pub(crate) fn split_import_adapter(
+    instructions: &[Instruction],
+    function_index: u32,
+) -> InstructionResult<Option<(&[Instruction], &[Instruction])>> {
+    let outside_blocks = outside_blocks(instructions)?;
+    let position =
+        instructions
+            .iter()
+            .zip(outside_blocks)
+            .position(|(instruction, outside_blocks)| {
+                outside_blocks
+                    && matches!(
+                        instruction,
+                        Instruction::CallCore { function_index: index } if *index == function_index
+                    )
+            });
+
+    Ok(position.map(|position| (&instructions[..position], &instructions[position + 1..])))
+}
+
+/// Represents a core-level function of an interface module,
+/// implemented by a host function through an import adapter. It is
+/// returned by
+/// [`InterfaceModule::import_function`](super::InterfaceModule::import_function),
+/// and is meant to be given to the WebAssembly runtime as the
+/// implementation of the core import.
+pub struct ImportFunction<'module, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// The import adapter.
+    pub(crate) adapter: &'module ImportAdapter<Instance, Export, LocalImport, Memory, MemoryView>,
+}
+
+impl<'module, Instance, Export, LocalImport, Memory, MemoryView>
+    ImportFunction<'module, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Returns the core input types of the function.
+    pub fn inputs(&self) -> &[InterfaceType] {
+        &self.adapter.inputs
+    }
+
+    /// Returns the core output types of the function.
+    pub fn outputs(&self) -> &[InterfaceType] {
+        &self.adapter.outputs
+    }
+
+    /// Calls the function with core arguments, and returns the core
+    /// results.
+    pub fn call(
+        &self,
+        arguments: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+    ) -> ModuleResult<Vec<InterfaceValue>> {
+        self.adapter.call(arguments, wasm_instance)
+    }
+}
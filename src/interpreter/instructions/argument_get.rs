@@ -4,21 +4,19 @@ use crate::{
 };
 
 executable_instruction!(
-    argument_get(index: u32, instruction: Instruction) -> _ {
-        move |runtime| -> _ {
-            let invocation_inputs = runtime.invocation_inputs;
+    argument_get(runtime, index: u32, instruction: &Instruction) {
+        let invocation_inputs = runtime.invocation_inputs;
 
-            if (index as usize) >= invocation_inputs.len() {
-                return Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::InvocationInputIsMissing { index },
-                ));
-            }
+        if (index as usize) >= invocation_inputs.len() {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::InvocationInputIsMissing { index },
+            ));
+        }
 
-            runtime.stack.push(invocation_inputs[index as usize].clone());
+        runtime.stack.push(invocation_inputs[index as usize].clone());
 
-            Ok(())
-        }
+        Ok(())
     }
 );
 
@@ -54,6 +52,6 @@ mod tests {
             instructions: [Instruction::ArgumentGet { index: 1 }],
             invocation_inputs: [InterfaceValue::I32(42)],
             instance: Instance::new(),
-            error: "`arg.get 1` cannot access invocation inputs #1 because it doesn't exist"
+            error: "`arg.get 1` cannot access invocation inputs #1 because it doesn't exist\n  at instruction 0"
     );
 }
@@ -0,0 +1,555 @@
+use super::values_are_of_types;
+use crate::{
+    ast::{Type, TypeKind},
+    errors::{ErrorAdapter, InstructionError, InstructionErrorKind, InstructionResult},
+    interpreter::{
+        bytecode::{CompiledAdapter, Program},
+        compile,
+        stack::Stackable,
+        wasm, ExecutionContext, Instruction, Observer, Runtime, MAX_ADAPTER_CALL_DEPTH,
+    },
+    types::InterfaceType,
+};
+use std::{marker::PhantomData, mem, sync::Arc};
+
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Checks that another adapter can be called, i.e. that the
+    /// maximum depth of nested adapter calls isn't reached.
+    fn check_adapter_call_depth(&self, instruction: &Instruction) -> InstructionResult<()> {
+        if self.adapter_call_depth >= MAX_ADAPTER_CALL_DEPTH {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::AdapterCallDepthExceeded {
+                    max_depth: MAX_ADAPTER_CALL_DEPTH,
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Runs the program of an adapter, with values of the
+    /// `adapter_inputs` types read from the stack. The values left by
+    /// the adapter must be of the `adapter_outputs` types, and are
+    /// pushed on the stack.
+    ///
+    /// The adapter runs with an execution context of the pool of the
+    /// runtime, so that the buffers of each nesting level are reused
+    /// from one call to another.
+    fn run_adapter(
+        &mut self,
+        adapter_index: u32,
+        adapter_inputs: &[InterfaceType],
+        adapter_outputs: &[InterfaceType],
+        adapter_program: &Program,
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        let mut nested_context = self.nested_contexts.pop().unwrap_or_default();
+        nested_context.clear();
+
+        let result = self.run_nested_adapter(
+            &mut nested_context,
+            adapter_index,
+            adapter_inputs,
+            adapter_outputs,
+            adapter_program,
+            instruction,
+        );
+
+        self.nested_contexts.push(nested_context);
+
+        result
+    }
+
+    /// Runs the program of an adapter like [`Runtime::run_adapter`],
+    /// with the buffers of `nested_context`.
+    fn run_nested_adapter(
+        &mut self,
+        nested_context: &mut ExecutionContext,
+        adapter_index: u32,
+        adapter_inputs: &[InterfaceType],
+        adapter_outputs: &[InterfaceType],
+        adapter_program: &Program,
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        let inputs_cardinality = adapter_inputs.len();
+
+        if !self
+            .stack
+            .pop_into(inputs_cardinality, &mut nested_context.inputs)
+        {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall {
+                    needed: inputs_cardinality,
+                },
+            ));
+        }
+
+        let inputs = &nested_context.inputs;
+
+        if !values_are_of_types(inputs, adapter_inputs) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::AdapterSignatureMismatch {
+                    adapter_index,
+                    expected: adapter_inputs.to_vec(),
+                    received: inputs.iter().map(Into::into).collect(),
+                },
+            ));
+        }
+
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.enter_adapter(adapter_index);
+        }
+
+        // The nested adapter runs on the stack of its context, with
+        // the values read from the current stack as invocation inputs.
+        let mut nested_runtime = Runtime {
+            invocation_inputs: &nested_context.inputs,
+            stack: mem::take(&mut nested_context.stack),
+            wasm_instance: &mut *self.wasm_instance,
+            adapter_call_depth: self.adapter_call_depth + 1,
+            compiled_adapters: self.compiled_adapters,
+            called_adapters: mem::take(&mut self.called_adapters),
+            arguments: mem::take(&mut nested_context.arguments),
+            limits: self.limits,
+            remaining_fuel: self.remaining_fuel,
+            memory_bytes: self.memory_bytes,
+            observer: self
+                .observer
+                .as_deref_mut()
+                .map(|observer| observer as &mut dyn Observer),
+            deferred_calls: mem::take(&mut nested_context.deferred_calls),
+            deferred_arguments: mem::take(&mut nested_context.deferred_arguments),
+            nested_contexts: mem::take(&mut self.nested_contexts),
+            program_counter: 0,
+            block_heights: mem::take(&mut nested_context.block_heights),
+            iterations: mem::take(&mut nested_context.iterations),
+            _phantom: PhantomData,
+        };
+
+        let result = nested_runtime.execute(adapter_program);
+
+        // The nested adapter consumes the fuel and the memory bytes
+        // of the current one, and gives back the called adapters, the
+        // contexts of the deeper levels and its own buffers.
+        self.remaining_fuel = nested_runtime.remaining_fuel;
+        self.memory_bytes = nested_runtime.memory_bytes;
+        self.called_adapters = nested_runtime.called_adapters;
+        self.nested_contexts = nested_runtime.nested_contexts;
+        nested_context.stack = nested_runtime.stack;
+        nested_context.arguments = nested_runtime.arguments;
+        nested_context.deferred_calls = nested_runtime.deferred_calls;
+        nested_context.deferred_arguments = nested_runtime.deferred_arguments;
+        nested_context.block_heights = nested_runtime.block_heights;
+        nested_context.iterations = nested_runtime.iterations;
+
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.exit_adapter(adapter_index);
+        }
+
+        result.map_err(|error| describe_adapter(&*self.wasm_instance, adapter_index, error))?;
+
+        let outputs = nested_context.stack.as_slice();
+
+        if !values_are_of_types(outputs, adapter_outputs) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::AdapterOutputsMismatch {
+                    adapter_index,
+                    expected: adapter_outputs.to_vec(),
+                    received: outputs.iter().map(Into::into).collect(),
+                },
+            ));
+        }
+
+        for output in nested_context.stack.drain() {
+            self.stack.push(output);
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the input types, the output types and the instructions of
+/// the adapter at `adapter_index` from the WebAssembly instance.
+pub(crate) fn resolve_adapter<'instance, Instance, Export, LocalImport, Memory, MemoryView>(
+    instance: &'instance Instance,
+    adapter_index: u32,
+    instruction: &Instruction,
+) -> InstructionResult<(
+    &'instance [InterfaceType],
+    &'instance [InterfaceType],
+    &'instance [Instruction],
+)>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    let adapter = instance.wit_adapter(adapter_index).ok_or_else(|| {
+        InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::AdapterIsMissing { adapter_index },
+        )
+    })?;
+    let type_index = adapter.function_type;
+
+    match instance.wit_type(type_index).ok_or_else(|| {
+        InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::TypeIsMissing { type_index },
+        )
+    })? {
+        Type::Function { inputs, outputs } => Ok((inputs, outputs, &adapter.instructions)),
+        Type::Record(_) => Err(InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::InvalidTypeKind {
+                expected_kind: TypeKind::Function,
+                received_kind: TypeKind::Record,
+            },
+        )),
+    }
+}
+
+/// Describes the adapter at `adapter_index` in the backtrace of an
+/// error raised by one of its instructions.
+pub(crate) fn describe_adapter<Instance, Export, LocalImport, Memory, MemoryView>(
+    instance: &Instance,
+    adapter_index: u32,
+    error: InstructionError,
+) -> InstructionError
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    match instance.wit_adapter(adapter_index) {
+        Some(adapter) => error.in_adapter(ErrorAdapter {
+            adapter_index,
+            function_type: adapter.function_type,
+            export_name: instance
+                .wit_export_name(adapter_index)
+                .map(ToString::to_string),
+        }),
+
+        None => error,
+    }
+}
+
+executable_instruction!(
+    call_adapter(runtime, adapter_index: u32, instruction: &Instruction) {
+        runtime.check_adapter_call_depth(instruction)?;
+
+        let (inputs, outputs, adapter_instructions) =
+            resolve_adapter(&*runtime.wasm_instance, adapter_index, instruction)?;
+
+        // The adapters compiled by a previous run are kept by the
+        // execution context, which can be used with another instance:
+        // a compiled adapter is reused only if it is still identical
+        // to the adapter of the instance.
+        let compiled_adapter = match runtime.called_adapters.get(&adapter_index) {
+            Some(compiled_adapter)
+                if compiled_adapter.is_compiled_from(inputs, outputs, adapter_instructions) =>
+            {
+                Arc::clone(compiled_adapter)
+            }
+
+            _ => {
+                let compiled_adapter = Arc::new(CompiledAdapter {
+                    adapter_index,
+                    inputs: inputs.to_vec(),
+                    outputs: outputs.to_vec(),
+                    program: compile(adapter_instructions).map_err(|error| {
+                        describe_adapter(&*runtime.wasm_instance, adapter_index, error)
+                    })?,
+                });
+
+                runtime
+                    .called_adapters
+                    .insert(adapter_index, Arc::clone(&compiled_adapter));
+
+                compiled_adapter
+            }
+        };
+
+        runtime.run_adapter(
+            adapter_index,
+            &compiled_adapter.inputs,
+            &compiled_adapter.outputs,
+            &compiled_adapter.program,
+            instruction,
+        )
+    }
+);
+
+executable_instruction!(
+    resolved_call_adapter(runtime, adapter_index: u32, compiled_index: usize, instruction: &Instruction) {
+        runtime.check_adapter_call_depth(instruction)?;
+
+        let compiled_adapters = runtime.compiled_adapters;
+        let compiled_adapter = &compiled_adapters[compiled_index];
+
+        runtime.run_adapter(
+            adapter_index,
+            &compiled_adapter.inputs,
+            &compiled_adapter.outputs,
+            &compiled_adapter.program,
+            instruction,
+        )
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Adapter, Type};
+
+    test_executable_instruction!(
+        test_call_adapter =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallAdapter { adapter_index: 0 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Function {
+                    inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                    outputs: vec![InterfaceType::S32],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![
+                        Instruction::ArgumentGet { index: 1 },
+                        Instruction::ArgumentGet { index: 0 },
+                        Instruction::CallCore { function_index: 42 },
+                        Instruction::S32FromI32,
+                    ],
+                });
+
+                instance
+            },
+            stack: [InterfaceValue::S32(12)],
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__nested =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::CallAdapter { adapter_index: 1 },
+            ],
+            invocation_inputs: [InterfaceValue::I32(7)],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Function {
+                    inputs: vec![InterfaceType::I32],
+                    outputs: vec![InterfaceType::S32],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![
+                        Instruction::ArgumentGet { index: 0 },
+                        Instruction::S32FromI32,
+                    ],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![
+                        Instruction::ArgumentGet { index: 0 },
+                        Instruction::CallAdapter { adapter_index: 0 },
+                    ],
+                });
+
+                instance
+            },
+            stack: [InterfaceValue::S32(7)],
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__adapter_is_missing =
+            instructions: [
+                Instruction::CallAdapter { adapter_index: 0 },
+            ],
+            invocation_inputs: [],
+            instance: Instance::new(),
+            error: "`call-adapter 0` the adapter `0` doesn't exist\n  at instruction 0",
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__invalid_type_kind =
+            instructions: [
+                Instruction::CallAdapter { adapter_index: 0 },
+            ],
+            invocation_inputs: [],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_adapters.push(Adapter {
+                    function_type: 0,
+                    //             ^ this is a record type
+                    instructions: vec![],
+                });
+
+                instance
+            },
+            error: "`call-adapter 0` read a type of kind `Record`, but the kind `Function` was expected\n  at instruction 0",
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__stack_is_too_small =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::CallAdapter { adapter_index: 0 },
+                //                                        ^ the adapter expects 2 values on the stack, only one is present
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Function {
+                    inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                    outputs: vec![],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![],
+                });
+
+                instance
+            },
+            error: "`call-adapter 0` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__invalid_types_in_the_stack =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallAdapter { adapter_index: 0 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I64(4),
+                //              ^^^ mismatch with the adapter signature
+            ],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Function {
+                    inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                    outputs: vec![],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![],
+                });
+
+                instance
+            },
+            error: "`call-adapter 0` the adapter `0` expects values of kind `[I32, I32]` but it received values of kind `[I32, I64]`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__invalid_outputs =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::CallAdapter { adapter_index: 0 },
+            ],
+            invocation_inputs: [InterfaceValue::I32(3)],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Function {
+                    inputs: vec![InterfaceType::I32],
+                    outputs: vec![InterfaceType::S32],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![
+                        Instruction::ArgumentGet { index: 0 },
+                        // the `s32` output is missing, an `i32` is left instead
+                    ],
+                });
+
+                instance
+            },
+            error: "`call-adapter 0` the adapter `0` must return values of kind `[S32]` but it returned values of kind `[I32]`\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__error_backtrace =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::CallAdapter { adapter_index: 1 },
+            ],
+            invocation_inputs: [InterfaceValue::I32(7)],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Function {
+                    inputs: vec![InterfaceType::I32],
+                    outputs: vec![],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![
+                        Instruction::ArgumentGet { index: 0 },
+                        Instruction::ArgumentGet { index: 1 },
+                        //                                ^ the adapter has only one input
+                    ],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![
+                        Instruction::ArgumentGet { index: 0 },
+                        Instruction::ArgumentGet { index: 0 },
+                        Instruction::CallAdapter { adapter_index: 0 },
+                    ],
+                });
+                instance.wit_export_names.insert(1, "foo".into());
+
+                instance
+            },
+            error: "`arg.get 1` cannot access invocation inputs #1 because it doesn't exist\n  at instruction 1 of adapter 0 (type 1)\n  at instruction 2 of adapter 1 (type 1, export \"foo\")\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_call_adapter__infinite_recursion =
+            instructions: [
+                Instruction::CallAdapter { adapter_index: 0 },
+            ],
+            invocation_inputs: [],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Function {
+                    inputs: vec![],
+                    outputs: vec![],
+                });
+                instance.wit_adapters.push(Adapter {
+                    function_type: 1,
+                    instructions: vec![
+                        Instruction::CallAdapter { adapter_index: 0 },
+                        //                                        ^ calls itself
+                    ],
+                });
+
+                instance
+            },
+            error: "`call-adapter 0` reached the maximum depth of nested adapter calls (128)\n  at instruction 0 of adapter 0 (type 1) (128 times)\n  at instruction 0",
+    );
+}
@@ -1,65 +1,246 @@
+use super::values_are_of_types;
 use crate::{
-    errors::{InstructionError, InstructionErrorKind},
-    interpreter::wasm::structures::{FunctionIndex, TypedIndex},
-    interpreter::Instruction,
+    errors::{InstructionError, InstructionErrorKind, InstructionResult},
+    interpreter::wasm::{
+        self,
+        structures::{FunctionIndex, TypedIndex},
+    },
+    interpreter::{stack::Stackable, Instruction, Runtime},
     types::InterfaceType,
+    values::InterfaceValue,
 };
 
+/// Checks that the values returned by a local or import function are
+/// of its output types, so that a host function returning the wrong
+/// number or the wrong types of values is caught at the boundary.
+pub(crate) fn check_outputs(
+    function_index: u32,
+    inputs_types: &[InterfaceType],
+    outputs_types: &[InterfaceType],
+    outputs: &[InterfaceValue],
+    instruction: &Instruction,
+) -> InstructionResult<()> {
+    if values_are_of_types(outputs, outputs_types) {
+        return Ok(());
+    }
+
+    Err(InstructionError::new(
+        instruction.clone(),
+        InstructionErrorKind::LocalOrImportSignatureMismatch {
+            function_index,
+            expected: (inputs_types.to_vec(), outputs_types.to_vec()),
+            received: (
+                inputs_types.to_vec(),
+                outputs.iter().map(Into::into).collect(),
+            ),
+        },
+    ))
+}
+
 executable_instruction!(
-    call_core(function_index: u32, instruction: Instruction) -> _ {
-        move |runtime| -> _ {
-            let instance = &mut runtime.wasm_instance;
-            let index = FunctionIndex::new(function_index as usize);
-
-            let local_or_import = instance.local_or_import(index).ok_or_else(|| {
-                InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::LocalOrImportIsMissing {
-                        function_index: function_index,
-                    },
-                )
-            })?;
-            let inputs_cardinality = local_or_import.inputs_cardinality();
-
-            let inputs = runtime.stack.pop(inputs_cardinality).ok_or_else(|| {
-                InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::StackIsTooSmall {
-                        needed: inputs_cardinality,
-                    },
-                )
-            })?;
-            let input_types = inputs
-                .iter()
-                .map(Into::into)
-                .collect::<Vec<InterfaceType>>();
-
-            if input_types != local_or_import.inputs() {
-                return Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::LocalOrImportSignatureMismatch {
-                        function_index: function_index,
-                        expected: (local_or_import.inputs().to_vec(), vec![]),
-                        received: (input_types, vec![]),
-                    },
-                ));
-            }
+    call_core(runtime, function_index: u32, instruction: &Instruction) {
+        let instance = &mut runtime.wasm_instance;
+        let index = FunctionIndex::new(function_index as usize);
 
-            let outputs = local_or_import.call(&inputs).map_err(|_| {
-                InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::LocalOrImportCall {
-                        function_index: function_index,
-                    },
-                )
-            })?;
+        let local_or_import = instance.local_or_import(index).ok_or_else(|| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportIsMissing {
+                    function_index: function_index,
+                },
+            )
+        })?;
+        let inputs_cardinality = local_or_import.inputs_cardinality();
 
-            for output in outputs.into_iter() {
-                runtime.stack.push(output)
-            }
+        if !runtime.stack.pop_into(inputs_cardinality, &mut runtime.arguments) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall {
+                    needed: inputs_cardinality,
+                },
+            ));
+        }
 
-            Ok(())
+        let inputs = &runtime.arguments;
+        if !values_are_of_types(inputs, local_or_import.inputs()) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportSignatureMismatch {
+                    function_index: function_index,
+                    expected: (
+                        local_or_import.inputs().to_vec(),
+                        local_or_import.outputs().to_vec(),
+                    ),
+                    received: (inputs.iter().map(Into::into).collect(), vec![]),
+                },
+            ));
         }
+
+        let outputs = local_or_import.call(inputs).map_err(|source| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportCall {
+                    function_index: function_index,
+                    source,
+                },
+            )
+        })?;
+
+        check_outputs(
+            function_index,
+            local_or_import.inputs(),
+            local_or_import.outputs(),
+            &outputs,
+            instruction,
+        )?;
+
+        for output in outputs.into_iter() {
+            runtime.stack.push(output)
+        }
+
+        Ok(())
+    }
+);
+
+/// Executes the `call-core` instruction with the asynchronous local or
+/// import function at `function_index`, like `call_core` does with the
+/// synchronous one. The runtime is suspended while the future of the
+/// call is pending.
+pub(crate) async fn async_call_core<Instance, Export, LocalImport, Memory, MemoryView>(
+    runtime: &mut Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>,
+    function_index: u32,
+    instruction: &Instruction,
+) -> InstructionResult<()>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    let index = FunctionIndex::new(function_index as usize);
+
+    let local_or_import = runtime
+        .wasm_instance
+        .async_local_or_import(index)
+        .ok_or_else(|| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportIsMissing { function_index },
+            )
+        })?;
+    let inputs_cardinality = local_or_import.inputs().len();
+
+    if !runtime
+        .stack
+        .pop_into(inputs_cardinality, &mut runtime.arguments)
+    {
+        return Err(InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::StackIsTooSmall {
+                needed: inputs_cardinality,
+            },
+        ));
+    }
+
+    let inputs = &runtime.arguments;
+
+    if !values_are_of_types(inputs, local_or_import.inputs()) {
+        return Err(InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::LocalOrImportSignatureMismatch {
+                function_index,
+                expected: (
+                    local_or_import.inputs().to_vec(),
+                    local_or_import.outputs().to_vec(),
+                ),
+                received: (inputs.iter().map(Into::into).collect(), vec![]),
+            },
+        ));
+    }
+
+    let outputs = local_or_import.call(inputs).await.map_err(|source| {
+        InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::LocalOrImportCall {
+                function_index,
+                source,
+            },
+        )
+    })?;
+
+    check_outputs(
+        function_index,
+        local_or_import.inputs(),
+        local_or_import.outputs(),
+        &outputs,
+        instruction,
+    )?;
+
+    for output in outputs.into_iter() {
+        runtime.stack.push(output)
+    }
+
+    Ok(())
+}
+
+executable_instruction!(
+    resolved_call_core(
+        runtime,
+        function_index: u32,
+        inputs_types: &[InterfaceType],
+        outputs_types: &[InterfaceType],
+        instruction: &Instruction
+    ) {
+        let inputs_cardinality = inputs_types.len();
+
+        if !runtime.stack.pop_into(inputs_cardinality, &mut runtime.arguments) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall {
+                    needed: inputs_cardinality,
+                },
+            ));
+        }
+
+        let inputs = &runtime.arguments;
+
+        if !values_are_of_types(inputs, inputs_types) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportSignatureMismatch {
+                    function_index,
+                    expected: (inputs_types.to_vec(), outputs_types.to_vec()),
+                    received: (inputs.iter().map(Into::into).collect(), vec![]),
+                },
+            ));
+        }
+
+        let index = FunctionIndex::new(function_index as usize);
+        let local_or_import = runtime.wasm_instance.local_or_import(index).ok_or_else(|| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportIsMissing { function_index },
+            )
+        })?;
+
+        let outputs = local_or_import.call(inputs).map_err(|source| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportCall {
+                    function_index,
+                    source,
+                },
+            )
+        })?;
+
+        check_outputs(function_index, inputs_types, outputs_types, &outputs, instruction)?;
+
+        for output in outputs.into_iter() {
+            runtime.stack.push(output)
+        }
+
+        Ok(())
     }
 );
 
@@ -90,7 +271,7 @@ mod tests {
                 InterfaceValue::I32(4),
             ],
             instance: Default::default(),
-            error: r#"`call-core 42` the local or import function `42` doesn't exist"#,
+            error: "`call-core 42` the local or import function `42` doesn't exist\n  at instruction 0",
     );
 
     test_executable_instruction!(
@@ -105,7 +286,7 @@ mod tests {
                 InterfaceValue::I32(4),
             ],
             instance: Instance::new(),
-            error: r#"`call-core 42` needed to read `2` value(s) from the stack, but it doesn't contain enough data"#,
+            error: "`call-core 42` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 1",
     );
 
     test_executable_instruction!(
@@ -121,7 +302,7 @@ mod tests {
                 //              ^^^ mismatch with `42` signature
             ],
             instance: Instance::new(),
-            error: r#"`call-core 42` the local or import function `42` has the signature `[I32, I32] -> []` but it received values of kind `[I32, I64] -> []`"#,
+            error: "`call-core 42` the local or import function `42` has the signature `[I32, I32] -> [I32]` but it received values of kind `[I32, I64] -> []`\n  at instruction 2",
     );
 
     test_executable_instruction!(
@@ -143,8 +324,8 @@ mod tests {
                         LocalImport {
                             inputs: vec![InterfaceType::I32, InterfaceType::I32],
                             outputs: vec![InterfaceType::I32],
-                            function: |_| Err(()),
-                            //            ^^^^^^^ function fails
+                            function: |_| Err("unreachable".into()),
+                            //            ^^^^^^^^^^^^^^^^^^^^^^^^^ function fails
                         },
                     );
 
@@ -152,11 +333,11 @@ mod tests {
                 },
                 ..Default::default()
             },
-            error: r#"`call-core 42` failed while calling the local or import function `42`"#,
+            error: "`call-core 42` failed while calling the local or import function `42`\n  at instruction 2",
     );
 
     test_executable_instruction!(
-        test_call_core__void =
+        test_call_core__invalid_outputs_cardinality =
             instructions: [
                 Instruction::ArgumentGet { index: 0 },
                 Instruction::ArgumentGet { index: 1 },
@@ -174,6 +355,68 @@ mod tests {
                         LocalImport {
                             inputs: vec![InterfaceType::I32, InterfaceType::I32],
                             outputs: vec![InterfaceType::I32],
+                            function: |_| Ok(vec![InterfaceValue::I32(1), InterfaceValue::I32(2)]),
+                            //            ^^ mismatch with the `42` signature
+                        },
+                    );
+
+                    hashmap
+                },
+                ..Default::default()
+            },
+            error: "`call-core 42` the local or import function `42` has the signature `[I32, I32] -> [I32]` but it received values of kind `[I32, I32] -> [I32, I32]`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_call_core__invalid_outputs_types =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallCore { function_index: 42 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance {
+                locals_or_imports: {
+                    let mut hashmap = HashMap::new();
+                    hashmap.insert(
+                        42,
+                        LocalImport {
+                            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                            outputs: vec![InterfaceType::I32],
+                            function: |_| Ok(vec![InterfaceValue::I64(1)]),
+                            //            ^^ mismatch with the `42` signature
+                        },
+                    );
+
+                    hashmap
+                },
+                ..Default::default()
+            },
+            error: "`call-core 42` the local or import function `42` has the signature `[I32, I32] -> [I32]` but it received values of kind `[I32, I32] -> [I64]`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_call_core__void =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallCore { function_index: 42 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance {
+                locals_or_imports: {
+                    let mut hashmap = HashMap::new();
+                    hashmap.insert(
+                        42,
+                        LocalImport {
+                            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                            outputs: vec![],
                             function: |_| Ok(vec![]),
                             //            ^^^^^^^^^^ void
                         },
@@ -185,4 +428,233 @@ mod tests {
             },
             stack: [],
     );
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_call_core__failure_source() {
+        use crate::interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            Instruction, Interpreter,
+        };
+        use crate::types::InterfaceType;
+        use std::{collections::HashMap, convert::TryInto, error::Error};
+
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> =
+            (&vec![Instruction::CallCore { function_index: 42 }])
+                .try_into()
+                .unwrap();
+        let mut instance = Instance {
+            locals_or_imports: {
+                let mut hashmap = HashMap::new();
+                hashmap.insert(
+                    42,
+                    LocalImport {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::I32],
+                        function: |_| Err("unreachable executed".into()),
+                    },
+                );
+
+                hashmap
+            },
+            ..Default::default()
+        };
+
+        let error = interpreter.run(&[], &mut instance).unwrap_err();
+
+        // The error raised by the host is the source of the
+        // instruction error.
+        assert_eq!(
+            error.source().map(ToString::to_string),
+            Some("unreachable executed".to_string())
+        );
+    }
+
+    /// Polls a future to completion on the current thread.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::{
+            sync::Arc,
+            task::{Context, Poll, Wake},
+        };
+
+        struct NoopWaker;
+
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let waker = Arc::new(NoopWaker).into();
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_call_core__async() {
+        use crate::interpreter::{
+            instructions::tests::{
+                AsyncLocalImport, Export, Instance, LocalImport, Memory, MemoryView,
+            },
+            stack::Stackable,
+            Instruction, Interpreter,
+        };
+        use crate::{types::InterfaceType, values::InterfaceValue};
+        use std::convert::TryInto;
+
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallCore { function_index: 7 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallCore { function_index: 42 },
+        ])
+            .try_into()
+            .unwrap();
+        let mut instance = Instance::new();
+        instance.async_locals_or_imports.insert(
+            7,
+            AsyncLocalImport {
+                inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                outputs: vec![InterfaceType::I32],
+                function: |arguments| match arguments {
+                    [InterfaceValue::I32(a), InterfaceValue::I32(b)] => {
+                        Ok(vec![InterfaceValue::I32(a - b)])
+                    }
+                    _ => Err("unreachable".into()),
+                },
+            },
+        );
+
+        // The asynchronous function 7 is awaited, and the synchronous
+        // function 42 is called as usual: (10 - 3) * 3.
+        let stack = block_on(interpreter.run_async(
+            &[InterfaceValue::I32(10), InterfaceValue::I32(3)],
+            &mut instance,
+        ))
+        .unwrap();
+
+        assert_eq!(stack.as_slice(), &[InterfaceValue::I32(21)]);
+
+        // The synchronous run doesn't know the asynchronous function.
+        assert_eq!(
+            interpreter
+                .run(
+                    &[InterfaceValue::I32(10), InterfaceValue::I32(3)],
+                    &mut instance
+                )
+                .unwrap_err()
+                .to_string(),
+            "`call-core 7` the local or import function `7` doesn't exist\n  at instruction 2",
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_call_core__async_with_observer_and_context() {
+        use crate::interpreter::{
+            instructions::tests::{
+                AsyncLocalImport, Export, Instance, LocalImport, Memory, MemoryView,
+            },
+            ExecutionContext, Instruction, Interpreter, Profiler,
+        };
+        use crate::{types::InterfaceType, values::InterfaceValue};
+        use std::convert::TryInto;
+
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::CallCore { function_index: 7 },
+        ])
+            .try_into()
+            .unwrap();
+        let mut instance = Instance::new();
+        instance.async_locals_or_imports.insert(
+            7,
+            AsyncLocalImport {
+                inputs: vec![InterfaceType::I32],
+                outputs: vec![InterfaceType::I32],
+                function: |arguments| match arguments {
+                    [InterfaceValue::I32(a)] => Ok(vec![InterfaceValue::I32(a + 1)]),
+                    _ => Err("unreachable".into()),
+                },
+            },
+        );
+
+        let mut profiler = Profiler::new();
+
+        block_on(interpreter.run_async_with_observer(
+            &[InterfaceValue::I32(1)],
+            &mut instance,
+            &mut profiler,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            profiler
+                .report()
+                .instructions
+                .iter()
+                .map(|profile| profile.count)
+                .sum::<u64>(),
+            2,
+        );
+
+        let mut context = ExecutionContext::new();
+        let mut outputs = Vec::new();
+
+        for value in 1..3 {
+            block_on(interpreter.run_async_into(
+                &[InterfaceValue::I32(value)],
+                &mut instance,
+                &mut context,
+                &mut outputs,
+            ))
+            .unwrap();
+
+            assert_eq!(outputs, vec![InterfaceValue::I32(value + 1)]);
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_call_core__async_failure() {
+        use crate::interpreter::{
+            instructions::tests::{
+                AsyncLocalImport, Export, Instance, LocalImport, Memory, MemoryView,
+            },
+            Instruction, Interpreter,
+        };
+        use crate::types::InterfaceType;
+        use std::{convert::TryInto, error::Error};
+
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> =
+            (&vec![Instruction::CallCore { function_index: 7 }])
+                .try_into()
+                .unwrap();
+        let mut instance = Instance::new();
+        instance.async_locals_or_imports.insert(
+            7,
+            AsyncLocalImport {
+                inputs: vec![],
+                outputs: vec![InterfaceType::I32],
+                function: |_| Err("connection refused".into()),
+            },
+        );
+
+        let error = block_on(interpreter.run_async(&[], &mut instance)).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "`call-core 7` failed while calling the local or import function `7`\n  at instruction 0",
+        );
+        assert_eq!(
+            error.source().map(ToString::to_string),
+            Some("connection refused".to_string())
+        );
+    }
 }
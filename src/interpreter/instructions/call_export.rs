@@ -0,0 +1,197 @@
+use super::values_are_of_types;
+use crate::{
+    errors::{InstructionError, InstructionErrorKind},
+    interpreter::Instruction,
+};
+
+executable_instruction!(
+    call_export(runtime, export_name: &str, instruction: &Instruction) {
+        let instance = &runtime.wasm_instance;
+
+        let export = instance.export(export_name).ok_or_else(|| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::ExportIsMissing {
+                    export_name: export_name.to_string(),
+                },
+            )
+        })?;
+        let inputs_cardinality = export.inputs_cardinality();
+
+        if !runtime.stack.pop_into(inputs_cardinality, &mut runtime.arguments) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall {
+                    needed: inputs_cardinality,
+                },
+            ));
+        }
+
+        let inputs = &runtime.arguments;
+
+        if !values_are_of_types(inputs, export.inputs()) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::ExportSignatureMismatch {
+                    export_name: export_name.to_string(),
+                    expected: (export.inputs().to_vec(), export.outputs().to_vec()),
+                    received: (inputs.iter().map(Into::into).collect(), vec![]),
+                },
+            ));
+        }
+
+        let outputs = export.call(inputs).map_err(|source| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::ExportCall {
+                    export_name: export_name.to_string(),
+                    source,
+                },
+            )
+        })?;
+
+        if !values_are_of_types(&outputs, export.outputs()) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::ExportSignatureMismatch {
+                    export_name: export_name.to_string(),
+                    expected: (export.inputs().to_vec(), export.outputs().to_vec()),
+                    received: (
+                        export.inputs().to_vec(),
+                        outputs.iter().map(Into::into).collect(),
+                    ),
+                },
+            ));
+        }
+
+        for output in outputs.into_iter() {
+            runtime.stack.push(output)
+        }
+
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    test_executable_instruction!(
+        test_call_export =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallExport { export_name: "sum".into() },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance::new(),
+            stack: [InterfaceValue::I32(7)],
+    );
+
+    test_executable_instruction!(
+        test_call_export__invalid_export_name =
+            instructions: [
+                Instruction::CallExport { export_name: "sum".into() },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Default::default(),
+            error: "`call-export \"sum\"` the exported function `sum` doesn't exist\n  at instruction 0",
+    );
+
+    test_executable_instruction!(
+        test_call_export__stack_is_too_small =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::CallExport { export_name: "sum".into() },
+                //                                      ^^^ `sum` expects 2 values on the stack, only one is present
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance::new(),
+            error: "`call-export \"sum\"` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_call_export__invalid_types_in_the_stack =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallExport { export_name: "sum".into() },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I64(4),
+                //              ^^^ mismatch with `sum` signature
+            ],
+            instance: Instance::new(),
+            error: "`call-export \"sum\"` the exported function `sum` has the signature `[I32, I32] -> [I32]` but it received values of kind `[I32, I64] -> []`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_call_export__failure_when_calling =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallExport { export_name: "sum".into() },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance {
+                exports: {
+                    let mut hashmap = HashMap::new();
+                    hashmap.insert(
+                        "sum".into(),
+                        Export {
+                            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                            outputs: vec![InterfaceType::I32],
+                            function: |_| Err("unreachable".into()),
+                            //            ^^^^^^^^^^^^^^^^^^^^^^^^^ function fails
+                        },
+                    );
+
+                    hashmap
+                },
+                ..Default::default()
+            },
+            error: "`call-export \"sum\"` failed while calling the exported function `sum`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_call_export__invalid_outputs_types =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::CallExport { export_name: "sum".into() },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance {
+                exports: {
+                    let mut hashmap = HashMap::new();
+                    hashmap.insert(
+                        "sum".into(),
+                        Export {
+                            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                            outputs: vec![InterfaceType::I32],
+                            function: |_| Ok(vec![InterfaceValue::I64(1)]),
+                            //            ^^ mismatch with the `sum` signature
+                        },
+                    );
+
+                    hashmap
+                },
+                ..Default::default()
+            },
+            error: "`call-export \"sum\"` the exported function `sum` has the signature `[I32, I32] -> [I32]` but it received values of kind `[I32, I32] -> [I64]`\n  at instruction 2",
+    );
+}
@@ -0,0 +1,510 @@
+use super::{to_native, values_are_of_types};
+use crate::{
+    errors::{InstructionError, InstructionErrorKind, InstructionResult},
+    interpreter::{stack::Stackable, wasm, Instruction, Runtime},
+    types::InterfaceType,
+};
+
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Checks that the values of the innermost block end with values
+    /// of the `expected` types. If `exact` is true, the block must
+    /// contain exactly these values.
+    pub(super) fn check_block_values(
+        &self,
+        expected: &[InterfaceType],
+        exact: bool,
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        // The floor of the stack is the height of the innermost block,
+        // so the stack never goes below it.
+        let height = self.block_heights.last().copied().unwrap_or(0);
+        let values = &self.stack.as_slice()[height..];
+
+        let values = if exact {
+            values
+        } else if values.len() < expected.len() {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall {
+                    needed: expected.len(),
+                },
+            ));
+        } else {
+            &values[values.len() - expected.len()..]
+        };
+
+        if !values_are_of_types(values, expected) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::BlockSignatureMismatch {
+                    expected: expected.to_vec(),
+                    received: values.iter().map(Into::into).collect(),
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Enters a new block, which reads values of the `inputs` types
+    /// from the stack.
+    pub(super) fn enter_block(
+        &mut self,
+        inputs: &[InterfaceType],
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        self.check_block_values(inputs, false, instruction)?;
+        self.block_heights
+            .push(self.stack.as_slice().len() - inputs.len());
+        self.update_stack_floor();
+
+        Ok(())
+    }
+
+    /// Leaves the innermost block.
+    pub(super) fn leave_block(&mut self) {
+        self.block_heights.pop();
+        self.update_stack_floor();
+    }
+
+    /// Sets the floor of the stack to the height of the innermost
+    /// block, so that the instructions of a block can't consume the
+    /// values below it.
+    fn update_stack_floor(&mut self) {
+        let height = self.block_heights.last().copied().unwrap_or(0);
+
+        self.stack.set_floor(height);
+    }
+
+    /// Pops the `i32` condition of `if` or `br_if`.
+    fn pop_condition(&mut self, instruction: &Instruction) -> InstructionResult<bool> {
+        let condition = self.stack.pop1().ok_or_else(|| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall { needed: 1 },
+            )
+        })?;
+
+        Ok(to_native::<i32>(&condition, instruction)? != 0)
+    }
+}
+
+executable_instruction!(
+    block(runtime, inputs: &[InterfaceType], instruction: &Instruction) {
+        runtime.enter_block(inputs, instruction)
+    }
+);
+
+executable_instruction!(
+    r#if(runtime, inputs: &[InterfaceType], false_target: usize, instruction: &Instruction) {
+        let condition = runtime.pop_condition(instruction)?;
+
+        runtime.enter_block(inputs, instruction)?;
+
+        if !condition {
+            runtime.program_counter = false_target;
+        }
+
+        Ok(())
+    }
+);
+
+executable_instruction!(
+    r#else(runtime, end_index: usize) {
+        // `else` is reached at the end of the `then` branch, so
+        // the `else` branch is skipped.
+        runtime.program_counter = end_index;
+
+        Ok(())
+    }
+);
+
+executable_instruction!(
+    end(runtime, outputs: &[InterfaceType], instruction: &Instruction) {
+        runtime.check_block_values(outputs, true, instruction)?;
+        runtime.leave_block();
+
+        Ok(())
+    }
+);
+
+executable_instruction!(
+    br_if(
+        runtime,
+        relative_depth: u32,
+        end_index: usize,
+        outputs: &[InterfaceType],
+        instruction: &Instruction
+    ) {
+        if !runtime.pop_condition(instruction)? {
+            return Ok(());
+        }
+
+        runtime.check_block_values(outputs, false, instruction)?;
+
+        // Unwind the stack, the blocks and the iterations up to
+        // the targeted block, and jump to its `end` instruction,
+        // which closes the block (or continues the iteration). The
+        // values of the block outputs are kept on the stack.
+        let target = runtime.block_heights.len() - 1 - relative_depth as usize;
+        let height = runtime.block_heights[target];
+
+        runtime.block_heights.truncate(target + 1);
+        runtime.update_stack_floor();
+        runtime.stack.unwind(height, outputs.len());
+
+        // Iterations are nested, so they are ordered by depth.
+        let iterations = runtime
+            .iterations
+            .iter()
+            .take_while(|iteration| iteration.block_depth <= target)
+            .count();
+
+        runtime.iterations.truncate(iterations);
+        runtime.program_counter = end_index;
+
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::BlockType;
+
+    test_executable_instruction!(
+        test_block__consumes_an_outer_value =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                },
+                Instruction::CallCore { function_index: 42 },
+                //                                      ^^ the first argument is below the block
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(3), InterfaceValue::I32(4)],
+            instance: Instance::new(),
+            error: "`call-core 42` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 3",
+    );
+
+    test_executable_instruction!(
+        test_block =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::I32, InterfaceType::I32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(3), InterfaceValue::I32(4)],
+            instance: Instance::new(),
+            stack: [InterfaceValue::I32(3), InterfaceValue::I32(4)],
+    );
+
+    test_executable_instruction!(
+        test_block__invalid_inputs =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![InterfaceType::S32],
+                        outputs: vec![],
+                    },
+                },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(3)],
+            instance: Instance::new(),
+            error: "`block (param s32)` the block expects values of kind `[S32]` but it received values of kind `[I32]`\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_block__invalid_outputs =
+            instructions: [
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(3)],
+            instance: Instance::new(),
+            error: "`end` the block expects values of kind `[I32]` but it received values of kind `[I32, I32]`\n  at instruction 3",
+    );
+
+    test_executable_instruction!(
+        test_if__true =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::If {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::S32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::S32FromI32,
+                Instruction::Else,
+                Instruction::ArgumentGet { index: 2 },
+                Instruction::S32FromI32,
+                Instruction::End,
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(1),
+                InterfaceValue::I32(2),
+                InterfaceValue::I32(3),
+            ],
+            instance: Instance::new(),
+            stack: [InterfaceValue::S32(2)],
+    );
+
+    test_executable_instruction!(
+        test_if__false =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::If {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::S32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::S32FromI32,
+                Instruction::Else,
+                Instruction::ArgumentGet { index: 2 },
+                Instruction::S32FromI32,
+                Instruction::End,
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(0),
+                InterfaceValue::I32(2),
+                InterfaceValue::I32(3),
+            ],
+            instance: Instance::new(),
+            stack: [InterfaceValue::S32(3)],
+    );
+
+    test_executable_instruction!(
+        test_if__false_without_else =
+            instructions: [
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::If {
+                    block_type: BlockType {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                },
+                Instruction::CallCore { function_index: 43 },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(0), InterfaceValue::I32(7)],
+            instance: Instance::new(),
+            stack: [InterfaceValue::I32(7)],
+    );
+
+    test_executable_instruction!(
+        test_if__invalid_condition =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::If {
+                    block_type: BlockType::default(),
+                },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I64(1)],
+            instance: Instance::new(),
+            error: "`if` failed to cast the WIT value `I64` to its native type\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_br_if =
+            instructions: [
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::BrIf { relative_depth: 0 },
+                Instruction::S8FromI32,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(1), InterfaceValue::I32(7)],
+            instance: Instance::new(),
+            stack: [InterfaceValue::I32(7)],
+    );
+
+    test_executable_instruction!(
+        test_br_if__not_taken =
+            instructions: [
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::BrIf { relative_depth: 0 },
+                Instruction::S8FromI32,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(0), InterfaceValue::I32(7)],
+            instance: Instance::new(),
+            error: "`end` the block expects values of kind `[I32]` but it received values of kind `[S8]`\n  at instruction 5",
+    );
+
+    test_executable_instruction!(
+        test_br_if__unwind_nested_blocks =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::I32],
+                    },
+                },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::Block {
+                    block_type: BlockType::default(),
+                },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::BrIf { relative_depth: 1 },
+                Instruction::End,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(1), InterfaceValue::I32(7)],
+            instance: Instance::new(),
+            stack: [InterfaceValue::I32(1), InterfaceValue::I32(7)],
+    );
+
+    test_executable_instruction!(
+        test_br_if__invalid_outputs =
+            instructions: [
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::S8],
+                    },
+                },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::BrIf { relative_depth: 0 },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(1)],
+            instance: Instance::new(),
+            error: "`br_if 0` the block expects values of kind `[S8]` but it received values of kind `[I32]`\n  at instruction 3",
+    );
+
+    mod validation {
+        use crate::interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            BlockType, Instruction, Interpreter,
+        };
+        use crate::types::InterfaceType;
+        use std::convert::TryFrom;
+
+        fn compile_error(instructions: Vec<Instruction>) -> String {
+            Interpreter::<Instance, Export, LocalImport, Memory, MemoryView>::try_from(
+                &instructions,
+            )
+            .err()
+            .unwrap()
+            .to_string()
+        }
+
+        #[test]
+        fn test_block_is_not_closed() {
+            assert_eq!(
+                compile_error(vec![
+                    Instruction::Block {
+                        block_type: BlockType::default(),
+                    },
+                    Instruction::Block {
+                        block_type: BlockType::default(),
+                    },
+                    Instruction::End,
+                ]),
+                "`block` the block is not closed by an `end` instruction\n  at instruction 0",
+            );
+        }
+
+        #[test]
+        fn test_else_without_if() {
+            assert_eq!(
+                compile_error(vec![
+                    Instruction::Block {
+                        block_type: BlockType::default(),
+                    },
+                    Instruction::Else,
+                    Instruction::End,
+                ]),
+                "`else` the `else` instruction doesn't belong to an `if` block\n  at instruction 1",
+            );
+        }
+
+        #[test]
+        fn test_end_without_block() {
+            assert_eq!(
+                compile_error(vec![Instruction::End]),
+                "`end` the `end` instruction doesn't close any block\n  at instruction 0",
+            );
+        }
+
+        #[test]
+        fn test_invalid_branch_depth() {
+            assert_eq!(
+                compile_error(vec![
+                    Instruction::Block {
+                        block_type: BlockType::default(),
+                    },
+                    Instruction::BrIf { relative_depth: 1 },
+                    Instruction::End,
+                ]),
+                "`br_if 1` cannot branch to the block at depth `1` because it doesn't exist\n  at instruction 1",
+            );
+        }
+
+        #[test]
+        fn test_if_without_else_signature_mismatch() {
+            assert_eq!(
+                compile_error(vec![
+                    Instruction::If {
+                        block_type: BlockType {
+                            inputs: vec![],
+                            outputs: vec![InterfaceType::I32],
+                        },
+                    },
+                    Instruction::End,
+                ]),
+                "`if (result i32)` an `if` block without `else` must leave the types it reads, but its signature is `[] -> [I32]`\n  at instruction 0",
+            );
+        }
+    }
+}
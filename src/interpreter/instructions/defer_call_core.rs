@@ -0,0 +1,237 @@
+use super::values_are_of_types;
+use crate::{
+    errors::{InstructionError, InstructionErrorKind},
+    interpreter::wasm::structures::{FunctionIndex, TypedIndex},
+    interpreter::{DeferredCall, Instruction},
+};
+
+executable_instruction!(
+    defer_call_core(runtime, function_index: u32, instruction: &Instruction) {
+        let instance = &mut runtime.wasm_instance;
+        let index = FunctionIndex::new(function_index as usize);
+
+        let local_or_import = instance.local_or_import(index).ok_or_else(|| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportIsMissing { function_index },
+            )
+        })?;
+        let inputs_cardinality = local_or_import.inputs_cardinality();
+
+        if !runtime.stack.pop_into(inputs_cardinality, &mut runtime.arguments) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall {
+                    needed: inputs_cardinality,
+                },
+            ));
+        }
+
+        let inputs = &runtime.arguments;
+
+        if !values_are_of_types(inputs, local_or_import.inputs()) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::LocalOrImportSignatureMismatch {
+                    function_index,
+                    expected: (
+                        local_or_import.inputs().to_vec(),
+                        local_or_import.outputs().to_vec(),
+                    ),
+                    received: (inputs.iter().map(Into::into).collect(), vec![]),
+                },
+            ));
+        }
+
+        // The call is executed when the adapter finishes. The program
+        // counter has already moved past this instruction.
+        runtime.deferred_arguments.append(&mut runtime.arguments);
+        runtime.deferred_calls.push(DeferredCall {
+            function_index,
+            inputs_count: inputs_cardinality,
+            instruction: instruction.clone(),
+            offset: runtime.program_counter - 1,
+        });
+
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    test_executable_instruction!(
+        test_defer_call_core =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::DeferCallCore { function_index: 42 },
+                Instruction::ArgumentGet { index: 1 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance::new(),
+            stack: [InterfaceValue::I32(4)],
+            //                          ^ the outputs of a deferred call are ignored
+    );
+
+    test_executable_instruction!(
+        test_defer_call_core__invalid_local_import_index =
+            instructions: [
+                Instruction::DeferCallCore { function_index: 42 },
+            ],
+            invocation_inputs: [],
+            instance: Default::default(),
+            error: "`defer-call-core 42` the local or import function `42` doesn't exist\n  at instruction 0",
+    );
+
+    test_executable_instruction!(
+        test_defer_call_core__stack_is_too_small =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::DeferCallCore { function_index: 42 },
+                //                                           ^^ `42` expects 2 values on the stack, only one is present
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance::new(),
+            error: "`defer-call-core 42` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_defer_call_core__invalid_types_in_the_stack =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::DeferCallCore { function_index: 42 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I64(4),
+                //              ^^^ mismatch with `42` signature
+            ],
+            instance: Instance::new(),
+            error: "`defer-call-core 42` the local or import function `42` has the signature `[I32, I32] -> [I32]` but it received values of kind `[I32, I64] -> []`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_defer_call_core__failure_when_calling =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::DeferCallCore { function_index: 42 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance {
+                locals_or_imports: {
+                    let mut hashmap = HashMap::new();
+                    hashmap.insert(
+                        42,
+                        LocalImport {
+                            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                            outputs: vec![],
+                            function: |_| Err("unreachable".into()),
+                            //            ^^^^^^^^^^^^^^^^^^^^^^^^^ function fails when the adapter finishes
+                        },
+                    );
+
+                    hashmap
+                },
+                ..Default::default()
+            },
+            error: "`defer-call-core 42` failed while calling the local or import function `42`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_defer_call_core__invalid_outputs_types =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::DeferCallCore { function_index: 42 },
+            ],
+            invocation_inputs: [
+                InterfaceValue::I32(3),
+                InterfaceValue::I32(4),
+            ],
+            instance: Instance {
+                locals_or_imports: {
+                    let mut hashmap = HashMap::new();
+                    hashmap.insert(
+                        42,
+                        LocalImport {
+                            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+                            outputs: vec![InterfaceType::I32],
+                            function: |_| Ok(vec![InterfaceValue::I64(1)]),
+                            //            ^^ mismatch with the `42` signature
+                        },
+                    );
+
+                    hashmap
+                },
+                ..Default::default()
+            },
+            error: "`defer-call-core 42` the local or import function `42` has the signature `[I32, I32] -> [I32]` but it received values of kind `[I32, I32] -> [I64]`\n  at instruction 2",
+    );
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_defer_call_core__executed_in_reverse_order_when_the_adapter_fails() {
+        use crate::{
+            interpreter::{
+                instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+                Instruction, Interpreter,
+            },
+            types::InterfaceType,
+            values::InterfaceValue,
+        };
+        use std::{convert::TryInto, sync::Mutex};
+
+        static DEALLOCATED_POINTERS: Mutex<Vec<InterfaceValue>> = Mutex::new(Vec::new());
+
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::DeferCallCore { function_index: 44 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::DeferCallCore { function_index: 44 },
+            Instruction::ArgumentGet { index: 2 },
+            //                                ^ fails, the invocation input doesn't exist
+        ])
+            .try_into()
+            .unwrap();
+
+        let invocation_inputs = vec![InterfaceValue::I32(7), InterfaceValue::I32(8)];
+        let mut instance = Instance::new();
+        instance.locals_or_imports.insert(
+            44,
+            LocalImport {
+                inputs: vec![InterfaceType::I32],
+                outputs: vec![],
+                function: |arguments| {
+                    DEALLOCATED_POINTERS
+                        .lock()
+                        .unwrap()
+                        .extend_from_slice(arguments);
+
+                    Ok(vec![])
+                },
+            },
+        );
+
+        let run = interpreter.run(&invocation_inputs, &mut instance);
+
+        assert_eq!(
+            run.unwrap_err().to_string(),
+            "`arg.get 2` cannot access invocation inputs #2 because it doesn't exist\n  at instruction 4"
+        );
+        assert_eq!(
+            *DEALLOCATED_POINTERS.lock().unwrap(),
+            vec![InterfaceValue::I32(8), InterfaceValue::I32(7)]
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use super::to_native;
+use crate::{
+    errors::{InstructionError, InstructionErrorKind, InstructionResult},
+    interpreter::{stack::Stackable, wasm, Instruction, Iteration, IterationInputs, Runtime},
+    types::InterfaceType,
+    values::InterfaceValue,
+};
+use std::{convert::TryInto, iter};
+
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Starts an iteration: the body will be executed once per input.
+    /// The first input is pushed on the stack, and the iteration block
+    /// is entered.
+    ///
+    /// The inputs must not be empty.
+    fn start_iteration(
+        &mut self,
+        mut remaining_inputs: IterationInputs,
+        input_types: &[InterfaceType],
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        if let Some(input) = remaining_inputs.next() {
+            self.stack.push(input);
+        }
+
+        self.iterations.push(Iteration {
+            block_depth: self.block_heights.len(),
+            remaining_inputs,
+            outputs: Vec::new(),
+        });
+
+        self.enter_block(input_types, instruction)
+    }
+}
+
+executable_instruction!(
+    list_lift(runtime, element_type: &InterfaceType, end_index: usize, instruction: &Instruction) {
+        let length = runtime.stack.pop1().ok_or_else(|| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall { needed: 1 },
+            )
+        })?;
+        let length: usize = to_native::<i32>(&length, instruction)?
+            .try_into()
+            .map_err(|e| (e, "length").into())
+            .map_err(|k| InstructionError::new(instruction.clone(), k))?;
+
+        if length == 0 {
+            runtime.stack.push(InterfaceValue::List(element_type.clone(), vec![]));
+            runtime.program_counter = end_index + 1;
+
+            return Ok(());
+        }
+
+        // The body receives the index of the element to lift. The
+        // indices are produced one at a time, so that a huge length
+        // is bounded by the limits rather than by the memory.
+        runtime.start_iteration(IterationInputs::Indices(0..length as i32), &[InterfaceType::I32], instruction)
+    }
+);
+
+executable_instruction!(
+    for_each(runtime, element_type: &InterfaceType, end_index: usize, instruction: &Instruction) {
+        let elements = match runtime.stack.pop1() {
+            Some(InterfaceValue::List(list_element_type, elements)) if list_element_type == *element_type => elements,
+
+            Some(value) => return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::InvalidValueOnTheStack {
+                    expected_type: InterfaceType::List(Box::new(element_type.clone())),
+                    received_type: (&value).into(),
+                },
+            )),
+
+            None => return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall { needed: 1 },
+            )),
+        };
+
+        if elements.is_empty() {
+            runtime.program_counter = end_index + 1;
+
+            return Ok(());
+        }
+
+        runtime.start_iteration(
+            IterationInputs::Elements(elements.into_iter()),
+            std::slice::from_ref(element_type),
+            instruction,
+        )
+    }
+);
+
+executable_instruction!(
+    iteration_end(
+        runtime,
+        input_types: &[InterfaceType],
+        element_type: Option<&InterfaceType>,
+        body_index: usize,
+        instruction: &Instruction
+    ) {
+        let output_types = element_type.map(std::slice::from_ref).unwrap_or(&[]);
+
+        runtime.check_block_values(output_types, true, instruction)?;
+        runtime.leave_block();
+
+        if element_type.is_some() {
+            if let Some(output) = runtime.stack.pop1() {
+                runtime.copy_lifted_values(iter::once(&output), instruction)?;
+                runtime
+                    .iterations
+                    .last_mut()
+                    .expect("An iteration block is always entered by its iteration instruction.")
+                    .outputs
+                    .push(output);
+            }
+        }
+
+        let iteration = runtime
+            .iterations
+            .last_mut()
+            .expect("An iteration block is always entered by its iteration instruction.");
+
+        // Execute the body again with the next input, if any.
+        if let Some(input) = iteration.remaining_inputs.next() {
+            runtime.stack.push(input);
+            runtime.enter_block(input_types, instruction)?;
+            runtime.program_counter = body_index;
+
+            return Ok(());
+        }
+
+        let iteration = runtime
+            .iterations
+            .pop()
+            .expect("An iteration block is always entered by its iteration instruction.");
+
+        if let Some(element_type) = element_type {
+            runtime.stack.push(InterfaceValue::List(element_type.clone(), iteration.outputs));
+        }
+
+        Ok(())
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Type, types::RecordType};
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    test_executable_instruction!(
+        test_list_lift__strings =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::String,
+                },
+                Instruction::CallCore { function_index: 44 },
+                Instruction::StringLiftMemory,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(2)],
+            instance: {
+                let mut instance = Instance::new();
+                instance.memory = Memory::new(
+                    "helloworld"
+                        .as_bytes()
+                        .iter()
+                        .map(|u| Cell::new(*u))
+                        .collect(),
+                );
+                // Returns the pointer and the length of the string
+                // at the given index.
+                instance.locals_or_imports.insert(
+                    44,
+                    LocalImport {
+                        inputs: vec![InterfaceType::I32],
+                        outputs: vec![InterfaceType::I32, InterfaceType::I32],
+                        function: |arguments: &[InterfaceValue]| {
+                            let index: i32 = (&arguments[0]).try_into().unwrap();
+
+                            Ok(vec![InterfaceValue::I32(index * 5), InterfaceValue::I32(5)])
+                        },
+                    },
+                );
+
+                instance
+            },
+            stack: [InterfaceValue::List(
+                InterfaceType::String,
+                vec![
+                    InterfaceValue::String("hello".into()),
+                    InterfaceValue::String("world".into()),
+                ],
+            )],
+    );
+
+    test_executable_instruction!(
+        test_list_lift__records =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::Record(RecordType {
+                        fields: vec1![InterfaceType::I32, InterfaceType::I32],
+                    }),
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::RecordLift { type_index: 1 },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(2), InterfaceValue::I32(7)],
+            instance: {
+                let mut instance = Instance::new();
+                instance.wit_types.push(Type::Record(RecordType {
+                    fields: vec1![InterfaceType::I32, InterfaceType::I32],
+                }));
+
+                instance
+            },
+            stack: [InterfaceValue::List(
+                InterfaceType::Record(RecordType {
+                    fields: vec1![InterfaceType::I32, InterfaceType::I32],
+                }),
+                vec![
+                    InterfaceValue::Record(vec1![InterfaceValue::I32(0), InterfaceValue::I32(7)]),
+                    InterfaceValue::Record(vec1![InterfaceValue::I32(1), InterfaceValue::I32(7)]),
+                ],
+            )],
+    );
+
+    test_executable_instruction!(
+        test_list_lift__empty =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::String,
+                },
+                Instruction::StringLiftMemory,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(0)],
+            instance: Instance::new(),
+            stack: [InterfaceValue::List(InterfaceType::String, vec![])],
+    );
+
+    test_executable_instruction!(
+        test_list_lift__nested =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::List(Box::new(InterfaceType::I32)),
+                },
+                // The index is the length of the nested list.
+                Instruction::ListLift {
+                    element_type: InterfaceType::I32,
+                },
+                Instruction::End,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(3)],
+            instance: Instance::new(),
+            stack: [InterfaceValue::List(
+                InterfaceType::List(Box::new(InterfaceType::I32)),
+                vec![
+                    InterfaceValue::List(InterfaceType::I32, vec![]),
+                    InterfaceValue::List(InterfaceType::I32, vec![InterfaceValue::I32(0)]),
+                    InterfaceValue::List(
+                        InterfaceType::I32,
+                        vec![InterfaceValue::I32(0), InterfaceValue::I32(1)],
+                    ),
+                ],
+            )],
+    );
+
+    test_executable_instruction!(
+        test_list_lift__br_if_continues =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::I32,
+                },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::BrIf { relative_depth: 0 },
+                Instruction::S8FromI32,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(2), InterfaceValue::I32(1)],
+            instance: Instance::new(),
+            stack: [InterfaceValue::List(
+                InterfaceType::I32,
+                vec![InterfaceValue::I32(0), InterfaceValue::I32(1)],
+            )],
+    );
+
+    test_executable_instruction!(
+        test_list_lift__negative_length =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::I32,
+                },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(-1)],
+            instance: Instance::new(),
+            error: "`list.lift i32` attempted to convert `length` but it appears to be a negative value\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_list_lift__invalid_element =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ListLift {
+                    element_type: InterfaceType::String,
+                },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::I32(1)],
+            instance: Instance::new(),
+            error: "`end` the block expects values of kind `[String]` but it received values of kind `[I32]`\n  at instruction 2",
+    );
+
+    test_executable_instruction!(
+        test_for_each__empty =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ForEach {
+                    element_type: InterfaceType::I32,
+                },
+                Instruction::S8FromI32,
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::List(InterfaceType::I32, vec![])],
+            instance: Instance::new(),
+            stack: [],
+    );
+
+    test_executable_instruction!(
+        test_for_each__invalid_value_on_the_stack =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ForEach {
+                    element_type: InterfaceType::I32,
+                },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::List(InterfaceType::S8, vec![])],
+            instance: Instance::new(),
+            error: "`for-each i32` read a value of type `List(S8)` from the stack, but the type `List(I32)` was expected\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_for_each__invalid_element =
+            instructions: [
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ForEach {
+                    element_type: InterfaceType::I32,
+                },
+                Instruction::End,
+            ],
+            invocation_inputs: [InterfaceValue::List(
+                InterfaceType::I32,
+                vec![InterfaceValue::S8(1)],
+            )],
+            instance: Instance::new(),
+            error: "`for-each i32` the block expects values of kind `[I32]` but it received values of kind `[S8]`\n  at instruction 1",
+    );
+
+    test_executable_instruction!(
+        test_for_each__body_consumes_an_outer_value =
+            instructions: [
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ForEach {
+                    element_type: InterfaceType::I32,
+                },
+                Instruction::CallCore { function_index: 42 },
+                //                                      ^^ the accumulator is below the block
+                Instruction::End,
+            ],
+            invocation_inputs: [
+                InterfaceValue::List(InterfaceType::I32, vec![InterfaceValue::I32(1)]),
+                InterfaceValue::I32(0),
+            ],
+            instance: Instance::new(),
+            error: "`call-core 42` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 3",
+    );
+
+    #[test]
+    fn test_for_each() {
+        use crate::interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            stack::Stackable,
+            Instruction, Interpreter,
+        };
+        use crate::{types::InterfaceType, values::InterfaceValue};
+        use std::convert::TryInto;
+
+        static SUM: AtomicI32 = AtomicI32::new(0);
+
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ForEach {
+                element_type: InterfaceType::I32,
+            },
+            Instruction::CallCore { function_index: 44 },
+            Instruction::End,
+        ])
+            .try_into()
+            .unwrap();
+
+        let mut instance = Instance::new();
+        instance.locals_or_imports.insert(
+            44,
+            LocalImport {
+                inputs: vec![InterfaceType::I32],
+                outputs: vec![],
+                function: |arguments: &[InterfaceValue]| {
+                    let value: i32 = (&arguments[0]).try_into().unwrap();
+                    SUM.fetch_add(value, Ordering::SeqCst);
+
+                    Ok(vec![])
+                },
+            },
+        );
+
+        let invocation_inputs = vec![InterfaceValue::List(
+            InterfaceType::I32,
+            vec![
+                InterfaceValue::I32(1),
+                InterfaceValue::I32(2),
+                InterfaceValue::I32(3),
+            ],
+        )];
+        let stack = interpreter.run(&invocation_inputs, &mut instance).unwrap();
+
+        assert!(stack.is_empty());
+        assert_eq!(SUM.load(Ordering::SeqCst), 6);
+    }
+}
@@ -1,22 +1,35 @@
 mod argument_get;
+mod call_adapter;
 mod call_core;
+mod call_export;
+mod control_flow;
+mod defer_call_core;
+mod lists;
 mod numbers;
 mod records;
 mod strings;
 
 use crate::{
     errors::{InstructionError, InstructionErrorKind, InstructionResult, WasmValueNativeCastError},
+    types::InterfaceType,
     values::{InterfaceValue, NativeType},
 };
 pub(crate) use argument_get::argument_get;
-pub(crate) use call_core::call_core;
+pub(crate) use call_adapter::{
+    call_adapter, describe_adapter, resolve_adapter, resolved_call_adapter,
+};
+pub(crate) use call_core::{async_call_core, call_core, check_outputs, resolved_call_core};
+pub(crate) use call_export::call_export;
+pub(crate) use control_flow::*;
+pub(crate) use defer_call_core::defer_call_core;
+pub(crate) use lists::*;
 pub(crate) use numbers::*;
 pub(crate) use records::*;
 use std::convert::TryFrom;
 pub(crate) use strings::*;
 
 /// Represents all the possible WIT instructions.
-#[derive(PartialEq, Debug, Clone, Copy)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Instruction {
     /// The `arg.get` instruction.
     ArgumentGet {
@@ -146,30 +159,118 @@ pub enum Instruction {
         /// The type index of the record.
         type_index: u32,
     },
+
+    /// The `call-adapter` instruction.
+    CallAdapter {
+        /// The adapter index.
+        adapter_index: u32,
+    },
+
+    /// The `call-export` instruction.
+    CallExport {
+        /// The exported function name.
+        export_name: String,
+    },
+
+    /// The `defer-call-core` instruction.
+    DeferCallCore {
+        /// The function index.
+        function_index: u32,
+    },
+
+    /// The `block` instruction.
+    Block {
+        /// The block signature.
+        block_type: BlockType,
+    },
+
+    /// The `if` instruction.
+    If {
+        /// The block signature.
+        block_type: BlockType,
+    },
+
+    /// The `else` instruction.
+    Else,
+
+    /// The `end` instruction.
+    End,
+
+    /// The `br_if` instruction.
+    BrIf {
+        /// The depth of the targeted block, relatively to the
+        /// innermost block (0 being the innermost block).
+        relative_depth: u32,
+    },
+
+    /// The `list.lift` instruction.
+    ListLift {
+        /// The type of the list elements.
+        element_type: InterfaceType,
+    },
+
+    /// The `for-each` instruction.
+    ForEach {
+        /// The type of the list elements.
+        element_type: InterfaceType,
+    },
+}
+
+/// Represents the signature of a block, i.e. the types of the values
+/// a block reads from the stack, and the types of the values it
+/// leaves on the stack.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct BlockType {
+    /// Types of the values read by the block.
+    pub inputs: Vec<InterfaceType>,
+
+    /// Types of the values left by the block.
+    pub outputs: Vec<InterfaceType>,
 }
 
 /// Just a short helper to map the error of a cast from an
 /// `InterfaceValue` to a native value.
 pub(crate) fn to_native<'a, T>(
     wit_value: &'a InterfaceValue,
-    instruction: Instruction,
+    instruction: &Instruction,
 ) -> InstructionResult<T>
 where
     T: NativeType + TryFrom<&'a InterfaceValue, Error = WasmValueNativeCastError>,
 {
-    T::try_from(wit_value)
-        .map_err(|error| InstructionError::new(instruction, InstructionErrorKind::ToNative(error)))
+    T::try_from(wit_value).map_err(|error| {
+        InstructionError::new(instruction.clone(), InstructionErrorKind::ToNative(error))
+    })
+}
+
+/// Checks that the values are of the given types, without collecting
+/// the types of the values.
+pub(crate) fn values_are_of_types(values: &[InterfaceValue], types: &[InterfaceType]) -> bool {
+    values.len() == types.len()
+        && values
+            .iter()
+            .zip(types)
+            .all(|(value, ty)| &InterfaceType::from(value) == ty)
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::{ast::*, interpreter::wasm, types::*, values::*};
-    use std::{cell::Cell, collections::HashMap, convert::TryInto, ops::Deref, rc::Rc};
+    use crate::{ast::*, errors::HostError, interpreter::wasm, types::*, values::*};
+    use std::{
+        cell::Cell,
+        collections::HashMap,
+        convert::TryInto,
+        future::Future,
+        ops::Deref,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll},
+    };
 
     pub(crate) struct Export {
         pub(crate) inputs: Vec<InterfaceType>,
         pub(crate) outputs: Vec<InterfaceType>,
-        pub(crate) function: fn(arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()>,
+        pub(crate) function:
+            fn(arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError>,
     }
 
     impl wasm::structures::Export for Export {
@@ -189,7 +290,7 @@ pub(crate) mod tests {
             &self.outputs
         }
 
-        fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()> {
+        fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> {
             (self.function)(arguments)
         }
     }
@@ -197,7 +298,8 @@ pub(crate) mod tests {
     pub(crate) struct LocalImport {
         pub(crate) inputs: Vec<InterfaceType>,
         pub(crate) outputs: Vec<InterfaceType>,
-        pub(crate) function: fn(arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()>,
+        pub(crate) function:
+            fn(arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError>,
     }
 
     impl wasm::structures::LocalImport for LocalImport {
@@ -217,11 +319,53 @@ pub(crate) mod tests {
             &self.outputs
         }
 
-        fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()> {
+        fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> {
             (self.function)(arguments)
         }
     }
 
+    pub(crate) struct AsyncLocalImport {
+        pub(crate) inputs: Vec<InterfaceType>,
+        pub(crate) outputs: Vec<InterfaceType>,
+        pub(crate) function:
+            fn(arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError>,
+    }
+
+    /// A future that is pending once before being ready, like an I/O.
+    struct YieldOnce(Option<Result<Vec<InterfaceValue>, HostError>>, bool);
+
+    impl Future for YieldOnce {
+        type Output = Result<Vec<InterfaceValue>, HostError>;
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+            if self.1 {
+                Poll::Ready(self.0.take().unwrap())
+            } else {
+                self.1 = true;
+                context.waker().wake_by_ref();
+
+                Poll::Pending
+            }
+        }
+    }
+
+    impl wasm::structures::AsyncLocalImport for AsyncLocalImport {
+        fn inputs(&self) -> &[InterfaceType] {
+            &self.inputs
+        }
+
+        fn outputs(&self) -> &[InterfaceType] {
+            &self.outputs
+        }
+
+        fn call<'call>(
+            &'call self,
+            arguments: &'call [InterfaceValue],
+        ) -> wasm::structures::LocalImportFuture<'call> {
+            Box::pin(YieldOnce(Some((self.function)(arguments)), false))
+        }
+    }
+
     #[derive(Default, Clone)]
     pub(crate) struct MemoryView(Rc<Vec<Cell<u8>>>);
 
@@ -258,8 +402,11 @@ pub(crate) mod tests {
     pub(crate) struct Instance {
         pub(crate) exports: HashMap<String, Export>,
         pub(crate) locals_or_imports: HashMap<usize, LocalImport>,
+        pub(crate) async_locals_or_imports: HashMap<usize, AsyncLocalImport>,
         pub(crate) memory: Memory,
         pub(crate) wit_types: Vec<Type>,
+        pub(crate) wit_adapters: Vec<Adapter>,
+        pub(crate) wit_export_names: HashMap<u32, String>,
     }
 
     impl Instance {
@@ -315,6 +462,7 @@ pub(crate) mod tests {
 
                     hashmap
                 },
+                async_locals_or_imports: HashMap::new(),
                 memory: Memory::new(vec![Cell::new(0); 128]),
                 wit_types: vec![Type::Record(RecordType {
                     fields: vec1![
@@ -325,6 +473,8 @@ pub(crate) mod tests {
                         InterfaceType::I64,
                     ],
                 })],
+                wit_adapters: vec![],
+                wit_export_names: HashMap::new(),
             }
         }
     }
@@ -341,6 +491,17 @@ pub(crate) mod tests {
             self.locals_or_imports.get(&index.index())
         }
 
+        fn async_local_or_import<
+            I: wasm::structures::TypedIndex + wasm::structures::LocalImportIndex,
+        >(
+            &mut self,
+            index: I,
+        ) -> Option<&dyn wasm::structures::AsyncLocalImport> {
+            self.async_locals_or_imports
+                .get(&index.index())
+                .map(|local_or_import| local_or_import as &dyn wasm::structures::AsyncLocalImport)
+        }
+
         fn memory(&self, _index: usize) -> Option<&Memory> {
             Some(&self.memory)
         }
@@ -348,5 +509,15 @@ pub(crate) mod tests {
         fn wit_type(&self, index: u32) -> Option<&Type> {
             self.wit_types.get(index as usize)
         }
+
+        fn wit_adapter(&self, index: u32) -> Option<&Adapter> {
+            self.wit_adapters.get(index as usize)
+        }
+
+        fn wit_export_name(&self, adapter_index: u32) -> Option<&str> {
+            self.wit_export_names
+                .get(&adapter_index)
+                .map(String::as_str)
+        }
     }
 }
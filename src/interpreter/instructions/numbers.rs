@@ -9,45 +9,43 @@ use std::convert::TryInto;
 macro_rules! lowering_lifting {
     ($instruction_function_name:ident, $instruction_name:expr, $to_variant:ident, $from_variant:ident) => {
         executable_instruction!(
-            $instruction_function_name(instruction: Instruction) -> _ {
-                move |runtime| -> _ {
-                    match runtime.stack.pop1() {
-                        Some(InterfaceValue::$from_variant(value)) => {
-                            runtime
-                                .stack
-                                .push(InterfaceValue::$to_variant(value.try_into().map_err(
-                                    |_| {
-                                        InstructionError::new(
-                                            instruction,
-                                            InstructionErrorKind::LoweringLifting {
-                                                from: InterfaceType::$from_variant,
-                                                to: InterfaceType::$to_variant
-                                            },
-                                        )
-                                    },
-                                )?))
-                        }
-
-                        Some(wrong_value) => {
-                            return Err(InstructionError::new(
-                                instruction,
-                                InstructionErrorKind::InvalidValueOnTheStack {
-                                    expected_type: InterfaceType::$from_variant,
-                                    received_type: (&wrong_value).into(),
-                                }
-                            ))
-                        },
-
-                        None => {
-                            return Err(InstructionError::new(
-                                instruction,
-                                InstructionErrorKind::StackIsTooSmall { needed: 1 },
-                            ))
-                        }
+            $instruction_function_name(runtime, instruction: &Instruction) {
+                match runtime.stack.pop1() {
+                    Some(InterfaceValue::$from_variant(value)) => {
+                        runtime
+                            .stack
+                            .push(InterfaceValue::$to_variant(value.try_into().map_err(
+                                |_| {
+                                    InstructionError::new(
+                                        instruction.clone(),
+                                        InstructionErrorKind::LoweringLifting {
+                                            from: InterfaceType::$from_variant,
+                                            to: InterfaceType::$to_variant
+                                        },
+                                    )
+                                },
+                            )?))
                     }
 
-                    Ok(())
+                    Some(wrong_value) => {
+                        return Err(InstructionError::new(
+                            instruction.clone(),
+                            InstructionErrorKind::InvalidValueOnTheStack {
+                                expected_type: InterfaceType::$from_variant,
+                                received_type: (&wrong_value).into(),
+                            }
+                        ))
+                    },
+
+                    None => {
+                        return Err(InstructionError::new(
+                            instruction.clone(),
+                            InstructionErrorKind::StackIsTooSmall { needed: 1 },
+                        ))
+                    }
                 }
+
+                Ok(())
             }
         );
     };
@@ -93,7 +91,7 @@ mod tests {
             instructions: [Instruction::ArgumentGet { index: 0}, Instruction::S8FromI32],
             invocation_inputs: [InterfaceValue::I32(128)],
             instance: Instance::new(),
-            error: "`s8.from_i32` failed to cast `I32` to `S8`"
+            error: "`s8.from_i32` failed to cast `I32` to `S8`\n  at instruction 1"
     );
 
     test_executable_instruction!(
@@ -101,7 +99,7 @@ mod tests {
             instructions: [Instruction::ArgumentGet { index: 0}, Instruction::S8FromI32],
             invocation_inputs: [InterfaceValue::I64(42)],
             instance: Instance::new(),
-            error: "`s8.from_i32` read a value of type `I64` from the stack, but the type `I32` was expected"
+            error: "`s8.from_i32` read a value of type `I64` from the stack, but the type `I32` was expected\n  at instruction 1"
     );
 
     test_executable_instruction!(
@@ -109,7 +107,7 @@ mod tests {
             instructions: [Instruction::S8FromI32],
             invocation_inputs: [InterfaceValue::I32(42)],
             instance: Instance::new(),
-            error: "`s8.from_i32` needed to read `1` value(s) from the stack, but it doesn't contain enough data"
+            error: "`s8.from_i32` needed to read `1` value(s) from the stack, but it doesn't contain enough data\n  at instruction 0"
     );
 
     test_executable_instruction!(
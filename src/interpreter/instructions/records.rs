@@ -1,15 +1,15 @@
 use crate::{
     ast::{Type, TypeKind},
-    errors::{InstructionError, InstructionErrorKind},
+    errors::{InstructionError, InstructionErrorKind, InstructionResult},
     interpreter::{
         stack::{Stack, Stackable},
-        Instruction,
+        wasm, Instruction,
     },
     types::{InterfaceType, RecordType},
     values::{FlattenInterfaceValueIterator, InterfaceValue},
     vec1::Vec1,
 };
-use std::collections::VecDeque;
+use std::{collections::VecDeque, slice};
 
 /// Build an `InterfaceValue::Record` based on values on the stack.
 ///
@@ -63,81 +63,107 @@ fn record_lift_(
     ))
 }
 
-executable_instruction!(
-    record_lift(type_index: u32, instruction: Instruction) -> _ {
-        move |runtime| -> _ {
-            let instance = &runtime.wasm_instance;
-            let record_type = match instance.wit_type(type_index).ok_or_else(|| {
-                InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::TypeIsMissing { type_index },
-                )
-            })? {
-                Type::Record(record_type) => record_type,
-                Type::Function { .. } => return Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::InvalidTypeKind {
-                        expected_kind: TypeKind::Record,
-                        received_kind: TypeKind::Function
-                    }
-                )),
-            };
-
-            let record = record_lift_(&mut runtime.stack, &record_type)
-                .map_err(|k| InstructionError::new(instruction, k))?;
-
-            runtime.stack.push(record);
+/// Lowers the record on top of the stack into its flattened values.
+fn record_lower_(
+    stack: &mut Stack<InterfaceValue>,
+    record_type: &RecordType,
+) -> Result<(), InstructionErrorKind> {
+    match stack.pop1() {
+        Some(InterfaceValue::Record(record_values)) if record_type == &(&*record_values).into() => {
+            let values = FlattenInterfaceValueIterator::new(&record_values);
+
+            for value in values {
+                stack.push(value.clone());
+            }
 
             Ok(())
         }
+
+        Some(value) => Err(InstructionErrorKind::InvalidValueOnTheStack {
+            expected_type: InterfaceType::Record(record_type.clone()),
+            received_type: (&value).into(),
+        }),
+
+        None => Err(InstructionErrorKind::StackIsTooSmall { needed: 1 }),
+    }
+}
+
+/// Reads the record type at `type_index` from the WebAssembly
+/// instance.
+pub(crate) fn resolve_record_type<'instance, Instance, Export, LocalImport, Memory, MemoryView>(
+    instance: &'instance Instance,
+    type_index: u32,
+    instruction: &Instruction,
+) -> InstructionResult<&'instance RecordType>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    match instance.wit_type(type_index).ok_or_else(|| {
+        InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::TypeIsMissing { type_index },
+        )
+    })? {
+        Type::Record(record_type) => Ok(record_type),
+        Type::Function { .. } => Err(InstructionError::new(
+            instruction.clone(),
+            InstructionErrorKind::InvalidTypeKind {
+                expected_kind: TypeKind::Record,
+                received_kind: TypeKind::Function,
+            },
+        )),
+    }
+}
+
+executable_instruction!(
+    record_lift(runtime, type_index: u32, instruction: &Instruction) {
+        let record_type = resolve_record_type(&*runtime.wasm_instance, type_index, instruction)?;
+
+        let record = record_lift_(&mut runtime.stack, record_type)
+            .map_err(|k| InstructionError::new(instruction.clone(), k))?;
+
+        runtime.copy_lifted_values(
+            FlattenInterfaceValueIterator::new(slice::from_ref(&record)),
+            instruction,
+        )?;
+        runtime.stack.push(record);
+
+        Ok(())
     }
 );
 
 executable_instruction!(
-    record_lower(type_index: u32, instruction: Instruction) -> _ {
-        move |runtime| -> _ {
-            let instance = &runtime.wasm_instance;
-            let record_type = match instance.wit_type(type_index).ok_or_else(|| {
-                InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::TypeIsMissing { type_index },
-                )
-            })? {
-                Type::Record(record_type) => record_type,
-                Type::Function { .. } => return Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::InvalidTypeKind {
-                        expected_kind: TypeKind::Record,
-                        received_kind: TypeKind::Function
-                    }
-                )),
-            };
-
-            match runtime.stack.pop1() {
-                Some(InterfaceValue::Record(record_values)) if record_type == &(&*record_values).into() => {
-                    let values = FlattenInterfaceValueIterator::new(&record_values);
-
-                    for value in values {
-                        runtime.stack.push(value.clone());
-                    }
-
-                    Ok(())
-                },
+    resolved_record_lift(runtime, record_type: &RecordType, instruction: &Instruction) {
+        let record = record_lift_(&mut runtime.stack, record_type)
+            .map_err(|k| InstructionError::new(instruction.clone(), k))?;
 
-                Some(value) => Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::InvalidValueOnTheStack {
-                        expected_type: InterfaceType::Record(record_type.clone()),
-                        received_type: (&value).into(),
-                    }
-                )),
-
-                None => Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::StackIsTooSmall { needed: 1 },
-                )),
-            }
-        }
+        runtime.copy_lifted_values(
+            FlattenInterfaceValueIterator::new(slice::from_ref(&record)),
+            instruction,
+        )?;
+        runtime.stack.push(record);
+
+        Ok(())
+    }
+);
+
+executable_instruction!(
+    record_lower(runtime, type_index: u32, instruction: &Instruction) {
+        let record_type = resolve_record_type(&*runtime.wasm_instance, type_index, instruction)?;
+
+        record_lower_(&mut runtime.stack, record_type)
+            .map_err(|k| InstructionError::new(instruction.clone(), k))
+    }
+);
+
+executable_instruction!(
+    resolved_record_lower(runtime, record_type: &RecordType, instruction: &Instruction) {
+        record_lower_(&mut runtime.stack, record_type)
+            .map_err(|k| InstructionError::new(instruction.clone(), k))
     }
 );
 
@@ -272,7 +298,7 @@ mod tests {
             ],
             invocation_inputs: [],
             instance: Default::default(),
-            error: r#"`record.lift 0` the type `0` doesn't exist"#,
+            error: "`record.lift 0` the type `0` doesn't exist\n  at instruction 0",
     );
 
     test_executable_instruction!(
@@ -292,7 +318,7 @@ mod tests {
                 InterfaceValue::I64(3),
             ],
             instance: Instance::new(),
-            error: r#"`record.lift 0` read a value of type `F64` from the stack, but the type `F32` was expected"#,
+            error: "`record.lift 0` read a value of type `F64` from the stack, but the type `F32` was expected\n  at instruction 4",
     );
 
     test_executable_instruction!(
@@ -360,7 +386,7 @@ mod tests {
                 InterfaceValue::I32(1),
             ],
             instance: Instance::new(),
-            error: r#"`record.lower 0` read a value of type `I32` from the stack, but the type `Record(RecordType { fields: [I32, Record(RecordType { fields: [String, F32] }), I64] })` was expected"#,
+            error: "`record.lower 0` read a value of type `I32` from the stack, but the type `Record(RecordType { fields: [I32, Record(RecordType { fields: [String, F32] }), I64] })` was expected\n  at instruction 1",
     );
 
     test_executable_instruction!(
@@ -379,6 +405,6 @@ mod tests {
                 ])
             ],
             instance: Instance::new(),
-            error: r#"`record.lower 0` read a value of type `Record(RecordType { fields: [I32, Record(RecordType { fields: [String] }), I64] })` from the stack, but the type `Record(RecordType { fields: [I32, Record(RecordType { fields: [String, F32] }), I64] })` was expected"#,
+            error: "`record.lower 0` read a value of type `Record(RecordType { fields: [I32, Record(RecordType { fields: [String] }), I64] })` from the stack, but the type `Record(RecordType { fields: [I32, Record(RecordType { fields: [String, F32] }), I64] })` was expected\n  at instruction 1",
     );
 }
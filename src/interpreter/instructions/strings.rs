@@ -5,141 +5,157 @@ use crate::{
     types::InterfaceType,
     values::InterfaceValue,
 };
-use std::{cell::Cell, convert::TryInto};
+use std::{cell::Cell, convert::TryInto, time::Instant};
 
 executable_instruction!(
-    string_lift_memory(instruction: Instruction) -> _ {
-        move |runtime| -> _ {
-            let inputs = runtime.stack.pop(2).ok_or_else(|| {
+    string_lift_memory(runtime, instruction: &Instruction) {
+        if !runtime.stack.pop_into(2, &mut runtime.arguments) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall { needed: 2 },
+            ));
+        }
+
+        let inputs = &runtime.arguments;
+
+        let memory_index: u32 = 0;
+        let memory = runtime
+            .wasm_instance
+            .memory(memory_index as usize)
+            .ok_or_else(|| {
                 InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::StackIsTooSmall { needed: 2 },
+                    instruction.clone(),
+                    InstructionErrorKind::MemoryIsMissing { memory_index },
                 )
             })?;
 
-            let memory_index: u32 = 0;
-            let memory = runtime
-                .wasm_instance
-                .memory(memory_index as usize)
-                .ok_or_else(|| {
-                    InstructionError::new(
-                        instruction,
-                        InstructionErrorKind::MemoryIsMissing { memory_index },
-                    )
-                })?;
-
-            let pointer: usize = to_native::<i32>(&inputs[0], instruction)?
-                .try_into()
-                .map_err(|e| (e, "pointer").into())
-                .map_err(|k| InstructionError::new(instruction, k))?;
-            let length: usize = to_native::<i32>(&inputs[1], instruction)?
-                .try_into()
-                .map_err(|e| (e, "length").into())
-                .map_err(|k| InstructionError::new(instruction, k))?;
-            let memory_view = memory.view();
-
-            if length == 0 {
-                runtime.stack.push(InterfaceValue::String("".into()));
-
-                return Ok(())
-            }
-
-            if memory_view.len() <= pointer + length - 1 {
-                return Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::MemoryOutOfBoundsAccess {
-                        index: pointer + length,
-                        length: memory_view.len(),
-                    },
-                ));
-            }
-
-            let data: Vec<u8> = (&memory_view[pointer..=pointer + length - 1])
-                .iter()
-                .map(Cell::get)
-                .collect();
-
-            let string = String::from_utf8(data)
-                .map_err(|error| InstructionError::new(instruction, InstructionErrorKind::String(error)))?;
-
-            runtime.stack.push(InterfaceValue::String(string));
-
-            Ok(())
+        let pointer: usize = to_native::<i32>(&inputs[0], instruction)?
+            .try_into()
+            .map_err(|e| (e, "pointer").into())
+            .map_err(|k| InstructionError::new(instruction.clone(), k))?;
+        let length: usize = to_native::<i32>(&inputs[1], instruction)?
+            .try_into()
+            .map_err(|e| (e, "length").into())
+            .map_err(|k| InstructionError::new(instruction.clone(), k))?;
+        let memory_view = memory.view();
+
+        if length == 0 {
+            runtime.stack.push(InterfaceValue::String("".into()));
+
+            return Ok(())
+        }
+
+        runtime.check_string_length(length, instruction)?;
+
+        if memory_view.len() <= pointer + length - 1 {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::MemoryOutOfBoundsAccess {
+                    index: pointer + length,
+                    length: memory_view.len(),
+                },
+            ));
+        }
+
+        runtime.copy_memory_bytes(length, instruction)?;
+
+        // The copy and the validation are timed for the observer only.
+        let copy_start = runtime.observer.as_ref().map(|_| Instant::now());
+
+        let data: Vec<u8> = (&memory_view[pointer..=pointer + length - 1])
+            .iter()
+            .map(Cell::get)
+            .collect();
+
+        let validation_start = copy_start.map(|_| Instant::now());
+
+        let string = String::from_utf8(data)
+            .map_err(|error| InstructionError::new(instruction.clone(), InstructionErrorKind::String(error)))?;
+
+        if let (Some(observer), Some(copy_start), Some(validation_start)) =
+            (runtime.observer.as_deref_mut(), copy_start, validation_start)
+        {
+            observer.string_lifted(length, validation_start - copy_start, validation_start.elapsed());
         }
+
+        runtime.stack.push(InterfaceValue::String(string));
+
+        Ok(())
     }
 );
 
 executable_instruction!(
-    string_lower_memory(instruction: Instruction) -> _ {
-        move |runtime| -> _ {
-            let inputs = runtime.stack.pop(2).ok_or_else(|| {
-                InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::StackIsTooSmall { needed: 2 },
-                )
-            })?;
+    string_lower_memory(runtime, instruction: &Instruction) {
+        if !runtime.stack.pop_into(2, &mut runtime.arguments) {
+            return Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall { needed: 2 },
+            ));
+        }
 
-            let string_pointer: usize = to_native::<i32>(&inputs[0], instruction)?
-                .try_into()
-                .map_err(|e| (e, "pointer").into())
-                .map_err(|k| InstructionError::new(instruction, k))?;
-            let string: String = to_native(&inputs[1], instruction)?;
-            let string_bytes = string.as_bytes();
-            let string_length: i32 = string_bytes.len().try_into().map_err(|_| {
+        let inputs = &runtime.arguments;
+
+        let string_pointer: usize = to_native::<i32>(&inputs[0], instruction)?
+            .try_into()
+            .map_err(|e| (e, "pointer").into())
+            .map_err(|k| InstructionError::new(instruction.clone(), k))?;
+        let string: String = to_native(&inputs[1], instruction)?;
+        let string_bytes = string.as_bytes();
+
+        runtime.check_string_length(string_bytes.len(), instruction)?;
+
+        let string_length: i32 = string_bytes.len().try_into().map_err(|_| {
+            InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::NegativeValue { subject: "string_length" },
+            )
+        })?;
+
+        let instance = &mut runtime.wasm_instance;
+        let memory_index: u32 = 0;
+        let memory_view = instance
+            .memory(memory_index as usize)
+            .ok_or_else(|| {
                 InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::NegativeValue { subject: "string_length" },
+                    instruction.clone(),
+                    InstructionErrorKind::MemoryIsMissing { memory_index },
                 )
-            })?;
+            })?
+            .view();
 
-            let instance = &mut runtime.wasm_instance;
-            let memory_index: u32 = 0;
-            let memory_view = instance
-                .memory(memory_index as usize)
-                .ok_or_else(|| {
-                    InstructionError::new(
-                        instruction,
-                        InstructionErrorKind::MemoryIsMissing { memory_index },
-                    )
-                })?
-                .view();
-
-            for (nth, byte) in string_bytes.iter().enumerate() {
-                memory_view[string_pointer as usize + nth].set(*byte);
-            }
-
-            runtime.stack.push(InterfaceValue::I32(string_pointer as i32));
-            runtime.stack.push(InterfaceValue::I32(string_length));
-
-            Ok(())
+        for (nth, byte) in string_bytes.iter().enumerate() {
+            memory_view[string_pointer as usize + nth].set(*byte);
         }
+
+        runtime.stack.push(InterfaceValue::I32(string_pointer as i32));
+        runtime.stack.push(InterfaceValue::I32(string_length));
+
+        Ok(())
     }
 );
 
 executable_instruction!(
-    string_size(instruction: Instruction) -> _ {
-        move |runtime| -> _ {
-            match runtime.stack.pop1() {
-                Some(InterfaceValue::String(string)) => {
-                    let length = string.len() as i32;
-                    runtime.stack.push(InterfaceValue::I32(length));
-
-                    Ok(())
+    string_size(runtime, instruction: &Instruction) {
+        match runtime.stack.pop1() {
+            Some(InterfaceValue::String(string)) => {
+                let length = string.len() as i32;
+                runtime.stack.push(InterfaceValue::I32(length));
+
+                Ok(())
+            },
+
+            Some(value) => Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::InvalidValueOnTheStack {
+                    expected_type: InterfaceType::String,
+                    received_type: (&value).into(),
                 },
+            )),
 
-                Some(value) => Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::InvalidValueOnTheStack {
-                        expected_type: InterfaceType::String,
-                        received_type: (&value).into(),
-                    },
-                )),
-
-                None => Err(InstructionError::new(
-                    instruction,
-                    InstructionErrorKind::StackIsTooSmall { needed: 1 },
-                )),
-            }
+            None => Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StackIsTooSmall { needed: 1 },
+            )),
         }
     }
 );
@@ -199,7 +215,7 @@ mod tests {
                 memory: Memory::new("Hello!".as_bytes().iter().map(|u| Cell::new(*u)).collect()),
                 ..Default::default()
             },
-            error: r#"`string.lift_memory` attempted to convert `pointer` but it appears to be a negative value"#,
+            error: "`string.lift_memory` attempted to convert `pointer` but it appears to be a negative value\n  at instruction 2",
     );
 
     test_executable_instruction!(
@@ -217,7 +233,7 @@ mod tests {
                 memory: Memory::new("Hello!".as_bytes().iter().map(|u| Cell::new(*u)).collect()),
                 ..Default::default()
             },
-            error: r#"`string.lift_memory` attempted to convert `length` but it appears to be a negative value"#,
+            error: "`string.lift_memory` attempted to convert `length` but it appears to be a negative value\n  at instruction 2",
     );
 
     test_executable_instruction!(
@@ -237,7 +253,7 @@ mod tests {
                 memory: Memory::new("Hello!".as_bytes().iter().map(|u| Cell::new(*u)).collect()),
                 ..Default::default()
             },
-            error: r#"`string.lift_memory` read out of the memory bounds (index 13 > memory length 6)"#,
+            error: "`string.lift_memory` read out of the memory bounds (index 13 > memory length 6)\n  at instruction 2",
     );
 
     test_executable_instruction!(
@@ -257,7 +273,7 @@ mod tests {
                 memory: Memory::new(vec![0, 159, 146, 150].iter().map(|b| Cell::new(*b)).collect::<Vec<Cell<u8>>>()),
                 ..Default::default()
             },
-            error: r#"`string.lift_memory` invalid utf-8 sequence of 1 bytes from index 1"#,
+            error: "`string.lift_memory` invalid utf-8 sequence of 1 bytes from index 1\n  at instruction 2",
     );
 
     test_executable_instruction!(
@@ -272,7 +288,7 @@ mod tests {
                 InterfaceValue::I32(13),
             ],
             instance: Instance::new(),
-            error: r#"`string.lift_memory` needed to read `2` value(s) from the stack, but it doesn't contain enough data"#,
+            error: "`string.lift_memory` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 1",
     );
 
     test_executable_instruction!(
@@ -317,7 +333,7 @@ mod tests {
             ],
             invocation_inputs: [],
             instance: Instance::new(),
-            error: r#"`string.lower_memory` needed to read `2` value(s) from the stack, but it doesn't contain enough data"#,
+            error: "`string.lower_memory` needed to read `2` value(s) from the stack, but it doesn't contain enough data\n  at instruction 0",
     );
 
     test_executable_instruction!(
@@ -338,7 +354,7 @@ mod tests {
             ],
             invocation_inputs: [],
             instance: Instance::new(),
-            error: r#"`string.size` needed to read `1` value(s) from the stack, but it doesn't contain enough data"#,
+            error: "`string.size` needed to read `1` value(s) from the stack, but it doesn't contain enough data\n  at instruction 0",
     );
 
     test_executable_instruction!(
@@ -349,6 +365,6 @@ mod tests {
             ],
             invocation_inputs: [InterfaceValue::I32(42)],
             instance: Instance::new(),
-            error: r#"`string.size` read a value of type `I32` from the stack, but the type `String` was expected"#,
+            error: "`string.size` read a value of type `I32` from the stack, but the type `String` was expected\n  at instruction 1",
     );
 }
@@ -0,0 +1,455 @@
+//! Limits bound the cost of running an adapter, which may come from
+//! an untrusted module.
+
+use super::{stack::Stackable, wasm, Instruction, Runtime};
+use crate::{
+    errors::{InstructionError, InstructionErrorKind, InstructionResult},
+    values::InterfaceValue,
+};
+
+/// Represents the limits of an interpreter run. A limit set to `None`
+/// is not enforced, which is the default.
+///
+/// # Example
+///
+/// ```rust
+/// use wasmer_interface_types::interpreter::Limits;
+///
+/// let limits = Limits {
+///     fuel: Some(10_000),
+///     max_string_length: Some(1024),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// The maximum number of instructions to execute, including the
+    /// instructions of the adapters called with `call-adapter`.
+    pub fuel: Option<u64>,
+
+    /// The maximum number of values on the stack of an adapter.
+    pub max_stack_depth: Option<usize>,
+
+    /// The maximum number of bytes copied out of the memory, including
+    /// by the called adapters: the bytes of the strings lifted by
+    /// `string.lift_memory`, and the bytes of the scalar values lifted
+    /// into records by `record.lift` and into lists by `list.lift`
+    /// (e.g. 4 bytes per `i32`).
+    pub max_memory_bytes: Option<usize>,
+
+    /// The maximum length of a string, in bytes, lifted from or
+    /// lowered into the memory.
+    pub max_string_length: Option<usize>,
+}
+
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Consumes one unit of fuel to execute an instruction.
+    pub(super) fn consume_fuel(&mut self, instruction: &Instruction) -> InstructionResult<()> {
+        if let Some(remaining_fuel) = self.remaining_fuel.as_mut() {
+            if *remaining_fuel == 0 {
+                return Err(InstructionError::new(
+                    instruction.clone(),
+                    InstructionErrorKind::FuelExhausted {
+                        fuel: self.limits.fuel.unwrap_or(0),
+                    },
+                ));
+            }
+
+            *remaining_fuel -= 1;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the stack doesn't contain too many values.
+    pub(super) fn check_stack_depth(&self, instruction: &Instruction) -> InstructionResult<()> {
+        match self.limits.max_stack_depth {
+            Some(max_stack_depth) if self.stack.as_slice().len() > max_stack_depth => {
+                Err(InstructionError::new(
+                    instruction.clone(),
+                    InstructionErrorKind::StackDepthExceeded { max_stack_depth },
+                ))
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that a string of `length` bytes isn't too long.
+    pub(super) fn check_string_length(
+        &self,
+        length: usize,
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        match self.limits.max_string_length {
+            Some(max_string_length) if length > max_string_length => Err(InstructionError::new(
+                instruction.clone(),
+                InstructionErrorKind::StringLengthExceeded {
+                    length,
+                    max_string_length,
+                },
+            )),
+
+            _ => Ok(()),
+        }
+    }
+
+    /// Accounts `length` bytes about to be copied out of the memory.
+    pub(super) fn copy_memory_bytes(
+        &mut self,
+        length: usize,
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        let memory_bytes = self.memory_bytes.saturating_add(length);
+
+        match self.limits.max_memory_bytes {
+            Some(max_memory_bytes) if memory_bytes > max_memory_bytes => {
+                Err(InstructionError::new(
+                    instruction.clone(),
+                    InstructionErrorKind::MemoryBytesExceeded { max_memory_bytes },
+                ))
+            }
+
+            _ => {
+                self.memory_bytes = memory_bytes;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Accounts the bytes of the values lifted into a record or a
+    /// list. Only the scalar values are counted: the strings, the
+    /// records and the lists have been counted when they were lifted.
+    pub(super) fn copy_lifted_values<'value>(
+        &mut self,
+        values: impl Iterator<Item = &'value InterfaceValue>,
+        instruction: &Instruction,
+    ) -> InstructionResult<()> {
+        self.copy_memory_bytes(values.map(scalar_size).sum(), instruction)
+    }
+}
+
+/// Returns the size of a scalar value in the memory, in bytes, or 0
+/// if the value isn't a scalar.
+fn scalar_size(value: &InterfaceValue) -> usize {
+    match value {
+        InterfaceValue::S8(_) | InterfaceValue::U8(_) => 1,
+        InterfaceValue::S16(_) | InterfaceValue::U16(_) => 2,
+        InterfaceValue::S32(_)
+        | InterfaceValue::U32(_)
+        | InterfaceValue::I32(_)
+        | InterfaceValue::F32(_) => 4,
+        InterfaceValue::S64(_)
+        | InterfaceValue::U64(_)
+        | InterfaceValue::I64(_)
+        | InterfaceValue::F64(_) => 8,
+        InterfaceValue::String(_) | InterfaceValue::Record(_) | InterfaceValue::List(..) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Limits;
+    use crate::{
+        ast::{Adapter, Type},
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            stack::Stackable,
+            Instruction, Interpreter,
+        },
+        types::InterfaceType,
+        values::InterfaceValue,
+    };
+    use std::convert::TryInto;
+
+    fn run(
+        instructions: Vec<Instruction>,
+        invocation_inputs: &[InterfaceValue],
+        mut instance: Instance,
+        limits: Limits,
+    ) -> Result<Vec<InterfaceValue>, String> {
+        let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> =
+            (&instructions).try_into().unwrap();
+
+        interpreter
+            .with_limits(limits)
+            .run(invocation_inputs, &mut instance)
+            .map(|stack| stack.as_slice().to_vec())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_fuel() {
+        let instructions = vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallCore { function_index: 42 },
+        ];
+        let invocation_inputs = [InterfaceValue::I32(3), InterfaceValue::I32(4)];
+        let limits = |fuel| Limits {
+            fuel: Some(fuel),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            run(
+                instructions.clone(),
+                &invocation_inputs,
+                Instance::new(),
+                limits(3)
+            ),
+            Ok(vec![InterfaceValue::I32(12)]),
+        );
+        assert_eq!(
+            run(instructions, &invocation_inputs, Instance::new(), limits(2)),
+            Err("`call-core 42` exhausted the fuel (2 instructions)\n  at instruction 2".into()),
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_fuel__consumed_by_called_adapters() {
+        let mut instance = Instance::new();
+        instance.wit_types.push(Type::Function {
+            inputs: vec![],
+            outputs: vec![],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![Instruction::CallAdapter { adapter_index: 1 }],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![],
+        });
+
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::CallAdapter { adapter_index: 0 },
+                    Instruction::CallAdapter { adapter_index: 0 },
+                ],
+                &[],
+                instance,
+                Limits {
+                    fuel: Some(3),
+                    ..Default::default()
+                },
+            ),
+            Err("`call-adapter 1` exhausted the fuel (3 instructions)\n  at instruction 0 of adapter 0 (type 1)\n  at instruction 1".into()),
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_fuel__huge_list_lift() {
+        // The indices of the elements are produced on demand, so the
+        // fuel is exhausted before anything huge is allocated.
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ListLift {
+                        element_type: InterfaceType::I32,
+                    },
+                    Instruction::End,
+                ],
+                &[InterfaceValue::I32(i32::MAX)],
+                Instance::new(),
+                Limits {
+                    fuel: Some(100),
+                    ..Default::default()
+                },
+            ),
+            Err("`end` exhausted the fuel (100 instructions)\n  at instruction 2".into()),
+        );
+    }
+
+    #[test]
+    fn test_max_stack_depth() {
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ArgumentGet { index: 0 },
+                ],
+                &[InterfaceValue::I32(1)],
+                Instance::new(),
+                Limits {
+                    max_stack_depth: Some(2),
+                    ..Default::default()
+                },
+            ),
+            Err(
+                "`arg.get 0` reached the maximum stack depth (2 values)\n  at instruction 2".into()
+            ),
+        );
+    }
+
+    #[test]
+    fn test_max_memory_bytes() {
+        let instructions = vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::StringLiftMemory,
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::StringLiftMemory,
+        ];
+        let invocation_inputs = [InterfaceValue::I32(0), InterfaceValue::I32(3)];
+        let limits = |max_memory_bytes| Limits {
+            max_memory_bytes: Some(max_memory_bytes),
+            ..Default::default()
+        };
+
+        assert!(run(
+            instructions.clone(),
+            &invocation_inputs,
+            Instance::new(),
+            limits(6)
+        )
+        .is_ok());
+        assert_eq!(
+            run(instructions, &invocation_inputs, Instance::new(), limits(5)),
+            Err(
+                "`string.lift_memory` reached the maximum number of bytes copied out of the memory (5 bytes)\n  at instruction 5"
+                    .into()
+            ),
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_max_memory_bytes__records_and_lists() {
+        let limits = |max_memory_bytes| Limits {
+            max_memory_bytes: Some(max_memory_bytes),
+            ..Default::default()
+        };
+
+        // The record has an `i32`, an `f32` and an `i64`: 16 bytes.
+        let instructions = vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::ArgumentGet { index: 2 },
+            Instruction::ArgumentGet { index: 3 },
+            Instruction::RecordLift { type_index: 0 },
+        ];
+        let invocation_inputs = [
+            InterfaceValue::I32(1),
+            InterfaceValue::String("Hello".into()),
+            InterfaceValue::F32(2.),
+            InterfaceValue::I64(3),
+        ];
+
+        assert!(run(
+            instructions.clone(),
+            &invocation_inputs,
+            Instance::new(),
+            limits(16)
+        )
+        .is_ok());
+        assert_eq!(
+            run(instructions, &invocation_inputs, Instance::new(), limits(15)),
+            Err(
+                "`record.lift 0` reached the maximum number of bytes copied out of the memory (15 bytes)\n  at instruction 4"
+                    .into()
+            ),
+        );
+
+        // The list has 4 `i32`: 16 bytes.
+        let instructions = vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ListLift {
+                element_type: InterfaceType::I32,
+            },
+            Instruction::End,
+        ];
+        let invocation_inputs = [InterfaceValue::I32(4)];
+
+        assert!(run(
+            instructions.clone(),
+            &invocation_inputs,
+            Instance::new(),
+            limits(16)
+        )
+        .is_ok());
+        assert_eq!(
+            run(instructions, &invocation_inputs, Instance::new(), limits(15)),
+            Err(
+                "`end` reached the maximum number of bytes copied out of the memory (15 bytes)\n  at instruction 2"
+                    .into()
+            ),
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_max_memory_bytes__out_of_bounds() {
+        // The bytes out of the memory are not counted: the access is
+        // reported.
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ArgumentGet { index: 1 },
+                    Instruction::StringLiftMemory,
+                ],
+                &[InterfaceValue::I32(0), InterfaceValue::I32(200)],
+                Instance::new(),
+                Limits {
+                    max_memory_bytes: Some(100),
+                    ..Default::default()
+                },
+            ),
+            Err(
+                "`string.lift_memory` read out of the memory bounds (index 200 > memory length 128)\n  at instruction 2"
+                    .into()
+            ),
+        );
+    }
+
+    #[test]
+    fn test_max_string_length() {
+        let limits = Limits {
+            max_string_length: Some(4),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ArgumentGet { index: 1 },
+                    Instruction::StringLiftMemory,
+                ],
+                &[InterfaceValue::I32(0), InterfaceValue::I32(5)],
+                Instance::new(),
+                limits,
+            ),
+            Err("`string.lift_memory` the string length (5 bytes) exceeds the maximum string length (4 bytes)\n  at instruction 2".into()),
+        );
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ArgumentGet { index: 1 },
+                    Instruction::StringLowerMemory,
+                ],
+                &[InterfaceValue::I32(0), InterfaceValue::String("Hello".into())],
+                Instance::new(),
+                limits,
+            ),
+            Err("`string.lower_memory` the string length (5 bytes) exceeds the maximum string length (4 bytes)\n  at instruction 2".into()),
+        );
+    }
+}
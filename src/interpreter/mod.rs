@@ -1,16 +1,48 @@
 //! A stack-based interpreter to execute instructions of WIT adapters.
 
+mod bytecode;
+mod context;
+mod execution;
+mod imports;
 mod instructions;
+mod limits;
+mod module;
+mod native;
+mod observer;
+mod profiler;
+mod signature;
 pub mod stack;
+mod typed;
 pub mod wasm;
 
 use crate::{
-    errors::{InstructionResult, InterpreterResult},
+    errors::{InstructionError, InstructionErrorKind, InstructionResult, InterpreterResult},
+    types::InterfaceType,
     values::InterfaceValue,
 };
-pub use instructions::Instruction;
-use stack::Stack;
-use std::{convert::TryFrom, marker::PhantomData};
+use bytecode::{compile, resolve, CompiledAdapter, Program};
+pub use context::ExecutionContext;
+pub use execution::{Execution, ExecutionState};
+pub use imports::{HostFunction, HostImports, ImportFunction};
+pub use instructions::{BlockType, Instruction};
+pub use limits::Limits;
+pub use module::InterfaceModule;
+pub use native::{IntoNativeFunction, NativeFunction, NativeValues};
+pub use observer::{Observer, WatTracer};
+pub use profiler::{AdapterProfile, InstructionProfile, ProfileReport, Profiler};
+use signature::Signature;
+use stack::{Stack, Stackable};
+use std::{
+    collections::HashMap, convert::TryFrom, marker::PhantomData, mem, ops::Range, sync::Arc,
+};
+pub use typed::TypedAdapter;
+use wasm::structures::{FunctionIndex, TypedIndex};
+
+/// The maximum number of nested adapters that can be executed with
+/// the `call-adapter` instruction, i.e. an adapter calling an adapter
+/// calling an adapter etc. It prevents runaway composition, like an
+/// adapter calling itself indefinitely.
+pub const MAX_ADAPTER_CALL_DEPTH: usize = 128;
 
 /// Represents the `Runtime`, which is used by an adapter to execute
 /// its instructions.
@@ -33,23 +65,232 @@ where
     /// instructions.
     wasm_instance: &'instance mut Instance,
 
+    /// The number of nested adapters being executed, i.e. how many
+    /// `call-adapter` instructions led to this runtime. It is used to
+    /// prevent runaway composition of adapters.
+    adapter_call_depth: usize,
+
+    /// The adapters compiled ahead of time, used by the resolved
+    /// `call-adapter` operations. It is empty if the interpreter
+    /// hasn't been resolved against an instance.
+    compiled_adapters: &'invocation [CompiledAdapter],
+
+    /// The adapters called by the unresolved `call-adapter`
+    /// operations, by adapter index. Each of them is compiled the
+    /// first time it is called, and then reused for the rest of the
+    /// run, including by the nested runtimes, and by the next runs
+    /// using the same execution context.
+    called_adapters: HashMap<u32, Arc<CompiledAdapter>>,
+
+    /// The scratch buffer receiving the arguments of a call, so that
+    /// popping them from the stack doesn't allocate.
+    arguments: Vec<InterfaceValue>,
+
+    /// The limits of the execution.
+    limits: Limits,
+
+    /// The fuel that remains, if the fuel is limited.
+    remaining_fuel: Option<u64>,
+
+    /// The number of bytes copied out of the memory so far.
+    memory_bytes: usize,
+
+    /// The observer notified of the execution of each instruction, if
+    /// any.
+    observer: Option<&'invocation mut dyn Observer>,
+
+    /// The calls registered by the `defer-call-core` instruction. They
+    /// are executed when the adapter finishes, whether it succeeds or
+    /// fails.
+    deferred_calls: Vec<DeferredCall>,
+
+    /// The arguments of the deferred calls, one after the other, in
+    /// the order of their registration.
+    deferred_arguments: Vec<InterfaceValue>,
+
+    /// The execution contexts of the adapters called with the
+    /// `call-adapter` instruction, one per nesting level below this
+    /// runtime.
+    nested_contexts: Vec<ExecutionContext>,
+
+    /// The index of the next instruction to execute. Control flow
+    /// instructions update it to jump to another instruction.
+    program_counter: usize,
+
+    /// The heights of the stack when the blocks have been entered,
+    /// from the outermost block to the innermost block.
+    block_heights: Vec<usize>,
+
+    /// The iterations of the `list.lift` and `for-each` instructions
+    /// being executed, from the outermost to the innermost.
+    iterations: Vec<Iteration>,
+
     /// Phantom data.
     _phantom: PhantomData<(Export, LocalImport, Memory, MemoryView)>,
 }
 
-/// Type alias for an executable instruction. It's an implementation
-/// details, but an instruction is a boxed closure instance.
-pub(crate) type ExecutableInstruction<Instance, Export, LocalImport, Memory, MemoryView> = Box<
-    dyn Fn(
-        &mut Runtime<Instance, Export, LocalImport, Memory, MemoryView>,
-    ) -> InstructionResult<()>,
->;
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Executes the instructions, following the jumps of the control
+    /// flow instructions, and then the deferred calls, in the reverse
+    /// order of their registration.
+    ///
+    /// The deferred calls are executed even if an instruction has
+    /// failed. In this case, the error of the instruction is returned,
+    /// otherwise the error of the first deferred call that has failed
+    /// is returned.
+    fn execute(&mut self, program: &Program) -> InstructionResult<()> {
+        let result = self.execute_operations(program);
+        let deferred_result = self.execute_deferred_calls();
+
+        result.and(deferred_result)
+    }
+
+    /// Executes the program like [`Runtime::execute`], but suspends at
+    /// the `call-core` instructions calling asynchronous local or
+    /// import functions.
+    async fn execute_async(&mut self, program: &Program) -> InstructionResult<()> {
+        let result = async {
+            while self.step_async(program).await? {}
+
+            Ok(())
+        }
+        .await;
+        let deferred_result = self.execute_deferred_calls();
+
+        result.and(deferred_result)
+    }
+
+    /// Executes and consumes all the deferred calls. Their outputs are
+    /// checked against the output types of the called functions, and
+    /// then dropped.
+    fn execute_deferred_calls(&mut self) -> InstructionResult<()> {
+        let mut result = Ok(());
+
+        while let Some(deferred_call) = self.deferred_calls.pop() {
+            let DeferredCall {
+                function_index,
+                inputs_count,
+                instruction,
+                offset,
+            } = deferred_call;
+            let index = FunctionIndex::new(function_index as usize);
+            let inputs_start = self.deferred_arguments.len() - inputs_count;
+
+            let call_result = match self.wasm_instance.local_or_import(index) {
+                Some(local_or_import) => local_or_import
+                    .call(&self.deferred_arguments[inputs_start..])
+                    .map_err(|source| {
+                        InstructionError::new(
+                            instruction.clone(),
+                            InstructionErrorKind::LocalOrImportCall {
+                                function_index,
+                                source,
+                            },
+                        )
+                    })
+                    .and_then(|outputs| {
+                        instructions::check_outputs(
+                            function_index,
+                            local_or_import.inputs(),
+                            local_or_import.outputs(),
+                            &outputs,
+                            &instruction,
+                        )
+                    }),
+
+                None => Err(InstructionError::new(
+                    instruction,
+                    InstructionErrorKind::LocalOrImportIsMissing { function_index },
+                )),
+            };
+
+            self.deferred_arguments.truncate(inputs_start);
+
+            if result.is_ok() {
+                result = call_result.map_err(|error| error.at_offset(offset));
+            }
+        }
+
+        result
+    }
+}
+
+/// Represents a call to a local or import function that has been
+/// registered by the `defer-call-core` instruction.
+pub(crate) struct DeferredCall {
+    /// The local or import function index.
+    function_index: u32,
+
+    /// The number of arguments of the call. They are the last
+    /// `inputs_count` deferred arguments of the runtime.
+    inputs_count: usize,
+
+    /// The instruction that has registered the call.
+    instruction: Instruction,
+
+    /// The offset of the instruction that has registered the call.
+    offset: usize,
+}
+
+/// Represents an iteration of a `list.lift` or a `for-each`
+/// instruction: its body is executed once per input.
+pub(crate) struct Iteration {
+    /// The position of the iteration block in the runtime block
+    /// heights.
+    block_depth: usize,
+
+    /// The inputs that remain to be given to the body, one per
+    /// execution.
+    remaining_inputs: IterationInputs,
+
+    /// The values left by each execution of the body.
+    outputs: Vec<InterfaceValue>,
+}
+
+/// Represents the inputs of an iteration, produced on demand.
+pub(crate) enum IterationInputs {
+    /// The indices of the elements to lift, for `list.lift`. They
+    /// aren't collected beforehand, since the length of the list is
+    /// untrusted.
+    Indices(Range<i32>),
+
+    /// The elements of the list, for `for-each`.
+    Elements(std::vec::IntoIter<InterfaceValue>),
+}
+
+impl Iterator for IterationInputs {
+    type Item = InterfaceValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Indices(indices) => indices.next().map(InterfaceValue::I32),
+            Self::Elements(elements) => elements.next(),
+        }
+    }
+}
+
+/// The instance types of an interpreter, behind a function pointer so
+/// that the interpreter is `Send` and `Sync` whatever they are.
+type InstanceTypes<Instance, Export, LocalImport, Memory, MemoryView> =
+    fn() -> (Instance, Export, LocalImport, Memory, MemoryView);
 
 /// An interpreter is the central piece of this crate. It is a set of
-/// executable instructions. Each instruction takes the runtime as
-/// argument. The runtime holds the invocation inputs, [the
+/// instructions compiled into operations, which are executed one
+/// after the other by a dispatch loop. Each operation takes the
+/// runtime as argument. The runtime holds the invocation inputs, [the
 /// stack](stack), and [the WebAssembly instance](wasm).
 ///
+/// An interpreter is `Send` and `Sync`: once compiled, it can be
+/// shared by several threads, each running it with its own instance.
+///
 /// When the interpreter executes the instructions, each of them can
 /// query the WebAssembly instance, operates on the stack, or reads
 /// the invocation inputs. At the end of the execution, the stack
@@ -74,7 +315,7 @@ pub(crate) type ExecutableInstruction<Instance, Export, LocalImport, Memory, Mem
 /// };
 ///
 /// // 1. Creates an interpreter from a set of instructions. They will
-/// //    be transformed into executable instructions.
+/// //    be compiled into operations.
 /// let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> = (&vec![
 ///     Instruction::ArgumentGet { index: 1 },
 ///     Instruction::ArgumentGet { index: 0 },
@@ -131,8 +372,28 @@ where
     MemoryView: wasm::structures::MemoryView,
     Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
 {
-    executable_instructions:
-        Vec<ExecutableInstruction<Instance, Export, LocalImport, Memory, MemoryView>>,
+    /// The compiled instructions.
+    program: Program,
+
+    /// The adapters called by the program, compiled ahead of time if
+    /// the interpreter has been resolved against an instance.
+    compiled_adapters: Vec<CompiledAdapter>,
+
+    /// The limits enforced when the interpreter runs.
+    limits: Limits,
+
+    /// The signature of the adapter, if known. The invocation inputs
+    /// and the values left on the stack are checked against it.
+    signature: Option<Signature>,
+
+    /// The index of the adapter, if known. The observer is notified
+    /// when the adapter is entered and exited, like for the adapters
+    /// called with `call-adapter`.
+    adapter_index: Option<u32>,
+
+    /// Phantom data. The interpreter holds no value of the instance
+    /// types.
+    _phantom: PhantomData<InstanceTypes<Instance, Export, LocalImport, Memory, MemoryView>>,
 }
 
 impl<Instance, Export, LocalImport, Memory, MemoryView>
@@ -144,36 +405,392 @@ where
     MemoryView: wasm::structures::MemoryView,
     Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
 {
-    fn iter(
-        &self,
-    ) -> impl Iterator<
-        Item = &ExecutableInstruction<Instance, Export, LocalImport, Memory, MemoryView>,
-    > + '_ {
-        self.executable_instructions.iter()
+    /// Creates an interpreter from a set of instructions, and resolves
+    /// it against a WebAssembly instance: the types, the local or
+    /// import functions and the adapters used by the instructions are
+    /// looked up once, here, instead of at each run. The called
+    /// adapters are compiled here too.
+    ///
+    /// Since the missing types, functions and adapters are detected
+    /// ahead of time, an error is returned if any of them doesn't
+    /// exist, even if the instruction using it would never be
+    /// executed.
+    ///
+    /// The interpreter must then be run with the same instance.
+    pub fn with_instance(
+        instructions: &[Instruction],
+        wasm_instance: &mut Instance,
+    ) -> InterpreterResult<Self> {
+        let mut program = compile(instructions)?;
+        let mut compiled_adapters = Vec::new();
+
+        resolve(&mut program, wasm_instance, &mut compiled_adapters)?;
+
+        Ok(Interpreter {
+            program,
+            compiled_adapters,
+            limits: Limits::default(),
+            signature: None,
+            adapter_index: None,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Creates an interpreter for the adapter at `adapter_index` of a
+    /// WebAssembly instance, like [`Interpreter::with_instance`] does
+    /// for its instructions. The interpreter knows the signature of
+    /// the adapter, see [`Interpreter::with_signature`], and its index,
+    /// which is given to the observers.
+    pub fn with_adapter(
+        adapter_index: u32,
+        wasm_instance: &mut Instance,
+    ) -> InterpreterResult<Self> {
+        let (inputs, outputs, instructions) = instructions::resolve_adapter(
+            &*wasm_instance,
+            adapter_index,
+            &Instruction::CallAdapter { adapter_index },
+        )?;
+        let signature = Signature {
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+        };
+        let instructions = instructions.to_vec();
+
+        let mut interpreter = Self::with_instance(&instructions, wasm_instance)?;
+        interpreter.signature = Some(signature);
+        interpreter.adapter_index = Some(adapter_index);
+
+        Ok(interpreter)
+    }
+
+    /// Sets the limits enforced when the interpreter runs. By default,
+    /// nothing is limited.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+
+        self
+    }
+
+    /// Sets the signature of the adapter executed by the interpreter.
+    /// The invocation inputs are checked against the `inputs` types
+    /// before each run, and the values left on the stack must be
+    /// exactly of the `outputs` types after each run. By default, the
+    /// interpreter doesn't know the signature, and nothing is checked.
+    pub fn with_signature(
+        mut self,
+        inputs: Vec<InterfaceType>,
+        outputs: Vec<InterfaceType>,
+    ) -> Self {
+        self.signature = Some(Signature { inputs, outputs });
+
+        self
     }
 
     /// Runs the interpreter, such as:
-    ///   1. Create a fresh stack,
-    ///   2. Create a fresh stack,
-    ///   3. Execute the instructions one after the other, and
-    ///      returns the stack.
+    ///   1. Create a fresh execution context, holding the stack,
+    ///   2. Execute the instructions, following the control flow,
+    ///   3. Execute the deferred calls, and
+    ///   4. Return the stack.
     pub fn run(
         &self,
         invocation_inputs: &[InterfaceValue],
         wasm_instance: &mut Instance,
     ) -> InterpreterResult<Stack<InterfaceValue>> {
-        let mut runtime = Runtime {
+        let mut context = ExecutionContext::new();
+
+        self.execute(invocation_inputs, wasm_instance, &mut context, None)?;
+
+        Ok(context.stack)
+    }
+
+    /// Runs the interpreter like [`Interpreter::run`], and notifies
+    /// the observer before and after the execution of each
+    /// instruction. See [`WatTracer`] to get a trace of the execution.
+    pub fn run_with_observer(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+        observer: &mut dyn Observer,
+    ) -> InterpreterResult<Stack<InterfaceValue>> {
+        let mut context = ExecutionContext::new();
+
+        self.execute(
             invocation_inputs,
-            stack: Stack::new(),
             wasm_instance,
-            _phantom: PhantomData,
-        };
+            &mut context,
+            Some(observer),
+        )?;
+
+        Ok(context.stack)
+    }
+
+    /// Runs the interpreter like [`Interpreter::run`], but with the
+    /// buffers of an execution context, which are reused from one run
+    /// to another. The values left on the stack replace the content of
+    /// `outputs`.
+    ///
+    /// When the same context and the same `outputs` are given to
+    /// repeated runs, once the buffers are large enough, the runs
+    /// allocate nothing but the values that need to (see
+    /// [`ExecutionContext`]) and the outputs returned by the called
+    /// functions.
+    pub fn run_into(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+        context: &mut ExecutionContext,
+        outputs: &mut Vec<InterfaceValue>,
+    ) -> InterpreterResult<()> {
+        outputs.clear();
+
+        self.execute(invocation_inputs, wasm_instance, context, None)?;
+
+        outputs.extend(context.stack.drain());
+
+        Ok(())
+    }
+
+    /// Runs the interpreter like [`Interpreter::run`], but
+    /// asynchronously: the `call-core` instructions calling a function
+    /// returned by
+    /// [`Instance::async_local_or_import`](wasm::structures::Instance::async_local_or_import)
+    /// suspend the run until the future of the call is ready, without
+    /// blocking the executor. The other instructions, and the stack,
+    /// behave exactly like in the synchronous run.
+    ///
+    /// Only the instructions of the interpreter are suspended. The
+    /// adapters called with `call-adapter`, and the deferred calls,
+    /// are executed synchronously, with the functions returned by
+    /// [`Instance::local_or_import`](wasm::structures::Instance::local_or_import):
+    /// calling an asynchronous-only function from them fails, as if
+    /// the function didn't exist.
+    pub async fn run_async(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+    ) -> InterpreterResult<Stack<InterfaceValue>> {
+        let mut context = ExecutionContext::new();
 
-        for executable_instruction in self.iter() {
-            executable_instruction(&mut runtime)?;
+        self.execute_async(invocation_inputs, wasm_instance, &mut context, None)
+            .await?;
+
+        Ok(context.stack)
+    }
+
+    /// Runs the interpreter asynchronously like
+    /// [`Interpreter::run_async`], and notifies the observer like
+    /// [`Interpreter::run_with_observer`].
+    pub async fn run_async_with_observer(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+        observer: &mut dyn Observer,
+    ) -> InterpreterResult<Stack<InterfaceValue>> {
+        let mut context = ExecutionContext::new();
+
+        self.execute_async(
+            invocation_inputs,
+            wasm_instance,
+            &mut context,
+            Some(observer),
+        )
+        .await?;
+
+        Ok(context.stack)
+    }
+
+    /// Runs the interpreter asynchronously like
+    /// [`Interpreter::run_async`], with the buffers of an execution
+    /// context like [`Interpreter::run_into`].
+    pub async fn run_async_into(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+        context: &mut ExecutionContext,
+        outputs: &mut Vec<InterfaceValue>,
+    ) -> InterpreterResult<()> {
+        outputs.clear();
+
+        self.execute_async(invocation_inputs, wasm_instance, context, None)
+            .await?;
+
+        outputs.extend(context.stack.drain());
+
+        Ok(())
+    }
+
+    /// Creates a typed adapter, to run the interpreter with native
+    /// parameters and to get native results, see [`TypedAdapter`].
+    ///
+    /// If the interpreter knows the signature of its adapter, it must
+    /// be exactly the interface types of `Params` and `Results`.
+    /// Otherwise, the values left on the stack are checked at each
+    /// call.
+    pub fn typed<Params, Results>(
+        &self,
+    ) -> InterpreterResult<
+        TypedAdapter<'_, Params, Results, Instance, Export, LocalImport, Memory, MemoryView>,
+    >
+    where
+        Params: NativeValues,
+        Results: NativeValues,
+    {
+        TypedAdapter::new(self)
+    }
+
+    /// Creates a resumable execution of the interpreter, paused before
+    /// its first instruction. See [`Execution`] to execute the
+    /// instructions step by step, and to inspect the stack between the
+    /// steps.
+    ///
+    /// If the interpreter knows the signature of its adapter, the
+    /// invocation inputs are checked here.
+    pub fn execution<'execution>(
+        &'execution self,
+        invocation_inputs: &'execution [InterfaceValue],
+        wasm_instance: &'execution mut Instance,
+    ) -> InterpreterResult<Execution<'execution, Instance, Export, LocalImport, Memory, MemoryView>>
+    {
+        if let Some(signature) = &self.signature {
+            signature.check_inputs(invocation_inputs)?;
         }
 
-        Ok(runtime.stack)
+        Ok(Execution::new(self, invocation_inputs, wasm_instance))
+    }
+
+    /// Executes the program with the buffers of the context. The
+    /// buffers are moved into the runtime, and moved back once the
+    /// execution is done, so that their allocations are kept.
+    fn execute(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+        context: &mut ExecutionContext,
+        observer: Option<&mut dyn Observer>,
+    ) -> InterpreterResult<()> {
+        let mut runtime = self.start(
+            invocation_inputs,
+            wasm_instance,
+            context,
+            observer.map(|observer| observer as &mut dyn Observer),
+        )?;
+        let result = runtime.execute(&self.program);
+
+        self.finish(runtime, context, result)
+    }
+
+    /// Executes the program asynchronously, like
+    /// [`Interpreter::execute`].
+    async fn execute_async(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+        context: &mut ExecutionContext,
+        observer: Option<&mut dyn Observer>,
+    ) -> InterpreterResult<()> {
+        let mut runtime = self.start(
+            invocation_inputs,
+            wasm_instance,
+            context,
+            observer.map(|observer| observer as &mut dyn Observer),
+        )?;
+        let result = runtime.execute_async(&self.program).await;
+
+        self.finish(runtime, context, result)
+    }
+
+    /// Checks the invocation inputs, creates the runtime with the
+    /// buffers of the context, and notifies the observer that the
+    /// adapter is entered.
+    fn start<'invocation, 'instance>(
+        &'invocation self,
+        invocation_inputs: &'invocation [InterfaceValue],
+        wasm_instance: &'instance mut Instance,
+        context: &mut ExecutionContext,
+        observer: Option<&'invocation mut dyn Observer>,
+    ) -> InterpreterResult<
+        Runtime<'invocation, 'instance, Instance, Export, LocalImport, Memory, MemoryView>,
+    > {
+        context.clear();
+
+        if let Some(signature) = &self.signature {
+            signature.check_inputs(invocation_inputs)?;
+        }
+
+        let mut runtime = self.runtime(invocation_inputs, wasm_instance, context, observer);
+
+        if let (Some(observer), Some(adapter_index)) =
+            (runtime.observer.as_deref_mut(), self.adapter_index)
+        {
+            observer.enter_adapter(adapter_index);
+        }
+
+        Ok(runtime)
+    }
+
+    /// Notifies the observer that the adapter is exited, moves the
+    /// buffers of the runtime back into the context, and checks the
+    /// values left on the stack.
+    fn finish(
+        &self,
+        mut runtime: Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>,
+        context: &mut ExecutionContext,
+        result: InstructionResult<()>,
+    ) -> InterpreterResult<()> {
+        if let (Some(observer), Some(adapter_index)) =
+            (runtime.observer.as_deref_mut(), self.adapter_index)
+        {
+            observer.exit_adapter(adapter_index);
+        }
+
+        context.stack = runtime.stack;
+        context.called_adapters = runtime.called_adapters;
+        context.arguments = runtime.arguments;
+        context.deferred_calls = runtime.deferred_calls;
+        context.deferred_arguments = runtime.deferred_arguments;
+        context.nested_contexts = runtime.nested_contexts;
+        context.block_heights = runtime.block_heights;
+        context.iterations = runtime.iterations;
+
+        result?;
+
+        if let Some(signature) = &self.signature {
+            signature.check_outputs(context.stack.as_slice())?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a runtime to execute the program from its first
+    /// instruction. The buffers of the context are moved into the
+    /// runtime.
+    fn runtime<'invocation, 'instance>(
+        &'invocation self,
+        invocation_inputs: &'invocation [InterfaceValue],
+        wasm_instance: &'instance mut Instance,
+        context: &mut ExecutionContext,
+        observer: Option<&'invocation mut dyn Observer>,
+    ) -> Runtime<'invocation, 'instance, Instance, Export, LocalImport, Memory, MemoryView> {
+        Runtime {
+            invocation_inputs,
+            stack: mem::take(&mut context.stack),
+            wasm_instance,
+            adapter_call_depth: 0,
+            compiled_adapters: &self.compiled_adapters,
+            called_adapters: mem::take(&mut context.called_adapters),
+            arguments: mem::take(&mut context.arguments),
+            limits: self.limits,
+            remaining_fuel: self.limits.fuel,
+            memory_bytes: 0,
+            observer,
+            deferred_calls: mem::take(&mut context.deferred_calls),
+            deferred_arguments: mem::take(&mut context.deferred_arguments),
+            nested_contexts: mem::take(&mut context.nested_contexts),
+            program_counter: 0,
+            block_heights: mem::take(&mut context.block_heights),
+            iterations: mem::take(&mut context.iterations),
+            _phantom: PhantomData,
+        }
     }
 }
 
@@ -187,68 +804,16 @@ where
     MemoryView: wasm::structures::MemoryView,
     Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
 {
-    type Error = ();
+    type Error = InstructionError;
 
     fn try_from(instructions: &Vec<Instruction>) -> Result<Self, Self::Error> {
-        let executable_instructions = instructions
-            .iter()
-            .map(|instruction| match instruction {
-                Instruction::ArgumentGet { index } => {
-                    instructions::argument_get(*index, *instruction)
-                }
-
-                Instruction::CallCore { function_index } => {
-                    instructions::call_core(*function_index, *instruction)
-                }
-
-                Instruction::S8FromI32 => instructions::s8_from_i32(*instruction),
-                Instruction::S8FromI64 => instructions::s8_from_i64(*instruction),
-                Instruction::S16FromI32 => instructions::s16_from_i32(*instruction),
-                Instruction::S16FromI64 => instructions::s16_from_i64(*instruction),
-                Instruction::S32FromI32 => instructions::s32_from_i32(*instruction),
-                Instruction::S32FromI64 => instructions::s32_from_i64(*instruction),
-                Instruction::S64FromI32 => instructions::s64_from_i32(*instruction),
-                Instruction::S64FromI64 => instructions::s64_from_i64(*instruction),
-                Instruction::I32FromS8 => instructions::i32_from_s8(*instruction),
-                Instruction::I32FromS16 => instructions::i32_from_s16(*instruction),
-                Instruction::I32FromS32 => instructions::i32_from_s32(*instruction),
-                Instruction::I32FromS64 => instructions::i32_from_s64(*instruction),
-                Instruction::I64FromS8 => instructions::i64_from_s8(*instruction),
-                Instruction::I64FromS16 => instructions::i64_from_s16(*instruction),
-                Instruction::I64FromS32 => instructions::i64_from_s32(*instruction),
-                Instruction::I64FromS64 => instructions::i64_from_s64(*instruction),
-                Instruction::U8FromI32 => instructions::u8_from_i32(*instruction),
-                Instruction::U8FromI64 => instructions::u8_from_i64(*instruction),
-                Instruction::U16FromI32 => instructions::u16_from_i32(*instruction),
-                Instruction::U16FromI64 => instructions::u16_from_i64(*instruction),
-                Instruction::U32FromI32 => instructions::u32_from_i32(*instruction),
-                Instruction::U32FromI64 => instructions::u32_from_i64(*instruction),
-                Instruction::U64FromI32 => instructions::u64_from_i32(*instruction),
-                Instruction::U64FromI64 => instructions::u64_from_i64(*instruction),
-                Instruction::I32FromU8 => instructions::i32_from_u8(*instruction),
-                Instruction::I32FromU16 => instructions::i32_from_u16(*instruction),
-                Instruction::I32FromU32 => instructions::i32_from_u32(*instruction),
-                Instruction::I32FromU64 => instructions::i32_from_u64(*instruction),
-                Instruction::I64FromU8 => instructions::i64_from_u8(*instruction),
-                Instruction::I64FromU16 => instructions::i64_from_u16(*instruction),
-                Instruction::I64FromU32 => instructions::i64_from_u32(*instruction),
-                Instruction::I64FromU64 => instructions::i64_from_u64(*instruction),
-
-                Instruction::StringLiftMemory => instructions::string_lift_memory(*instruction),
-                Instruction::StringLowerMemory => instructions::string_lower_memory(*instruction),
-                Instruction::StringSize => instructions::string_size(*instruction),
-
-                Instruction::RecordLift { type_index } => {
-                    instructions::record_lift(*type_index, *instruction)
-                }
-                Instruction::RecordLower { type_index } => {
-                    instructions::record_lower(*type_index, *instruction)
-                }
-            })
-            .collect();
-
         Ok(Interpreter {
-            executable_instructions,
+            program: compile(instructions)?,
+            compiled_adapters: Vec::new(),
+            limits: Limits::default(),
+            signature: None,
+            adapter_index: None,
+            _phantom: PhantomData,
         })
     }
 }
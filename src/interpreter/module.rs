@@ -0,0 +1,657 @@
+//! An interface module gathers the adapters of parsed interfaces,
+//! compiled once, and calls them by export name.
+
+use super::{
+    imports::{split_import_adapter, ImportAdapter, ImportFunction},
+    stack::Stack,
+    wasm, HostImports, Instruction, Interpreter, NativeValues, TypedAdapter,
+};
+use crate::{
+    ast::{Interfaces, Type},
+    errors::{ModuleError, ModuleResult},
+    values::InterfaceValue,
+};
+use std::{collections::HashMap, convert::TryFrom};
+
+/// Represents a set of interfaces ready to be called: all the adapters
+/// are compiled when the module is built, and each export is mapped to
+/// the adapter implementing it.
+///
+/// An export is mapped to an adapter through the implementations: the
+/// implementation whose core function type is the function type of
+/// the export gives the adapter function type, and the first adapter
+/// of this function type implements the export.
+///
+/// An import is mapped to an adapter the same way, through the
+/// implementation of its signature type, and is exposed to the guest
+/// through this adapter, see [`InterfaceModule::with_host_imports`].
+///
+/// The adapters are not resolved against an instance: the types,
+/// functions and adapters they use are looked up in the instance given
+/// to [`InterfaceModule::call`]. Like the interpreters, a module is
+/// `Send` and `Sync`, so that a single module can serve several
+/// threads.
+pub struct InterfaceModule<Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// The interpreters of the adapters, in the order of the adapters.
+    adapters: Vec<Interpreter<Instance, Export, LocalImport, Memory, MemoryView>>,
+
+    /// The positions of the implementing adapters in `adapters`, by
+    /// export name.
+    exports: HashMap<String, usize>,
+
+    /// The import adapters of the imports implemented by host
+    /// functions, by import namespace and name.
+    imports: Vec<ImportAdapter<Instance, Export, LocalImport, Memory, MemoryView>>,
+}
+
+impl<Instance, Export, LocalImport, Memory, MemoryView>
+    InterfaceModule<Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Creates a module from parsed interfaces. Each adapter is
+    /// compiled, and knows the signature of its function type, see
+    /// [`Interpreter::with_signature`].
+    ///
+    /// An error is returned if an adapter is invalid, or if an export
+    /// can't be mapped to an adapter.
+    pub fn new(interfaces: &Interfaces) -> ModuleResult<Self> {
+        Self::with_host_imports(interfaces, HostImports::new())
+    }
+
+    /// Creates a module from parsed interfaces, like
+    /// [`InterfaceModule::new`], where the imports are implemented by
+    /// the host functions of a registry.
+    ///
+    /// The imports occupy the first function indices, in their order
+    /// of declaration. The import adapter of an import is the adapter
+    /// implementing its signature type, see
+    /// [`Interfaces::import_adapter`]. It must call the function index
+    /// of the import with a `call-core` instruction outside of any
+    /// block. The instructions before the first such `call-core` lift
+    /// the core arguments into the inputs of the host function, and
+    /// the instructions after it lower the outputs of the host
+    /// function, pushed on the stack, into core results. The
+    /// core-level functions are given by
+    /// [`InterfaceModule::import_function`].
+    ///
+    /// An error is returned if a host function doesn't implement any
+    /// import, if an import has no adapter, or if its adapter doesn't
+    /// call it.
+    pub fn with_host_imports(
+        interfaces: &Interfaces,
+        mut host_imports: HostImports,
+    ) -> ModuleResult<Self> {
+        let mut adapters = Vec::with_capacity(interfaces.adapters.len());
+        let mut signatures = Vec::with_capacity(interfaces.adapters.len());
+
+        for (adapter_index, adapter) in interfaces.adapters.iter().enumerate() {
+            let adapter_index = adapter_index as u32;
+            let function_type = adapter.function_type;
+
+            let (inputs, outputs) = match interfaces.types.get(function_type as usize) {
+                Some(Type::Function { inputs, outputs }) => (inputs, outputs),
+
+                Some(_) => {
+                    return Err(ModuleError::AdapterTypeIsNotAFunction {
+                        adapter_index,
+                        function_type,
+                    })
+                }
+
+                None => {
+                    return Err(ModuleError::AdapterTypeIsMissing {
+                        adapter_index,
+                        function_type,
+                    })
+                }
+            };
+
+            let mut interpreter = Interpreter::try_from(&adapter.instructions)
+                .map_err(|error| ModuleError::InvalidAdapter {
+                    adapter_index,
+                    error,
+                })?
+                .with_signature(inputs.clone(), outputs.clone());
+            interpreter.adapter_index = Some(adapter_index);
+
+            adapters.push(interpreter);
+            signatures.push((inputs, outputs));
+        }
+
+        let mut exports = HashMap::with_capacity(interfaces.exports.len());
+
+        for export in &interfaces.exports {
+            let implementation = interfaces
+                .implementations
+                .iter()
+                .find(|implementation| implementation.core_function_type == export.function_type)
+                .ok_or_else(|| ModuleError::ExportIsNotImplemented {
+                    export_name: export.name.to_string(),
+                })?;
+
+            let adapter_index = interfaces.implementation_adapter(implementation).ok_or(
+                ModuleError::ImplementationAdapterIsMissing {
+                    core_function_type: implementation.core_function_type,
+                    adapter_function_type: implementation.adapter_function_type,
+                },
+            )?;
+
+            exports.insert(export.name.to_string(), adapter_index as usize);
+        }
+
+        let mut imports = Vec::new();
+
+        for (function_index, import) in interfaces.imports.iter().enumerate() {
+            let function = match host_imports.remove(&import.namespace, &import.name) {
+                Some(function) => function,
+                None => continue,
+            };
+
+            let (host_inputs, host_outputs) =
+                match interfaces.types.get(import.signature_type as usize) {
+                    Some(Type::Function { inputs, outputs }) => (inputs, outputs),
+                    _ => {
+                        return Err(ModuleError::ImportTypeIsInvalid {
+                            namespace: import.namespace.to_string(),
+                            name: import.name.to_string(),
+                            signature_type: import.signature_type,
+                        })
+                    }
+                };
+
+            let adapter_index = interfaces.import_adapter(import).ok_or_else(|| {
+                ModuleError::ImportAdapterIsMissing {
+                    namespace: import.namespace.to_string(),
+                    name: import.name.to_string(),
+                }
+            })?;
+
+            let (lift, lower) = split_import_adapter(
+                &interfaces.adapters[adapter_index as usize].instructions,
+                function_index as u32,
+            )
+            .map_err(|error| ModuleError::InvalidAdapter {
+                adapter_index,
+                error,
+            })?
+            .ok_or_else(|| ModuleError::ImportIsNotCalled {
+                namespace: import.namespace.to_string(),
+                name: import.name.to_string(),
+                adapter_index,
+            })?;
+
+            let compile = |instructions: &[Instruction]| {
+                Interpreter::try_from(&instructions.to_vec()).map_err(|error| {
+                    ModuleError::InvalidAdapter {
+                        adapter_index,
+                        error,
+                    }
+                })
+            };
+            let (lift, lower) = (compile(lift)?, compile(lower)?);
+
+            let (inputs, outputs) = signatures[adapter_index as usize];
+
+            imports.push(ImportAdapter {
+                namespace: import.namespace.to_string(),
+                name: import.name.to_string(),
+                inputs: inputs.clone(),
+                outputs: outputs.clone(),
+                host_outputs: host_outputs.clone(),
+                lift: lift.with_signature(inputs.clone(), host_inputs.clone()),
+                lower: lower.with_signature(inputs.clone(), outputs.clone()),
+                function,
+            });
+        }
+
+        if let Some((namespace, name)) = host_imports.names().next() {
+            return Err(ModuleError::HostImportIsUnknown {
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+            });
+        }
+
+        Ok(Self {
+            adapters,
+            exports,
+            imports,
+        })
+    }
+
+    /// Calls the adapter implementing the export `export_name` with
+    /// the invocation inputs, and returns the values it has left on
+    /// the stack.
+    pub fn call(
+        &self,
+        export_name: &str,
+        invocation_inputs: &[InterfaceValue],
+        wasm_instance: &mut Instance,
+    ) -> ModuleResult<Stack<InterfaceValue>> {
+        let interpreter =
+            self.interpreter(export_name)
+                .ok_or_else(|| ModuleError::ExportIsMissing {
+                    export_name: export_name.to_string(),
+                })?;
+
+        Ok(interpreter.run(invocation_inputs, wasm_instance)?)
+    }
+
+    /// Returns the adapter implementing the export `export_name` as a
+    /// typed adapter, see [`Interpreter::typed`].
+    pub fn typed<Params, Results>(
+        &self,
+        export_name: &str,
+    ) -> ModuleResult<
+        TypedAdapter<'_, Params, Results, Instance, Export, LocalImport, Memory, MemoryView>,
+    >
+    where
+        Params: NativeValues,
+        Results: NativeValues,
+    {
+        let interpreter =
+            self.interpreter(export_name)
+                .ok_or_else(|| ModuleError::ExportIsMissing {
+                    export_name: export_name.to_string(),
+                })?;
+
+        Ok(interpreter.typed()?)
+    }
+
+    /// Returns the interpreter of the adapter implementing the export
+    /// `export_name`, if any, e.g. to run it with an observer or with
+    /// limits.
+    pub fn interpreter(
+        &self,
+        export_name: &str,
+    ) -> Option<&Interpreter<Instance, Export, LocalImport, Memory, MemoryView>> {
+        self.exports
+            .get(export_name)
+            .map(|position| &self.adapters[*position])
+    }
+
+    /// Returns the core-level function implementing the import
+    /// `namespace`.`name` with a host function, if any.
+    pub fn import_function(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Option<ImportFunction<'_, Instance, Export, LocalImport, Memory, MemoryView>> {
+        self.imports
+            .iter()
+            .find(|adapter| adapter.namespace == namespace && adapter.name == name)
+            .map(|adapter| ImportFunction { adapter })
+    }
+
+    /// Returns the names of the exports, in no particular order.
+    pub fn export_names(&self) -> impl Iterator<Item = &str> {
+        self.exports.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InterfaceModule;
+    use crate::{
+        decoders::wat::{parse, Buffer},
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            stack::Stackable,
+            HostImports,
+        },
+        types::InterfaceType,
+        values::InterfaceValue,
+    };
+    use std::{error::Error, thread};
+
+    type TestModule = InterfaceModule<Instance, Export, LocalImport, Memory, MemoryView>;
+
+    fn module(input: &str) -> Result<TestModule, String> {
+        let buffer = Buffer::new(input).unwrap();
+        let interfaces = parse(&buffer).unwrap();
+
+        TestModule::new(&interfaces).map_err(|error| error.to_string())
+    }
+
+    const IMPORT: &str = "(@interface type (func (param s32) (result s32)))
+         (@interface type (func (param i32) (result i32)))
+         (@interface import \"env\" \"double\" (func (type 0)))
+         (@interface func (type 1)
+           arg.get 0
+           s32.from_i32
+           call-core 0
+           i32.from_s32)
+         (@interface implement (func 0) (func 1))";
+
+    fn module_with_host_imports(
+        input: &str,
+        host_imports: HostImports,
+    ) -> Result<TestModule, String> {
+        let buffer = Buffer::new(input).unwrap();
+        let interfaces = parse(&buffer).unwrap();
+
+        TestModule::with_host_imports(&interfaces, host_imports).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_call() {
+        let module = module(
+            "(@interface type (func (param i32 i32) (result i32)))
+             (@interface type (func (param s32 s32) (result s32)))
+             (@interface func (type 1)
+               arg.get 0
+               i32.from_s32
+               arg.get 1
+               i32.from_s32
+               call-core 42
+               s32.from_i32)
+             (@interface export \"mul\" (func 0))
+             (@interface implement (func 0) (func 1))",
+        )
+        .unwrap();
+
+        assert_eq!(module.export_names().collect::<Vec<_>>(), vec!["mul"]);
+        assert_eq!(
+            module
+                .call(
+                    "mul",
+                    &[InterfaceValue::S32(3), InterfaceValue::S32(4)],
+                    &mut Instance::new()
+                )
+                .unwrap()
+                .as_slice(),
+            &[InterfaceValue::S32(12)],
+        );
+        assert_eq!(
+            module
+                .call("mul", &[InterfaceValue::S32(3)], &mut Instance::new())
+                .err()
+                .unwrap()
+                .to_string(),
+            "the adapter expects invocation inputs of kind `[S32, S32]` but it received values of kind `[S32]`",
+        );
+        assert_eq!(
+            module
+                .call("div", &[], &mut Instance::new())
+                .err()
+                .unwrap()
+                .to_string(),
+            r#"the export "div" doesn't exist"#,
+        );
+    }
+
+    #[test]
+    fn test_export_is_not_implemented() {
+        assert_eq!(
+            module(
+                "(@interface type (func (param) (result)))
+                 (@interface func (type 0))
+                 (@interface export \"foo\" (func 0))",
+            )
+            .err(),
+            Some(r#"the export "foo" isn't implemented by any adapter"#.into()),
+        );
+    }
+
+    #[test]
+    fn test_implementation_adapter_is_missing() {
+        assert_eq!(
+            module(
+                "(@interface type (func (param) (result)))
+                 (@interface export \"foo\" (func 0))
+                 (@interface implement (func 0) (func 1))",
+            )
+            .err(),
+            Some("the implementation of the type `0` refers to the adapter type `1` but no adapter has this type".into()),
+        );
+    }
+
+    #[test]
+    fn test_invalid_adapter_type() {
+        assert_eq!(
+            module("(@interface func (type 0))").err(),
+            Some("the type `0` of the adapter `0` doesn't exist".into()),
+        );
+        assert_eq!(
+            module(
+                "(@interface type (record (field i32)))
+                 (@interface func (type 0))",
+            )
+            .err(),
+            Some("the type `0` of the adapter `0` isn't a function type".into()),
+        );
+    }
+
+    #[test]
+    fn test_invalid_adapter() {
+        let buffer = Buffer::new(
+            "(@interface type (func (param) (result)))
+             (@interface func (type 0) end)",
+        )
+        .unwrap();
+        let error = TestModule::new(&parse(&buffer).unwrap()).err().unwrap();
+
+        assert_eq!(error.to_string(), "the adapter `0` is invalid");
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_import_function() {
+        let mut host_imports = HostImports::new();
+        host_imports.register("env", "double", |arguments| match arguments {
+            [InterfaceValue::S32(value)] => Ok(vec![InterfaceValue::S32(value * 2)]),
+            _ => Err("unreachable".into()),
+        });
+
+        let module = module_with_host_imports(IMPORT, host_imports).unwrap();
+        let function = module.import_function("env", "double").unwrap();
+
+        assert_eq!(function.inputs(), &[InterfaceType::I32]);
+        assert_eq!(function.outputs(), &[InterfaceType::I32]);
+        assert_eq!(
+            function
+                .call(&[InterfaceValue::I32(21)], &mut Instance::new())
+                .unwrap(),
+            vec![InterfaceValue::I32(42)],
+        );
+        assert!(module.import_function("env", "triple").is_none());
+    }
+
+    #[test]
+    fn test_import_function_failures() {
+        let mut host_imports = HostImports::new();
+        host_imports.register("env", "double", |arguments| match arguments {
+            [InterfaceValue::S32(0)] => Err("zero".into()),
+            _ => Ok(vec![InterfaceValue::I64(0)]),
+        });
+
+        let module = module_with_host_imports(IMPORT, host_imports).unwrap();
+        let function = module.import_function("env", "double").unwrap();
+
+        let error = function
+            .call(&[InterfaceValue::I32(0)], &mut Instance::new())
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            r#"the host function of the import "env"."double" has failed"#,
+        );
+        assert_eq!(error.source().unwrap().to_string(), "zero");
+        assert_eq!(
+            function
+                .call(&[InterfaceValue::I32(1)], &mut Instance::new())
+                .err()
+                .unwrap()
+                .to_string(),
+            r#"the host function of the import "env"."double" must return values of kind `[S32]` but it returned values of kind `[I64]`"#,
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_import_function__deferred_calls() {
+        use std::sync::Mutex;
+
+        static CALLS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+        let mut host_imports = HostImports::new();
+        host_imports.register("env", "double", |arguments| {
+            CALLS.lock().unwrap().push("host");
+
+            match arguments {
+                [InterfaceValue::S32(value)] => Ok(vec![InterfaceValue::S32(value * 2)]),
+                _ => Err("unreachable".into()),
+            }
+        });
+
+        let module = module_with_host_imports(
+            "(@interface type (func (param s32) (result s32)))
+             (@interface type (func (param i32) (result i32)))
+             (@interface import \"env\" \"double\" (func (type 0)))
+             (@interface func (type 1)
+               arg.get 0
+               defer-call-core 44
+               arg.get 0
+               s32.from_i32
+               call-core 0
+               i32.from_s32)
+             (@interface implement (func 0) (func 1))",
+            host_imports,
+        )
+        .unwrap();
+        let function = module.import_function("env", "double").unwrap();
+
+        let mut instance = Instance::new();
+        instance.locals_or_imports.insert(
+            44,
+            LocalImport {
+                inputs: vec![InterfaceType::I32],
+                outputs: vec![],
+                function: |_| {
+                    CALLS.lock().unwrap().push("deferred");
+
+                    Ok(vec![])
+                },
+            },
+        );
+
+        assert_eq!(
+            function
+                .call(&[InterfaceValue::I32(21)], &mut instance)
+                .unwrap(),
+            vec![InterfaceValue::I32(42)],
+        );
+
+        // The call deferred before the host call is executed once the
+        // whole adapter has finished.
+        assert_eq!(*CALLS.lock().unwrap(), vec!["host", "deferred"]);
+    }
+
+    #[test]
+    fn test_host_import_is_unknown() {
+        let mut host_imports = HostImports::new();
+        host_imports.register("env", "triple", |_| Ok(vec![]));
+
+        assert_eq!(
+            module_with_host_imports(IMPORT, host_imports).err(),
+            Some(r#"a host function is registered for the import "env"."triple" but it doesn't exist"#.into()),
+        );
+    }
+
+    #[test]
+    fn test_import_adapter_is_missing() {
+        let mut host_imports = HostImports::new();
+        host_imports.register("env", "double", |_| Ok(vec![]));
+
+        assert_eq!(
+            module_with_host_imports(
+                "(@interface type (func (param s32) (result s32)))
+                 (@interface import \"env\" \"double\" (func (type 0)))",
+                host_imports,
+            )
+            .err(),
+            Some(r#"no adapter implements the import "env"."double""#.into()),
+        );
+    }
+
+    #[test]
+    fn test_import_is_not_called() {
+        let mut host_imports = HostImports::new();
+        host_imports.register("env", "double", |_| Ok(vec![]));
+
+        // The import is only called inside the body of a `for-each`.
+        assert_eq!(
+            module_with_host_imports(
+                "(@interface type (func (param s32) (result s32)))
+                 (@interface type (func (param i32) (result)))
+                 (@interface import \"env\" \"double\" (func (type 0)))
+                 (@interface func (type 1)
+                   arg.get 0
+                   for-each s32
+                     call-core 0
+                   end)
+                 (@interface implement (func 0) (func 1))",
+                host_imports,
+            )
+            .err(),
+            Some(r#"the adapter `0` implementing the import "env"."double" doesn't call it outside of any block"#.into()),
+        );
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let module = module(
+            "(@interface type (func (param i32 i32) (result i32)))
+             (@interface func (type 0)
+               arg.get 0
+               arg.get 1
+               call-core 42)
+             (@interface export \"mul\" (func 0))
+             (@interface implement (func 0) (func 0))",
+        )
+        .unwrap();
+
+        assert_send_sync(&module);
+        assert_send_sync(module.interpreter("mul").unwrap());
+
+        thread::scope(|scope| {
+            let module = &module;
+            let threads = (0..4)
+                .map(|i| {
+                    scope.spawn(move || {
+                        module
+                            .call(
+                                "mul",
+                                &[InterfaceValue::I32(i), InterfaceValue::I32(10)],
+                                &mut Instance::new(),
+                            )
+                            .unwrap()
+                            .as_slice()
+                            .to_vec()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for (i, thread) in threads.into_iter().enumerate() {
+                assert_eq!(
+                    thread.join().unwrap(),
+                    vec![InterfaceValue::I32(i as i32 * 10)]
+                );
+            }
+        });
+    }
+}
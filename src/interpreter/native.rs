@@ -0,0 +1,298 @@
+//! Native functions are Rust closures with native arguments and
+//! results, whose interface types are derived from [`NativeType`].
+
+use super::{wasm, HostFunction};
+use crate::{
+    errors::{HostError, WasmValueNativeCastError},
+    types::InterfaceType,
+    values::{InterfaceValue, NativeType},
+};
+use std::{convert::TryFrom, fmt};
+
+/// Represents a sequence of native values: nothing, one native
+/// value, or a tuple of native values. It is used for the results of
+/// a native function, and for the parameters and the results of a
+/// [`TypedAdapter`](super::TypedAdapter).
+pub trait NativeValues: Sized {
+    /// Returns the interface types of the values.
+    fn interface_types() -> Vec<InterfaceType>;
+
+    /// Converts the native values into interface values.
+    fn into_interface_values(self) -> Vec<InterfaceValue>;
+
+    /// Converts interface values into native values, `None` if they
+    /// aren't of the interface types.
+    fn from_interface_values(values: &[InterfaceValue]) -> Option<Self>;
+}
+
+impl NativeValues for () {
+    fn interface_types() -> Vec<InterfaceType> {
+        vec![]
+    }
+
+    fn into_interface_values(self) -> Vec<InterfaceValue> {
+        vec![]
+    }
+
+    fn from_interface_values(values: &[InterfaceValue]) -> Option<Self> {
+        if values.is_empty() {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> NativeValues for T
+where
+    T: NativeType
+        + Into<InterfaceValue>
+        + for<'a> TryFrom<&'a InterfaceValue, Error = WasmValueNativeCastError>,
+{
+    fn interface_types() -> Vec<InterfaceType> {
+        vec![T::INTERFACE_TYPE]
+    }
+
+    fn into_interface_values(self) -> Vec<InterfaceValue> {
+        vec![self.into()]
+    }
+
+    fn from_interface_values(values: &[InterfaceValue]) -> Option<Self> {
+        match values {
+            [value] => T::try_from(value).ok(),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! native_values {
+    ($($native:ident $value:ident),+) => {
+        impl<$($native),+> NativeValues for ($($native,)+)
+        where
+            $($native: NativeType
+                + Into<InterfaceValue>
+                + for<'a> TryFrom<&'a InterfaceValue, Error = WasmValueNativeCastError>),+
+        {
+            fn interface_types() -> Vec<InterfaceType> {
+                vec![$($native::INTERFACE_TYPE),+]
+            }
+
+            fn into_interface_values(self) -> Vec<InterfaceValue> {
+                let ($($value,)+) = self;
+
+                vec![$($value.into()),+]
+            }
+
+            fn from_interface_values(values: &[InterfaceValue]) -> Option<Self> {
+                match values {
+                    [$($value),+] => Some(($($native::try_from($value).ok()?,)+)),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+native_values!(A1 a1);
+native_values!(A1 a1, A2 a2);
+native_values!(A1 a1, A2 a2, A3 a3);
+native_values!(A1 a1, A2 a2, A3 a3, A4 a4);
+native_values!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5);
+native_values!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6);
+native_values!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6, A7 a7);
+native_values!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6, A7 a7, A8 a8);
+
+/// Represents a Rust closure that can be turned into a
+/// [`NativeFunction`]. It is implemented for the closures taking up
+/// to 8 native arguments, and returning `Result<R, E>` where `R`
+/// implements [`NativeValues`] and `E` can be turned into a
+/// [`HostError`]. The `Signature` type parameter is inferred from the
+/// closure.
+pub trait IntoNativeFunction<Signature> {
+    /// Turns the closure into a native function.
+    fn into_native_function(self) -> NativeFunction;
+}
+
+macro_rules! into_native_function {
+    ($($argument:ident $value:ident),*) => {
+        impl<Function, $($argument,)* Results, E> IntoNativeFunction<(($($argument,)*), Results, E)>
+            for Function
+        where
+            Function: Fn($($argument),*) -> Result<Results, E> + Send + Sync + 'static,
+            $($argument: NativeType + for<'a> TryFrom<&'a InterfaceValue, Error = WasmValueNativeCastError>,)*
+            Results: NativeValues,
+            E: Into<HostError>,
+        {
+            fn into_native_function(self) -> NativeFunction {
+                let inputs = vec![$($argument::INTERFACE_TYPE),*];
+                let inputs_cardinality = inputs.len();
+
+                NativeFunction {
+                    inputs,
+                    outputs: Results::interface_types(),
+                    function: Box::new(move |arguments| match arguments {
+                        [$($value),*] => Ok(self($($argument::try_from($value)?),*)
+                            .map_err(Into::into)?
+                            .into_interface_values()),
+
+                        _ => Err(format!(
+                            "the native function expects {} arguments but it received {}",
+                            inputs_cardinality,
+                            arguments.len(),
+                        )
+                        .into()),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+into_native_function!();
+into_native_function!(A1 a1);
+into_native_function!(A1 a1, A2 a2);
+into_native_function!(A1 a1, A2 a2, A3 a3);
+into_native_function!(A1 a1, A2 a2, A3 a3, A4 a4);
+into_native_function!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5);
+into_native_function!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6);
+into_native_function!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6, A7 a7);
+into_native_function!(A1 a1, A2 a2, A3 a3, A4 a4, A5 a5, A6 a6, A7 a7, A8 a8);
+
+/// Represents a Rust closure with native arguments and results,
+/// called with interface values. Its inputs and outputs are derived
+/// from the native types of the closure, so that it can be used as a
+/// [`LocalImport`](wasm::structures::LocalImport) without writing the
+/// implementation by hand, or registered as a host import with
+/// [`HostImports::register_native`](super::HostImports::register_native).
+///
+/// ```rust
+/// use wasmer_interface_types::{
+///     interpreter::{wasm::structures::LocalImport, NativeFunction},
+///     types::InterfaceType,
+///     values::InterfaceValue,
+/// };
+///
+/// let repeat = NativeFunction::new(|string: String, times: u32| {
+///     Ok::<_, String>(string.repeat(times as usize))
+/// });
+///
+/// assert_eq!(repeat.inputs(), &[InterfaceType::String, InterfaceType::U32]);
+/// assert_eq!(repeat.outputs(), &[InterfaceType::String]);
+/// assert_eq!(
+///     repeat
+///         .call(&[InterfaceValue::String("ab".into()), InterfaceValue::U32(2)])
+///         .unwrap(),
+///     vec![InterfaceValue::String("abab".into())],
+/// );
+/// ```
+pub struct NativeFunction {
+    /// The input types.
+    inputs: Vec<InterfaceType>,
+
+    /// The output types.
+    outputs: Vec<InterfaceType>,
+
+    /// The closure, converting the arguments and the results.
+    function: HostFunction,
+}
+
+impl NativeFunction {
+    /// Creates a native function from a Rust closure, see
+    /// [`IntoNativeFunction`].
+    pub fn new<Function, Signature>(function: Function) -> Self
+    where
+        Function: IntoNativeFunction<Signature>,
+    {
+        function.into_native_function()
+    }
+
+    /// Turns the native function into a host function.
+    pub(crate) fn into_host_function(self) -> HostFunction {
+        self.function
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("NativeFunction")
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .finish()
+    }
+}
+
+impl wasm::structures::LocalImport for NativeFunction {
+    fn inputs_cardinality(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn outputs_cardinality(&self) -> usize {
+        self.outputs.len()
+    }
+
+    fn inputs(&self) -> &[InterfaceType] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[InterfaceType] {
+        &self.outputs
+    }
+
+    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> {
+        (self.function)(arguments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NativeFunction;
+    use crate::{
+        interpreter::wasm::structures::LocalImport, types::InterfaceType, values::InterfaceValue,
+    };
+
+    #[test]
+    fn test_signatures() {
+        let nothing = NativeFunction::new(|| Ok::<_, String>(()));
+        let pair = NativeFunction::new(|x: i8, y: f64| Ok::<_, String>((y, x)));
+
+        assert_eq!(nothing.inputs(), &[]);
+        assert_eq!(nothing.outputs(), &[]);
+        assert_eq!(pair.inputs(), &[InterfaceType::S8, InterfaceType::F64]);
+        assert_eq!(pair.outputs(), &[InterfaceType::F64, InterfaceType::S8]);
+        assert_eq!(
+            pair.call(&[InterfaceValue::S8(1), InterfaceValue::F64(2.)])
+                .unwrap(),
+            vec![InterfaceValue::F64(2.), InterfaceValue::S8(1)],
+        );
+    }
+
+    #[test]
+    fn test_call_failures() {
+        let function =
+            NativeFunction::new(|x: i32| if x < 0 { Err("negative") } else { Ok(x as u64) });
+
+        assert_eq!(
+            function.call(&[InterfaceValue::I32(7)]).unwrap(),
+            vec![InterfaceValue::U64(7)],
+        );
+        assert_eq!(
+            function
+                .call(&[InterfaceValue::I32(-1)])
+                .unwrap_err()
+                .to_string(),
+            "negative",
+        );
+        assert_eq!(
+            function
+                .call(&[InterfaceValue::I64(7)])
+                .unwrap_err()
+                .to_string(),
+            "WasmValueNativeCastError { from: I64, to: I32 }",
+        );
+        assert_eq!(
+            function.call(&[]).unwrap_err().to_string(),
+            "the native function expects 1 arguments but it received 0",
+        );
+    }
+}
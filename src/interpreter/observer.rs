@@ -0,0 +1,251 @@
+//! Observers are notified of the execution of each instruction, in
+//! order to trace or to inspect the execution of an adapter.
+
+use super::Instruction;
+use crate::{errors::InstructionError, values::InterfaceValue};
+use std::time::Duration;
+
+/// An observer is notified before and after the execution of each
+/// instruction by [`Interpreter::run_with_observer`](super::Interpreter::run_with_observer).
+///
+/// The instructions of the adapters called with `call-adapter` are
+/// observed too, between the `enter_adapter` and `exit_adapter`
+/// notifications. Their indices are relative to their adapter.
+///
+/// All the methods do nothing by default.
+pub trait Observer {
+    /// Called before the instruction at `index` is executed, with the
+    /// values of the stack.
+    fn before_instruction(
+        &mut self,
+        _index: usize,
+        _instruction: &Instruction,
+        _stack: &[InterfaceValue],
+    ) {
+    }
+
+    /// Called after the instruction at `index` has been executed,
+    /// with the values of the stack, and the error of the instruction
+    /// if it has failed.
+    fn after_instruction(
+        &mut self,
+        _index: usize,
+        _instruction: &Instruction,
+        _stack: &[InterfaceValue],
+        _error: Option<&InstructionError>,
+    ) {
+    }
+
+    /// Called before the instructions of an adapter called with
+    /// `call-adapter` are executed. It is called for the adapter run
+    /// by the interpreter too, if the interpreter knows its index, e.g.
+    /// when it is created with
+    /// [`Interpreter::with_adapter`](super::Interpreter::with_adapter).
+    fn enter_adapter(&mut self, _adapter_index: u32) {}
+
+    /// Called after the instructions of an adapter entered with
+    /// `enter_adapter` have been executed.
+    fn exit_adapter(&mut self, _adapter_index: u32) {}
+
+    /// Called by a `string.lift_memory` instruction once it has copied
+    /// `length` bytes out of the memory and validated them as UTF-8,
+    /// with the time spent in each step.
+    fn string_lifted(&mut self, _length: usize, _copy_time: Duration, _validation_time: Duration) {}
+}
+
+/// An observer that renders a human-readable trace of the execution.
+/// There is one line per executed instruction, which is written in
+/// the WIT textual format, followed by the stack once it has been
+/// executed, or by its error. The instructions of the called adapters
+/// are indented.
+///
+/// # Example
+///
+/// ```text
+/// 0: arg.get 0 -> [I32(3)]
+/// 1: arg.get 1 -> [I32(3), I32(4)]
+/// adapter 0:
+///   0: arg.get 1 -> [I32(4)]
+///   1: arg.get 0 -> [I32(4), I32(3)]
+///   2: call-core 42 -> [I32(12)]
+/// 2: call-adapter 0 -> [I32(12)]
+/// ```
+#[derive(Debug, Default)]
+pub struct WatTracer {
+    /// The trace.
+    trace: String,
+
+    /// The number of adapters being executed.
+    depth: usize,
+}
+
+impl WatTracer {
+    /// Creates a new tracer, with an empty trace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the trace.
+    pub fn trace(&self) -> &str {
+        &self.trace
+    }
+
+    /// Consumes the tracer, and returns the trace.
+    pub fn into_trace(self) -> String {
+        self.trace
+    }
+
+    /// Writes a line at the current depth.
+    fn write_line(&mut self, line: &str) {
+        for _ in 0..self.depth {
+            self.trace.push_str("  ");
+        }
+
+        self.trace.push_str(line);
+        self.trace.push('\n');
+    }
+}
+
+impl Observer for WatTracer {
+    fn after_instruction(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        stack: &[InterfaceValue],
+        error: Option<&InstructionError>,
+    ) {
+        let line = match error {
+            Some(error) => format!(
+                "{}: {} -> error: {}",
+                index,
+                instruction.to_string(),
+                error.error_kind
+            ),
+            None => format!("{}: {} -> {:?}", index, instruction.to_string(), stack),
+        };
+
+        self.write_line(&line);
+    }
+
+    fn enter_adapter(&mut self, adapter_index: u32) {
+        self.write_line(&format!("adapter {}:", adapter_index));
+        self.depth += 1;
+    }
+
+    fn exit_adapter(&mut self, _adapter_index: u32) {
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Observer, WatTracer};
+    use crate::{
+        ast::{Adapter, Type},
+        errors::InstructionError,
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            Instruction, Interpreter,
+        },
+        types::InterfaceType,
+        values::InterfaceValue,
+    };
+    use std::convert::TryInto;
+
+    type TestInterpreter = Interpreter<Instance, Export, LocalImport, Memory, MemoryView>;
+
+    #[test]
+    fn test_observer() {
+        #[derive(Default)]
+        struct Counter {
+            before: Vec<usize>,
+            after: Vec<(usize, usize, bool)>,
+        }
+
+        impl Observer for Counter {
+            fn before_instruction(
+                &mut self,
+                index: usize,
+                _instruction: &Instruction,
+                _stack: &[InterfaceValue],
+            ) {
+                self.before.push(index);
+            }
+
+            fn after_instruction(
+                &mut self,
+                index: usize,
+                _instruction: &Instruction,
+                stack: &[InterfaceValue],
+                error: Option<&InstructionError>,
+            ) {
+                self.after.push((index, stack.len(), error.is_some()));
+            }
+        }
+
+        let interpreter: TestInterpreter = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+        ])
+            .try_into()
+            .unwrap();
+        let mut counter = Counter::default();
+
+        assert!(interpreter
+            .run_with_observer(
+                &[InterfaceValue::I32(1)],
+                &mut Instance::new(),
+                &mut counter
+            )
+            .is_err());
+        assert_eq!(counter.before, vec![0, 1]);
+        assert_eq!(counter.after, vec![(0, 1, false), (1, 1, true)]);
+    }
+
+    #[test]
+    fn test_wat_tracer() {
+        let mut instance = Instance::new();
+        instance.wit_types.push(Type::Function {
+            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+            outputs: vec![InterfaceType::I32],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::CallCore { function_index: 42 },
+            ],
+        });
+
+        let interpreter: TestInterpreter = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallAdapter { adapter_index: 0 },
+            Instruction::S32FromI64,
+        ])
+            .try_into()
+            .unwrap();
+        let mut tracer = WatTracer::new();
+
+        assert!(interpreter
+            .run_with_observer(
+                &[InterfaceValue::I32(3), InterfaceValue::I32(4)],
+                &mut instance,
+                &mut tracer,
+            )
+            .is_err());
+        assert_eq!(
+            tracer.trace(),
+            "0: arg.get 0 -> [I32(3)]
+1: arg.get 1 -> [I32(3), I32(4)]
+adapter 0:
+  0: arg.get 1 -> [I32(4)]
+  1: arg.get 0 -> [I32(4), I32(3)]
+  2: call-core 42 -> [I32(12)]
+2: call-adapter 0 -> [I32(12)]
+3: s32.from_i64 -> error: read a value of type `I32` from the stack, but the type `I64` was expected
+",
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! A profiler is an observer that measures where the time of an
+//! adapter run is spent.
+
+use super::{Instruction, Observer};
+use crate::{errors::InstructionError, values::InterfaceValue};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    mem::{self, Discriminant},
+    time::{Duration, Instant},
+};
+
+/// Represents the profile of an instruction kind, e.g. of all the
+/// `call-core` instructions, whatever their function index.
+#[derive(PartialEq, Debug, Clone)]
+pub struct InstructionProfile {
+    /// The name of the instruction kind, as written in the WIT
+    /// textual format, e.g. `call-core` or `string.lift_memory`.
+    pub name: String,
+
+    /// The number of executions.
+    pub count: u64,
+
+    /// The accumulated time of the executions. The time of a
+    /// `call-adapter` instruction includes the time of the
+    /// instructions of the called adapter.
+    pub time: Duration,
+}
+
+/// Represents the profile of an adapter, called with `call-adapter`,
+/// or run by an interpreter that knows its index, see
+/// [`Observer::enter_adapter`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct AdapterProfile {
+    /// The adapter index.
+    pub adapter_index: u32,
+
+    /// The number of calls.
+    pub count: u64,
+
+    /// The accumulated time of the calls.
+    pub time: Duration,
+}
+
+/// Represents the report of a [`Profiler`].
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// The profiles of the instruction kinds, from the most to the
+    /// least time consuming.
+    pub instructions: Vec<InstructionProfile>,
+
+    /// The profiles of the adapters, ordered by adapter index.
+    pub adapters: Vec<AdapterProfile>,
+
+    /// The number of bytes copied out of the memory by the
+    /// `string.lift_memory` instructions.
+    pub memory_bytes_lifted: u64,
+
+    /// The number of bytes copied into the memory by the
+    /// `string.lower_memory` instructions.
+    pub memory_bytes_lowered: u64,
+
+    /// The part of the time of the `string.lift_memory` instructions
+    /// spent copying the bytes out of the memory.
+    pub string_copy_time: Duration,
+
+    /// The part of the time of the `string.lift_memory` instructions
+    /// spent validating the bytes as UTF-8.
+    pub string_validation_time: Duration,
+}
+
+/// An observer that counts the executions and accumulates the time of
+/// each instruction kind and of each adapter, and counts the bytes
+/// copied from and to the memory by the lift and lower instructions.
+/// The time of the string lifts is split between the copy and the
+/// UTF-8 validation. It is given to
+/// [`Interpreter::run_with_observer`](super::Interpreter::run_with_observer),
+/// possibly for several runs, and its measures are read with
+/// [`Profiler::report`].
+///
+/// Only the instructions that succeed are counted in the copied
+/// bytes.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// The profiles of the instruction kinds, in the order of their
+    /// first execution.
+    instructions: Vec<InstructionProfile>,
+
+    /// The positions of the instruction kinds in `instructions`.
+    instruction_positions: HashMap<Discriminant<Instruction>, usize>,
+
+    /// The profiles of the called adapters.
+    adapters: BTreeMap<u32, AdapterProfile>,
+
+    /// The start times of the instructions being executed, from the
+    /// outermost to the innermost.
+    instruction_starts: Vec<Instant>,
+
+    /// The start times of the adapters being executed, from the
+    /// outermost to the innermost.
+    adapter_starts: Vec<Instant>,
+
+    /// The number of bytes copied out of the memory.
+    memory_bytes_lifted: u64,
+
+    /// The number of bytes copied into the memory.
+    memory_bytes_lowered: u64,
+
+    /// The time spent copying the lifted strings.
+    string_copy_time: Duration,
+
+    /// The time spent validating the lifted strings.
+    string_validation_time: Duration,
+}
+
+impl Profiler {
+    /// Creates a new profiler, with empty measures.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the report of the measures so far.
+    pub fn report(&self) -> ProfileReport {
+        let mut instructions = self.instructions.clone();
+        instructions.sort_by_key(|profile| Reverse(profile.time));
+
+        ProfileReport {
+            instructions,
+            adapters: self.adapters.values().cloned().collect(),
+            memory_bytes_lifted: self.memory_bytes_lifted,
+            memory_bytes_lowered: self.memory_bytes_lowered,
+            string_copy_time: self.string_copy_time,
+            string_validation_time: self.string_validation_time,
+        }
+    }
+
+    /// Returns the profile of an instruction kind, creating it on its
+    /// first execution.
+    fn instruction_profile(&mut self, instruction: &Instruction) -> &mut InstructionProfile {
+        let instructions = &mut self.instructions;
+        let position = *self
+            .instruction_positions
+            .entry(mem::discriminant(instruction))
+            .or_insert_with(|| {
+                let name = instruction.to_string();
+
+                instructions.push(InstructionProfile {
+                    name: name.split(' ').next().unwrap_or_default().to_string(),
+                    count: 0,
+                    time: Duration::default(),
+                });
+
+                instructions.len() - 1
+            });
+
+        &mut self.instructions[position]
+    }
+}
+
+impl Observer for Profiler {
+    fn before_instruction(
+        &mut self,
+        _index: usize,
+        _instruction: &Instruction,
+        _stack: &[InterfaceValue],
+    ) {
+        self.instruction_starts.push(Instant::now());
+    }
+
+    fn after_instruction(
+        &mut self,
+        _index: usize,
+        instruction: &Instruction,
+        stack: &[InterfaceValue],
+        error: Option<&InstructionError>,
+    ) {
+        let time = self
+            .instruction_starts
+            .pop()
+            .map(|start| start.elapsed())
+            .unwrap_or_default();
+
+        let profile = self.instruction_profile(instruction);
+        profile.count += 1;
+        profile.time += time;
+
+        if error.is_some() {
+            return;
+        }
+
+        match (instruction, stack.last()) {
+            (Instruction::StringLiftMemory, Some(InterfaceValue::String(string))) => {
+                self.memory_bytes_lifted += string.len() as u64;
+            }
+
+            (Instruction::StringLowerMemory, Some(InterfaceValue::I32(length))) => {
+                self.memory_bytes_lowered += *length as u64;
+            }
+
+            _ => {}
+        }
+    }
+
+    fn enter_adapter(&mut self, _adapter_index: u32) {
+        self.adapter_starts.push(Instant::now());
+    }
+
+    fn exit_adapter(&mut self, adapter_index: u32) {
+        let time = self
+            .adapter_starts
+            .pop()
+            .map(|start| start.elapsed())
+            .unwrap_or_default();
+
+        let profile = self
+            .adapters
+            .entry(adapter_index)
+            .or_insert_with(|| AdapterProfile {
+                adapter_index,
+                count: 0,
+                time: Duration::default(),
+            });
+        profile.count += 1;
+        profile.time += time;
+    }
+
+    fn string_lifted(&mut self, _length: usize, copy_time: Duration, validation_time: Duration) {
+        self.string_copy_time += copy_time;
+        self.string_validation_time += validation_time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profiler;
+    use crate::{
+        ast::{Adapter, Type},
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            Instruction, Interpreter,
+        },
+        types::InterfaceType,
+        values::InterfaceValue,
+    };
+    use std::{cell::Cell, convert::TryInto};
+
+    type TestInterpreter = Interpreter<Instance, Export, LocalImport, Memory, MemoryView>;
+
+    #[test]
+    fn test_profiler() {
+        let mut instance = Instance {
+            memory: Memory::new(
+                "Hello, World!"
+                    .as_bytes()
+                    .iter()
+                    .map(|u| Cell::new(*u))
+                    .collect(),
+            ),
+            ..Instance::new()
+        };
+        instance.wit_types.push(Type::Function {
+            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+            outputs: vec![InterfaceType::String],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::StringLiftMemory,
+            ],
+        });
+
+        let interpreter: TestInterpreter = (&vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallAdapter { adapter_index: 0 },
+            Instruction::StringLowerMemory,
+        ])
+            .try_into()
+            .unwrap();
+        let mut profiler = Profiler::new();
+
+        for _ in 0..2 {
+            interpreter
+                .run_with_observer(
+                    &[InterfaceValue::I32(0), InterfaceValue::I32(5)],
+                    &mut instance,
+                    &mut profiler,
+                )
+                .unwrap();
+        }
+
+        let report = profiler.report();
+        let mut counts = report
+            .instructions
+            .iter()
+            .map(|profile| (profile.name.as_str(), profile.count))
+            .collect::<Vec<_>>();
+        counts.sort();
+
+        assert_eq!(
+            counts,
+            vec![
+                ("arg.get", 10),
+                ("call-adapter", 2),
+                ("string.lift_memory", 2),
+                ("string.lower_memory", 2),
+            ]
+        );
+        assert_eq!(report.adapters.len(), 1);
+        assert_eq!(report.adapters[0].adapter_index, 0);
+        assert_eq!(report.adapters[0].count, 2);
+        assert_eq!(report.memory_bytes_lifted, 10);
+        assert_eq!(report.memory_bytes_lowered, 10);
+
+        let lift_time = report
+            .instructions
+            .iter()
+            .find(|profile| profile.name == "string.lift_memory")
+            .unwrap()
+            .time;
+
+        assert!(report.string_copy_time + report.string_validation_time <= lift_time);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_profiler__root_adapter() {
+        let mut instance = Instance::new();
+        instance.wit_types.push(Type::Function {
+            inputs: vec![],
+            outputs: vec![],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![Instruction::CallAdapter { adapter_index: 1 }],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![],
+        });
+
+        let interpreter = TestInterpreter::with_adapter(0, &mut instance).unwrap();
+        let mut profiler = Profiler::new();
+
+        interpreter
+            .run_with_observer(&[], &mut instance, &mut profiler)
+            .unwrap();
+
+        let report = profiler.report();
+
+        assert_eq!(
+            report
+                .adapters
+                .iter()
+                .map(|profile| (profile.adapter_index, profile.count))
+                .collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1)],
+        );
+        assert!(report.adapters[1].time <= report.adapters[0].time);
+    }
+}
@@ -0,0 +1,189 @@
+//! The signature of an adapter is checked against the invocation
+//! inputs before a run, and against the stack after a run.
+
+use super::instructions::values_are_of_types;
+use crate::{
+    errors::{InterpreterError, InterpreterResult},
+    types::InterfaceType,
+    values::InterfaceValue,
+};
+use std::cmp::Ordering;
+
+/// Represents the signature of the adapter executed by an
+/// interpreter, i.e. the inputs and the outputs of its function type.
+#[derive(Debug, Clone)]
+pub(crate) struct Signature {
+    /// The input types.
+    pub(crate) inputs: Vec<InterfaceType>,
+
+    /// The output types.
+    pub(crate) outputs: Vec<InterfaceType>,
+}
+
+impl Signature {
+    /// Checks that the invocation inputs are of the input types.
+    pub(crate) fn check_inputs(
+        &self,
+        invocation_inputs: &[InterfaceValue],
+    ) -> InterpreterResult<()> {
+        if values_are_of_types(invocation_inputs, &self.inputs) {
+            return Ok(());
+        }
+
+        Err(InterpreterError::InvocationInputsMismatch {
+            expected: self.inputs.clone(),
+            received: invocation_inputs.iter().map(Into::into).collect(),
+        })
+    }
+
+    /// Checks that the values left on the stack are exactly of the
+    /// output types.
+    pub(crate) fn check_outputs(&self, outputs: &[InterfaceValue]) -> InterpreterResult<()> {
+        if values_are_of_types(outputs, &self.outputs) {
+            return Ok(());
+        }
+
+        let expected = self.outputs.clone();
+        let received = outputs.iter().map(Into::into).collect();
+
+        Err(match outputs.len().cmp(&self.outputs.len()) {
+            Ordering::Less => InterpreterError::MissingOutputs { expected, received },
+            Ordering::Greater => InterpreterError::LeftoverOutputs { expected, received },
+            Ordering::Equal => InterpreterError::OutputsMismatch { expected, received },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Adapter, Type},
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            stack::Stackable,
+            Instruction, Interpreter,
+        },
+        types::InterfaceType,
+        values::InterfaceValue,
+    };
+    use std::convert::TryInto;
+
+    type TestInterpreter = Interpreter<Instance, Export, LocalImport, Memory, MemoryView>;
+
+    fn run(
+        instructions: Vec<Instruction>,
+        outputs: Vec<InterfaceType>,
+        invocation_inputs: &[InterfaceValue],
+    ) -> Result<Vec<InterfaceValue>, String> {
+        let interpreter: TestInterpreter = (&instructions).try_into().unwrap();
+
+        interpreter
+            .with_signature(vec![InterfaceType::I32, InterfaceType::I32], outputs)
+            .run(invocation_inputs, &mut Instance::new())
+            .map(|stack| stack.as_slice().to_vec())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_signature() {
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ArgumentGet { index: 1 },
+                    Instruction::CallCore { function_index: 42 },
+                ],
+                vec![InterfaceType::I32],
+                &[InterfaceValue::I32(3), InterfaceValue::I32(4)],
+            ),
+            Ok(vec![InterfaceValue::I32(12)]),
+        );
+    }
+
+    #[test]
+    fn test_invocation_inputs_mismatch() {
+        assert_eq!(
+            run(
+                vec![],
+                vec![],
+                &[InterfaceValue::I32(3), InterfaceValue::I64(4)],
+            ),
+            Err("the adapter expects invocation inputs of kind `[I32, I32]` but it received values of kind `[I32, I64]`".into()),
+        );
+    }
+
+    #[test]
+    fn test_missing_outputs() {
+        assert_eq!(
+            run(
+                vec![Instruction::ArgumentGet { index: 0 }],
+                vec![InterfaceType::I32, InterfaceType::I32],
+                &[InterfaceValue::I32(3), InterfaceValue::I32(4)],
+            ),
+            Err("the adapter must return values of kind `[I32, I32]` but it has left fewer values on the stack, of kind `[I32]`".into()),
+        );
+    }
+
+    #[test]
+    fn test_leftover_outputs() {
+        assert_eq!(
+            run(
+                vec![
+                    Instruction::ArgumentGet { index: 0 },
+                    Instruction::ArgumentGet { index: 1 },
+                ],
+                vec![InterfaceType::I32],
+                &[InterfaceValue::I32(3), InterfaceValue::I32(4)],
+            ),
+            Err("the adapter must return values of kind `[I32]` but it has left more values on the stack, of kind `[I32, I32]`".into()),
+        );
+    }
+
+    #[test]
+    fn test_outputs_mismatch() {
+        assert_eq!(
+            run(
+                vec![Instruction::ArgumentGet { index: 0 }],
+                vec![InterfaceType::S32],
+                &[InterfaceValue::I32(3), InterfaceValue::I32(4)],
+            ),
+            Err("the adapter must return values of kind `[S32]` but it has left values of kind `[I32]` on the stack".into()),
+        );
+    }
+
+    #[test]
+    fn test_with_adapter() {
+        let mut instance = Instance::new();
+        instance.wit_types.push(Type::Function {
+            inputs: vec![InterfaceType::I32],
+            outputs: vec![InterfaceType::S32],
+        });
+        instance.wit_adapters.push(Adapter {
+            function_type: 1,
+            instructions: vec![
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::S32FromI32,
+            ],
+        });
+
+        let interpreter = TestInterpreter::with_adapter(0, &mut instance).unwrap();
+
+        assert_eq!(
+            interpreter
+                .run(&[InterfaceValue::I32(7)], &mut instance)
+                .unwrap()
+                .as_slice(),
+            &[InterfaceValue::S32(7)],
+        );
+        assert!(interpreter
+            .run(&[InterfaceValue::S32(7)], &mut instance)
+            .is_err());
+        assert_eq!(
+            TestInterpreter::with_adapter(1, &mut instance)
+                .err()
+                .unwrap()
+                .to_string(),
+            "`call-adapter 1` the adapter `1` doesn't exist",
+        );
+    }
+}
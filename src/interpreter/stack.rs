@@ -17,21 +17,28 @@ pub trait Stackable {
     fn push(&mut self, item: Self::Item);
 
     /// Removes the last item of the stack and returns it, `None` if
-    /// the stack is empty.
+    /// the stack is empty, or if the item is below the floor of the
+    /// stack.
     fn pop1(&mut self) -> Option<Self::Item>;
 
     /// Removes `n` elements from the end of the stack, `None` if the
-    /// stack doesn't contain enough elements.
+    /// stack doesn't contain enough elements above its floor.
     /// Returned items are in reverse order: the last element comes
     /// last in the list.
     fn pop(&mut self, n: usize) -> Option<Vec<Self::Item>>;
 
     /// Peek the last item of the stack and returns a reference to it,
-    /// `None` if the stack is empty.
+    /// `None` if the stack is empty, or if the item is below the floor
+    /// of the stack.
     fn peek1(&self) -> Option<&Self::Item>;
 }
 
 /// A stack implementation of the `Stackable` trait, based on a vector.
+///
+/// The items below the floor of the stack can't be popped. The
+/// interpreter sets the floor to the height of the innermost block,
+/// so that the instructions of a block can only consume the values of
+/// the block, like in WebAssembly core.
 #[derive(Debug, Default)]
 pub struct Stack<T>
 where
@@ -39,6 +46,9 @@ where
 {
     /// Inner structure holding the items.
     inner: Vec<T>,
+
+    /// The number of items that can't be popped.
+    floor: usize,
 }
 
 impl<T> Stack<T>
@@ -51,6 +61,64 @@ where
             ..Default::default()
         }
     }
+
+    /// Creates a new empty stack, which can hold at least `capacity`
+    /// items without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+            floor: 0,
+        }
+    }
+
+    /// Removes all the items of the stack, while keeping its
+    /// capacity.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.floor = 0;
+    }
+
+    /// Removes all the items of the stack and returns them in an
+    /// iterator, from the first pushed item to the last one. The
+    /// capacity of the stack is kept.
+    pub fn drain(&mut self) -> std::vec::Drain<'_, T> {
+        self.floor = 0;
+
+        self.inner.drain(..)
+    }
+
+    /// Sets the number of items that can't be popped.
+    pub(crate) fn set_floor(&mut self, floor: usize) {
+        self.floor = floor;
+    }
+
+    /// Removes `n` items from the end of the stack, like
+    /// [`Stackable::pop`], but moves them into `buffer` instead of a
+    /// new vector. The previous content of `buffer` is dropped. It
+    /// returns `false` if the stack doesn't contain enough items
+    /// above its floor, in which case nothing is moved.
+    pub(crate) fn pop_into(&mut self, n: usize, buffer: &mut Vec<T>) -> bool {
+        if self.inner.len() < self.floor + n {
+            return false;
+        }
+
+        buffer.clear();
+        buffer.extend(self.inner.drain(self.inner.len() - n..));
+
+        true
+    }
+
+    /// Removes the items from the position `height`, except the `kept`
+    /// last items, which move down to `height`. The floor must not be
+    /// above `height`, and the stack must contain at least `kept`
+    /// items above `height`.
+    pub(crate) fn unwind(&mut self, height: usize, kept: usize) {
+        debug_assert!(self.floor <= height && height + kept <= self.inner.len());
+
+        let end = self.inner.len() - kept;
+
+        self.inner.drain(height..end);
+    }
 }
 
 impl<T> Stackable for Stack<T>
@@ -72,11 +140,15 @@ where
     }
 
     fn pop1(&mut self) -> Option<Self::Item> {
-        self.inner.pop()
+        if self.inner.len() <= self.floor {
+            None
+        } else {
+            self.inner.pop()
+        }
     }
 
     fn pop(&mut self, n: usize) -> Option<Vec<Self::Item>> {
-        if self.inner.len() < n {
+        if self.inner.len() < self.floor + n {
             None
         } else {
             let items = self
@@ -91,7 +163,7 @@ where
     }
 
     fn peek1(&self) -> Option<&Self::Item> {
-        if self.inner.is_empty() {
+        if self.inner.len() <= self.floor {
             None
         } else {
             Some(&self.inner[self.inner.len() - 1])
@@ -147,4 +219,72 @@ mod tests {
 
         assert_eq!(stack.peek1(), Some(&2));
     }
+
+    #[test]
+    fn test_floor() {
+        let mut stack = Stack::new();
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        stack.set_floor(2);
+
+        assert_eq!(stack.pop(2), None); // `2` is below the floor
+        assert_eq!(stack.peek1(), Some(&3));
+        assert_eq!(stack.pop1(), Some(3));
+        assert_eq!(stack.peek1(), None);
+        assert_eq!(stack.pop1(), None);
+        assert_eq!(stack.as_slice(), &[1, 2]);
+
+        stack.set_floor(0);
+
+        assert_eq!(stack.pop(2), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_pop_into() {
+        let mut stack = Stack::new();
+        let mut buffer = vec![0];
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+
+        assert!(stack.pop_into(2, &mut buffer));
+        assert_eq!(buffer, vec![2, 3]);
+        assert!(!stack.pop_into(2, &mut buffer)); // not enough items
+        assert_eq!(buffer, vec![2, 3]);
+        assert_eq!(stack.as_slice(), &[1]);
+    }
+
+    #[test]
+    fn test_unwind() {
+        let mut stack = Stack::new();
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        stack.push(4);
+        stack.unwind(1, 1);
+
+        assert_eq!(stack.as_slice(), &[1, 4]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut stack = Stack::with_capacity(4);
+        stack.push(1);
+        stack.push(2);
+        stack.clear();
+
+        assert!(stack.is_empty());
+        assert_eq!(stack.inner.capacity(), 4);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut stack = Stack::new();
+        stack.push(1);
+        stack.push(2);
+
+        assert_eq!(stack.drain().collect::<Vec<_>>(), vec![1, 2]);
+        assert!(stack.is_empty());
+    }
 }
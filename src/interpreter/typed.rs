@@ -0,0 +1,161 @@
+//! A typed adapter calls an interpreter with native parameters and
+//! returns native results.
+
+use super::{stack::Stackable, wasm, Interpreter, NativeValues};
+use crate::errors::{InterpreterError, InterpreterResult};
+use std::marker::PhantomData;
+
+/// Represents an interpreter called with native values, where
+/// `Params` and `Results` are [`NativeValues`], e.g. `()`, `i32`, or
+/// `(String, u32)`. It is created with [`Interpreter::typed`].
+pub struct TypedAdapter<
+    'interpreter,
+    Params,
+    Results,
+    Instance,
+    Export,
+    LocalImport,
+    Memory,
+    MemoryView,
+> where
+    Params: NativeValues,
+    Results: NativeValues,
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// The interpreter.
+    interpreter: &'interpreter Interpreter<Instance, Export, LocalImport, Memory, MemoryView>,
+
+    /// Phantom data.
+    _phantom: PhantomData<fn(Params) -> Results>,
+}
+
+impl<'interpreter, Params, Results, Instance, Export, LocalImport, Memory, MemoryView>
+    TypedAdapter<'interpreter, Params, Results, Instance, Export, LocalImport, Memory, MemoryView>
+where
+    Params: NativeValues,
+    Results: NativeValues,
+    Export: wasm::structures::Export,
+    LocalImport: wasm::structures::LocalImport,
+    Memory: wasm::structures::Memory<MemoryView>,
+    MemoryView: wasm::structures::MemoryView,
+    Instance: wasm::structures::Instance<Export, LocalImport, Memory, MemoryView>,
+{
+    /// Creates a typed adapter, and checks the native types against
+    /// the signature of the interpreter, if known.
+    pub(crate) fn new(
+        interpreter: &'interpreter Interpreter<Instance, Export, LocalImport, Memory, MemoryView>,
+    ) -> InterpreterResult<Self> {
+        if let Some(signature) = &interpreter.signature {
+            let inputs = Params::interface_types();
+            let outputs = Results::interface_types();
+
+            if signature.inputs != inputs || signature.outputs != outputs {
+                return Err(InterpreterError::TypedSignatureMismatch {
+                    expected: (signature.inputs.clone(), signature.outputs.clone()),
+                    received: (inputs, outputs),
+                });
+            }
+        }
+
+        Ok(Self {
+            interpreter,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Runs the interpreter with the parameters, and returns the
+    /// results.
+    pub fn call(&self, params: Params, wasm_instance: &mut Instance) -> InterpreterResult<Results> {
+        let stack = self
+            .interpreter
+            .run(&params.into_interface_values(), wasm_instance)?;
+        let outputs = stack.as_slice();
+
+        Results::from_interface_values(outputs).ok_or_else(|| InterpreterError::OutputsMismatch {
+            expected: Results::interface_types(),
+            received: outputs.iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{
+            instructions::tests::{Export, Instance, LocalImport, Memory, MemoryView},
+            Instruction, Interpreter,
+        },
+        types::InterfaceType,
+    };
+    use std::convert::TryInto;
+
+    type TestInterpreter = Interpreter<Instance, Export, LocalImport, Memory, MemoryView>;
+
+    fn interpreter(instructions: Vec<Instruction>) -> TestInterpreter {
+        (&instructions).try_into().unwrap()
+    }
+
+    #[test]
+    fn test_call() {
+        let interpreter = interpreter(vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::CallCore { function_index: 42 },
+        ])
+        .with_signature(
+            vec![InterfaceType::I32, InterfaceType::I32],
+            vec![InterfaceType::I32],
+        );
+        let multiply = interpreter.typed::<(i32, i32), i32>().unwrap();
+
+        assert_eq!(multiply.call((3, 4), &mut Instance::new()).unwrap(), 12);
+        assert_eq!(multiply.call((5, 6), &mut Instance::new()).unwrap(), 30);
+    }
+
+    #[test]
+    fn test_tuples() {
+        let interpreter = interpreter(vec![
+            Instruction::ArgumentGet { index: 1 },
+            Instruction::ArgumentGet { index: 0 },
+        ]);
+        let swap = interpreter.typed::<(String, u32), (u32, String)>().unwrap();
+
+        assert_eq!(
+            swap.call(("foo".into(), 7), &mut Instance::new()).unwrap(),
+            (7, "foo".into()),
+        );
+    }
+
+    #[test]
+    fn test_signature_mismatch() {
+        let interpreter = interpreter(vec![
+            Instruction::ArgumentGet { index: 0 },
+            Instruction::S32FromI32,
+        ])
+        .with_signature(vec![InterfaceType::I32], vec![InterfaceType::S32]);
+
+        assert_eq!(
+            interpreter.typed::<i32, i32>().err().unwrap().to_string(),
+            "the adapter has the signature `[I32] -> [S32]` but it is typed as `[I32] -> [I32]`",
+        );
+    }
+
+    #[test]
+    fn test_outputs_mismatch() {
+        let interpreter = interpreter(vec![Instruction::ArgumentGet { index: 0 }]);
+        let typed = interpreter.typed::<i32, ()>().unwrap();
+
+        assert_eq!(
+            typed
+                .call(1, &mut Instance::new())
+                .err()
+                .unwrap()
+                .to_string(),
+            "the adapter must return values of kind `[]` but it has left values of kind `[I32]` on the stack",
+        );
+    }
+}
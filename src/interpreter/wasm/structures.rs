@@ -1,7 +1,7 @@
 #![allow(missing_docs)]
 
-use crate::{ast, types::InterfaceType, values::InterfaceValue};
-use std::{cell::Cell, ops::Deref};
+use crate::{ast, errors::HostError, types::InterfaceType, values::InterfaceValue};
+use std::{cell::Cell, future::Future, ops::Deref, pin::Pin};
 
 pub trait TypedIndex: Copy + Clone {
     fn new(index: usize) -> Self;
@@ -44,7 +44,7 @@ pub trait Export {
     fn outputs_cardinality(&self) -> usize;
     fn inputs(&self) -> &[InterfaceType];
     fn outputs(&self) -> &[InterfaceType];
-    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()>;
+    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError>;
 }
 
 pub trait LocalImport {
@@ -52,7 +52,20 @@ pub trait LocalImport {
     fn outputs_cardinality(&self) -> usize;
     fn inputs(&self) -> &[InterfaceType];
     fn outputs(&self) -> &[InterfaceType];
-    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()>;
+    fn call(&self, arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError>;
+}
+
+/// The future returned by an asynchronous local or import function.
+pub type LocalImportFuture<'call> =
+    Pin<Box<dyn Future<Output = Result<Vec<InterfaceValue>, HostError>> + 'call>>;
+
+/// A local or import function whose calls return futures, e.g. because
+/// it does some I/O. It is called by the `call-core` instructions of
+/// [`Interpreter::run_async`](crate::interpreter::Interpreter::run_async).
+pub trait AsyncLocalImport {
+    fn inputs(&self) -> &[InterfaceType];
+    fn outputs(&self) -> &[InterfaceType];
+    fn call<'call>(&'call self, arguments: &'call [InterfaceValue]) -> LocalImportFuture<'call>;
 }
 
 pub trait MemoryView: Deref<Target = [Cell<u8>]> {}
@@ -75,6 +88,31 @@ where
     fn local_or_import<I: TypedIndex + LocalImportIndex>(&mut self, index: I) -> Option<&LI>;
     fn memory(&self, index: usize) -> Option<&M>;
     fn wit_type(&self, index: u32) -> Option<&ast::Type>;
+
+    /// Returns the WIT adapter at the given index, if any. It is used
+    /// by the `call-adapter` instructions.
+    fn wit_adapter(&self, _index: u32) -> Option<&ast::Adapter> {
+        None
+    }
+
+    /// Returns the name of the WIT export implemented by the adapter
+    /// at the given index, if any, see
+    /// [`Interfaces::adapter_export_name`](ast::Interfaces::adapter_export_name).
+    /// It is used to describe the adapters in the errors.
+    fn wit_export_name(&self, _adapter_index: u32) -> Option<&str> {
+        None
+    }
+
+    /// Returns the asynchronous local or import function at the given
+    /// index, if any. It takes precedence over
+    /// [`Instance::local_or_import`] when the interpreter runs with
+    /// [`Interpreter::run_async`](crate::interpreter::Interpreter::run_async).
+    fn async_local_or_import<I: TypedIndex + LocalImportIndex>(
+        &mut self,
+        _index: I,
+    ) -> Option<&dyn AsyncLocalImport> {
+        None
+    }
 }
 
 impl Export for () {
@@ -94,8 +132,8 @@ impl Export for () {
         &[]
     }
 
-    fn call(&self, _arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()> {
-        Err(())
+    fn call(&self, _arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> {
+        Err("`()` cannot be called".into())
     }
 }
 
@@ -116,8 +154,8 @@ impl LocalImport for () {
         &[]
     }
 
-    fn call(&self, _arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, ()> {
-        Err(())
+    fn call(&self, _arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> {
+        Err("`()` cannot be called".into())
     }
 }
 
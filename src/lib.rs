@@ -5,7 +5,8 @@
 //!    representations,
 //! 2. [AST]: To represent the WIT language as a tree
 //!    (which is not really abstract). This is the central
-//!    representation of the language.
+//!    representation of the language. The [visitors](visit)
+//!    traverse it, by reference or by mutable reference.
 //! 3. [Decoders](decoders): To read the [AST] from a particular data
 //!    representation; for instance, [`decoders::binary::parse`] reads
 //!    the [AST] from a binary.
@@ -21,7 +22,7 @@
 //!     * [A stack-based interpreter](interpreter::Interpreter),
 //!       defined by:
 //!          * A compiler that transforms a set of instructions into a
-//!            set of executable instructions,
+//!            set of operations, executed by a dispatch loop,
 //!          * A stack,
 //!          * A runtime that holds the “invocation inputs” (arguments
 //!            of the interpreter), the stack, and the WebAssembly
@@ -65,3 +66,4 @@ pub mod interpreter;
 mod serde;
 pub mod values;
 pub mod vec1;
+pub mod visit;
@@ -49,14 +49,12 @@ macro_rules! consume {
 ///
 /// ```rust,ignore
 /// executable_instruction!(
-///     foo(x: u64, y: u64, instruction_name: String) -> _ {
-/// //                                                   ^ output type is purposely blank
-/// //                      ^^^^^^^^^^^^^^^^ the instruction name, for debugging purposes
-/// //              ^ the `y` argument
-/// //      ^ the `x` argument
+///     foo(runtime, x: u64, y: u64, instruction: &Instruction) {
+/// //                               ^^^^^^^^^^^ the instruction, to report errors
+/// //                       ^ the `y` argument
+/// //               ^ the `x` argument
+/// //      ^^^^^^^ the name of the `Runtime` instance
 ///
-///     // an executable instruction is a closure that takes a `Runtime` instance
-///     move |runtime| -> _ {
 ///         // Do something.
 ///
 ///         Ok(())
@@ -64,12 +62,16 @@ macro_rules! consume {
 /// );
 /// ```
 ///
+/// It creates a function, which is called by the dispatch loop of the
+/// interpreter when it executes the corresponding operation.
+///
 /// Check the existing executable instruction to get more examples.
 macro_rules! executable_instruction {
-    ($name:ident ( $($argument_name:ident: $argument_type:ty),* ) -> _ $implementation:block ) => {
+    ($name:ident ( $runtime:ident $(, $argument_name:ident: $argument_type:ty)* $(,)? ) $implementation:block ) => {
         pub(crate) fn $name<Instance, Export, LocalImport, Memory, MemoryView>(
+            $runtime: &mut crate::interpreter::Runtime<'_, '_, Instance, Export, LocalImport, Memory, MemoryView>,
             $($argument_name: $argument_type),*
-        ) -> crate::interpreter::ExecutableInstruction<Instance, Export, LocalImport, Memory, MemoryView>
+        ) -> crate::errors::InstructionResult<()>
         where
             Export: crate::interpreter::wasm::structures::Export,
             LocalImport: crate::interpreter::wasm::structures::LocalImport,
@@ -80,7 +82,7 @@ macro_rules! executable_instruction {
             #[allow(unused_imports)]
             use crate::interpreter::{stack::Stackable};
 
-            Box::new($implementation)
+            $implementation
         }
     };
 }
@@ -109,8 +111,9 @@ macro_rules! test_executable_instruction {
             };
             use std::{cell::Cell, collections::HashMap, convert::TryInto};
 
+            let instructions = vec![$($instructions),*];
             let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> =
-                (&vec![$($instructions),*]).try_into().unwrap();
+                (&instructions).try_into().unwrap();
 
             let invocation_inputs = vec![$($invocation_inputs),*];
             let mut instance = $instance;
@@ -121,7 +124,27 @@ macro_rules! test_executable_instruction {
                 Err(e) => e.to_string(),
             };
 
-            assert!(run.is_ok(), err);
+            assert!(run.is_ok(), "{}", err);
+
+            let stack = run.unwrap();
+
+            assert_eq!(stack.as_slice(), &[$($stack),*]);
+
+            // The interpreter resolved against the instance must
+            // behave the same.
+            let mut instance = $instance;
+            let run = Interpreter::<Instance, Export, LocalImport, Memory, MemoryView>::with_instance(
+                &instructions,
+                &mut instance,
+            )
+            .and_then(|interpreter| interpreter.run(&invocation_inputs, &mut instance));
+
+            let err = match &run {
+                Ok(_) => "".to_string(),
+                Err(e) => e.to_string(),
+            };
+
+            assert!(run.is_ok(), "{}", err);
 
             let stack = run.unwrap();
 
@@ -151,8 +174,9 @@ macro_rules! test_executable_instruction {
             };
             use std::{cell::Cell, collections::HashMap, convert::TryInto};
 
+            let instructions = vec![$($instructions),*];
             let interpreter: Interpreter<Instance, Export, LocalImport, Memory, MemoryView> =
-                (&vec![$($instructions),*]).try_into().unwrap();
+                (&instructions).try_into().unwrap();
 
             let invocation_inputs = vec![$($invocation_inputs),*];
             let mut instance = $instance;
@@ -163,6 +187,22 @@ macro_rules! test_executable_instruction {
             let error = run.unwrap_err().to_string();
 
             assert_eq!(error, String::from($error));
+
+            // The interpreter resolved against the instance must fail
+            // the same way, either when it is resolved, or when it
+            // runs.
+            let mut instance = $instance;
+            let run = Interpreter::<Instance, Export, LocalImport, Memory, MemoryView>::with_instance(
+                &instructions,
+                &mut instance,
+            )
+            .and_then(|interpreter| interpreter.run(&invocation_inputs, &mut instance));
+
+            assert!(run.is_err());
+
+            let error = run.unwrap_err().to_string();
+
+            assert_eq!(error, String::from($error));
         }
     };
 }
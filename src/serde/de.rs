@@ -203,6 +203,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
             Some(InterfaceValue::I32(_)) => self.deserialize_i32(visitor),
             Some(InterfaceValue::I64(_)) => self.deserialize_i64(visitor),
             Some(InterfaceValue::Record(_)) => unreachable!("Records should have been flattened."), // already flattened
+            Some(InterfaceValue::List(..)) => self.deserialize_seq(visitor),
             None => Err(DeserializeError::InputEmpty),
         }
     }
@@ -359,11 +360,33 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        todo!("`seq` is not supported by WIT for the moment.")
+        match self.iterator.peek() {
+            Some(InterfaceValue::List(_, elements)) => {
+                self.iterator.next();
+
+                // The elements are deserialized by their own
+                // deserializer, so that records are flattened per
+                // element.
+                let mut deserializer = Deserializer::new(elements);
+                let result = visitor.visit_seq(Sequence::new(&mut deserializer))?;
+
+                match deserializer.iterator.peek() {
+                    None => Ok(result),
+                    _ => Err(DeserializeError::InputNotEmpty),
+                }
+            }
+
+            Some(wrong_value) => Err(de::Error::invalid_type(
+                de::Unexpected::Other(&format!("{:?}", InterfaceType::from(*wrong_value))),
+                &visitor,
+            )),
+
+            None => Err(DeserializeError::InputEmpty),
+        }
     }
 
     fn deserialize_tuple<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
@@ -462,6 +485,7 @@ impl<'de, 'a> de::SeqAccess<'de> for Sequence<'a, 'de> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::RecordType;
 
     macro_rules! deserialize_value {
         ($test_name:ident, $variant:ident, $ty:ident, $value:expr) => {
@@ -587,4 +611,55 @@ mod tests {
 
         assert_eq!(from_interface_values::<Line>(&input).unwrap(), output);
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_deserialize_value__list() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct S<'a> {
+            #[serde(borrow)]
+            names: Vec<&'a str>,
+            points: Vec<Point>,
+            bytes: Vec<u8>,
+        }
+
+        let input = vec![InterfaceValue::Record(vec1![
+            InterfaceValue::List(
+                InterfaceType::String,
+                vec![
+                    InterfaceValue::String("foo".to_string()),
+                    InterfaceValue::String("bar".to_string()),
+                ],
+            ),
+            InterfaceValue::List(
+                InterfaceType::Record(RecordType {
+                    fields: vec1![InterfaceType::I32, InterfaceType::I32],
+                }),
+                vec![
+                    InterfaceValue::Record(vec1![InterfaceValue::I32(1), InterfaceValue::I32(2)]),
+                    InterfaceValue::Record(vec1![InterfaceValue::I32(3), InterfaceValue::I32(4)]),
+                ],
+            ),
+            InterfaceValue::List(InterfaceType::U8, vec![]),
+        ])];
+        let output = S {
+            names: vec!["foo", "bar"],
+            points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+            bytes: vec![],
+        };
+
+        assert_eq!(from_interface_values::<S>(&input).unwrap(), output);
+        assert_eq!(
+            from_interface_values::<Vec<i32>>(&[InterfaceValue::I32(1)]),
+            Err(DeserializeError::Message(
+                "invalid type: I32, expected a sequence".to_string()
+            )),
+        );
+    }
 }
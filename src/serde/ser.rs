@@ -110,6 +110,10 @@ pub enum SerializeError {
     /// A record must contain at least one field.
     RecordNeedsAtLeastOneField,
 
+    /// A list must contain at least one element, to know its element
+    /// type.
+    ListNeedsAtLeastOneElement,
+
     /// Arbitrary message.
     Message(String),
 }
@@ -135,6 +139,10 @@ impl Display for SerializeError {
                 formatter,
                 "a record must contain at least one field, zero given"
             ),
+            Self::ListNeedsAtLeastOneElement => write!(
+                formatter,
+                "a list must contain at least one element to know its type, zero given"
+            ),
             Self::Message(ref msg) => write!(formatter, "{}", msg),
         }
     }
@@ -284,8 +292,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         todo!("`newtype_variant` is not supported by WIT for the moment.")
     }
 
-    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        todo!("`seq` is not supported by WIT for the moment.")
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.push_with_capacity(len.unwrap_or(0));
+
+        Ok(self)
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
@@ -341,15 +351,26 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
     type Ok = ();
     type Error = SerializeError;
 
-    fn serialize_element<T>(&mut self, _value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        unimplemented!()
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        unimplemented!()
+        let elements = self.pop()?;
+
+        // The element type is the type of the first element, thus an
+        // empty list has no type.
+        let element_type = elements
+            .first()
+            .map(Into::into)
+            .ok_or(Self::Error::ListNeedsAtLeastOneElement)?;
+        self.last()
+            .push(InterfaceValue::List(element_type, elements));
+
+        Ok(())
     }
 }
 
@@ -473,6 +494,7 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::{InterfaceType, RecordType};
 
     macro_rules! serialize_value {
         ($test_name:ident, $ty:ident, $variant:ident, $value:expr) => {
@@ -578,4 +600,49 @@ mod tests {
 
         assert_eq!(to_interface_value(&input).unwrap(), output);
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_serialize_value__list() {
+        #[derive(Serialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Serialize)]
+        struct S {
+            names: Vec<String>,
+            points: Vec<Point>,
+        }
+
+        let input = S {
+            names: vec!["foo".to_string(), "bar".to_string()],
+            points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+        };
+        let output = InterfaceValue::Record(vec1![
+            InterfaceValue::List(
+                InterfaceType::String,
+                vec![
+                    InterfaceValue::String("foo".to_string()),
+                    InterfaceValue::String("bar".to_string()),
+                ],
+            ),
+            InterfaceValue::List(
+                InterfaceType::Record(RecordType {
+                    fields: vec1![InterfaceType::I32, InterfaceType::I32],
+                }),
+                vec![
+                    InterfaceValue::Record(vec1![InterfaceValue::I32(1), InterfaceValue::I32(2)]),
+                    InterfaceValue::Record(vec1![InterfaceValue::I32(3), InterfaceValue::I32(4)]),
+                ],
+            ),
+        ]);
+
+        assert_eq!(to_interface_value(&input).unwrap(), output);
+        assert_eq!(
+            to_interface_value(&Vec::<i32>::new()),
+            Err(SerializeError::ListNeedsAtLeastOneElement),
+        );
+    }
 }
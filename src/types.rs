@@ -49,6 +49,9 @@ pub enum InterfaceType {
 
     /// A record.
     Record(RecordType),
+
+    /// A list, with the type of its elements.
+    List(Box<InterfaceType>),
 }
 
 /// Represents a record type.
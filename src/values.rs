@@ -55,6 +55,10 @@ pub enum InterfaceValue {
 
     /// A record.
     Record(Vec1<InterfaceValue>),
+
+    /// A list, with the type of its elements, so that the type of an
+    /// empty list is known.
+    List(InterfaceType, Vec<InterfaceValue>),
 }
 
 impl From<&InterfaceValue> for InterfaceType {
@@ -75,6 +79,7 @@ impl From<&InterfaceValue> for InterfaceType {
             InterfaceValue::I32(_) => Self::I32,
             InterfaceValue::I64(_) => Self::I64,
             InterfaceValue::Record(values) => Self::Record((&**values).into()),
+            InterfaceValue::List(element_type, _) => Self::List(Box::new(element_type.clone())),
         }
     }
 }
@@ -247,4 +252,21 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn interface_type_from_interface_value__list() {
+        assert_eq!(
+            InterfaceType::from(&InterfaceValue::List(
+                InterfaceType::String,
+                vec![InterfaceValue::String("a".to_string())]
+            )),
+            InterfaceType::List(Box::new(InterfaceType::String))
+        );
+
+        assert_eq!(
+            InterfaceType::from(&InterfaceValue::List(InterfaceType::I32, vec![])),
+            InterfaceType::List(Box::new(InterfaceType::I32))
+        );
+    }
 }
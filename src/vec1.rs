@@ -3,7 +3,7 @@
 use std::{
     error,
     fmt::{self, Debug},
-    ops,
+    ops, slice,
 };
 
 /// `Vec1<T>` represents a non-empty `Vec<T>`. It derefs to `Vec<T>`
@@ -39,6 +39,12 @@ where
             Ok(Self(items))
         }
     }
+
+    /// Returns an iterator over mutable references to the items. The
+    /// number of items can't change, so the vector stays non-empty.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.0.iter_mut()
+    }
 }
 
 impl<T> fmt::Debug for Vec1<T>
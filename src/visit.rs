@@ -0,0 +1,475 @@
+//! Traverses the [AST](crate::ast), by reference with [`Visit`], or
+//! by mutable reference with [`VisitMut`].
+//!
+//! Each method of the traits visits one kind of node. Its default
+//! implementation calls the free function with the same name, which
+//! visits the children of the node with the visitor. A pass overrides
+//! the methods of the nodes it cares about, and calls the free
+//! function from its override to keep recursing.
+//!
+//! ```rust
+//! use wasmer_interface_types::{
+//!     ast::{Export, Interfaces},
+//!     decoders::wat::{parse, Buffer},
+//!     visit::VisitMut,
+//! };
+//!
+//! struct Prefix;
+//!
+//! impl VisitMut for Prefix {
+//!     fn visit_export_mut(&mut self, export: &mut Export<'_>) {
+//!         export.name = format!("wit_{}", export.name).into();
+//!     }
+//! }
+//!
+//! let buffer = Buffer::new(r#"(@interface export "foo" (func 0))"#).unwrap();
+//! let mut interfaces: Interfaces = parse(&buffer).unwrap();
+//!
+//! Prefix.visit_interfaces_mut(&mut interfaces);
+//!
+//! assert_eq!(interfaces.exports[0].name, "wit_foo");
+//! ```
+
+use crate::{
+    ast::{Adapter, Export, Implementation, Import, Interfaces, Type},
+    interpreter::{BlockType, Instruction},
+    types::{InterfaceType, RecordType},
+};
+
+/// Visits the nodes of the AST by reference. `'ast` is the lifetime
+/// of the visited nodes, so that a visitor can keep references to
+/// them.
+pub trait Visit<'ast> {
+    /// Visits interfaces, see [`visit_interfaces`].
+    fn visit_interfaces(&mut self, interfaces: &'ast Interfaces<'_>) {
+        visit_interfaces(self, interfaces)
+    }
+
+    /// Visits a type, see [`visit_type`].
+    fn visit_type(&mut self, r#type: &'ast Type) {
+        visit_type(self, r#type)
+    }
+
+    /// Visits an interface type, see [`visit_interface_type`].
+    fn visit_interface_type(&mut self, interface_type: &'ast InterfaceType) {
+        visit_interface_type(self, interface_type)
+    }
+
+    /// Visits a record type, see [`visit_record_type`].
+    fn visit_record_type(&mut self, record_type: &'ast RecordType) {
+        visit_record_type(self, record_type)
+    }
+
+    /// Visits an import. It has no children.
+    fn visit_import(&mut self, _import: &'ast Import<'_>) {}
+
+    /// Visits an export. It has no children.
+    fn visit_export(&mut self, _export: &'ast Export<'_>) {}
+
+    /// Visits an adapter, see [`visit_adapter`].
+    fn visit_adapter(&mut self, adapter: &'ast Adapter) {
+        visit_adapter(self, adapter)
+    }
+
+    /// Visits an implementation. It has no children.
+    fn visit_implementation(&mut self, _implementation: &'ast Implementation) {}
+
+    /// Visits an instruction, see [`visit_instruction`].
+    fn visit_instruction(&mut self, instruction: &'ast Instruction) {
+        visit_instruction(self, instruction)
+    }
+
+    /// Visits a block type, see [`visit_block_type`].
+    fn visit_block_type(&mut self, block_type: &'ast BlockType) {
+        visit_block_type(self, block_type)
+    }
+}
+
+/// Visits the types, the imports, the adapters, the exports, and the
+/// implementations, in this order.
+pub fn visit_interfaces<'ast, V>(visitor: &mut V, interfaces: &'ast Interfaces<'_>)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    for r#type in &interfaces.types {
+        visitor.visit_type(r#type);
+    }
+
+    for import in &interfaces.imports {
+        visitor.visit_import(import);
+    }
+
+    for adapter in &interfaces.adapters {
+        visitor.visit_adapter(adapter);
+    }
+
+    for export in &interfaces.exports {
+        visitor.visit_export(export);
+    }
+
+    for implementation in &interfaces.implementations {
+        visitor.visit_implementation(implementation);
+    }
+}
+
+/// Visits the inputs and the outputs of a function type, or the
+/// record type.
+pub fn visit_type<'ast, V>(visitor: &mut V, r#type: &'ast Type)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    match r#type {
+        Type::Function { inputs, outputs } => {
+            for interface_type in inputs.iter().chain(outputs) {
+                visitor.visit_interface_type(interface_type);
+            }
+        }
+
+        Type::Record(record_type) => visitor.visit_record_type(record_type),
+    }
+}
+
+/// Visits the record type of a record, or the element type of a
+/// list.
+pub fn visit_interface_type<'ast, V>(visitor: &mut V, interface_type: &'ast InterfaceType)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    match interface_type {
+        InterfaceType::Record(record_type) => visitor.visit_record_type(record_type),
+        InterfaceType::List(element_type) => visitor.visit_interface_type(element_type),
+        _ => {}
+    }
+}
+
+/// Visits the field types.
+pub fn visit_record_type<'ast, V>(visitor: &mut V, record_type: &'ast RecordType)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    for field in record_type.fields.iter() {
+        visitor.visit_interface_type(field);
+    }
+}
+
+/// Visits the instructions.
+pub fn visit_adapter<'ast, V>(visitor: &mut V, adapter: &'ast Adapter)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    for instruction in &adapter.instructions {
+        visitor.visit_instruction(instruction);
+    }
+}
+
+/// Visits the block type of `block` and `if`, or the element type of
+/// `list.lift` and `for-each`.
+pub fn visit_instruction<'ast, V>(visitor: &mut V, instruction: &'ast Instruction)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    match instruction {
+        Instruction::Block { block_type } | Instruction::If { block_type } => {
+            visitor.visit_block_type(block_type)
+        }
+
+        Instruction::ListLift { element_type } | Instruction::ForEach { element_type } => {
+            visitor.visit_interface_type(element_type)
+        }
+
+        _ => {}
+    }
+}
+
+/// Visits the inputs and the outputs.
+pub fn visit_block_type<'ast, V>(visitor: &mut V, block_type: &'ast BlockType)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    for interface_type in block_type.inputs.iter().chain(&block_type.outputs) {
+        visitor.visit_interface_type(interface_type);
+    }
+}
+
+/// Visits the nodes of the AST by mutable reference, so that a
+/// visitor can rewrite them in place.
+pub trait VisitMut {
+    /// Visits interfaces, see [`visit_interfaces_mut`].
+    fn visit_interfaces_mut(&mut self, interfaces: &mut Interfaces<'_>) {
+        visit_interfaces_mut(self, interfaces)
+    }
+
+    /// Visits a type, see [`visit_type_mut`].
+    fn visit_type_mut(&mut self, r#type: &mut Type) {
+        visit_type_mut(self, r#type)
+    }
+
+    /// Visits an interface type, see [`visit_interface_type_mut`].
+    fn visit_interface_type_mut(&mut self, interface_type: &mut InterfaceType) {
+        visit_interface_type_mut(self, interface_type)
+    }
+
+    /// Visits a record type, see [`visit_record_type_mut`].
+    fn visit_record_type_mut(&mut self, record_type: &mut RecordType) {
+        visit_record_type_mut(self, record_type)
+    }
+
+    /// Visits an import. It has no children.
+    fn visit_import_mut(&mut self, _import: &mut Import<'_>) {}
+
+    /// Visits an export. It has no children.
+    fn visit_export_mut(&mut self, _export: &mut Export<'_>) {}
+
+    /// Visits an adapter, see [`visit_adapter_mut`].
+    fn visit_adapter_mut(&mut self, adapter: &mut Adapter) {
+        visit_adapter_mut(self, adapter)
+    }
+
+    /// Visits an implementation. It has no children.
+    fn visit_implementation_mut(&mut self, _implementation: &mut Implementation) {}
+
+    /// Visits an instruction, see [`visit_instruction_mut`].
+    fn visit_instruction_mut(&mut self, instruction: &mut Instruction) {
+        visit_instruction_mut(self, instruction)
+    }
+
+    /// Visits a block type, see [`visit_block_type_mut`].
+    fn visit_block_type_mut(&mut self, block_type: &mut BlockType) {
+        visit_block_type_mut(self, block_type)
+    }
+}
+
+/// Visits the types, the imports, the adapters, the exports, and the
+/// implementations, in this order.
+pub fn visit_interfaces_mut<V>(visitor: &mut V, interfaces: &mut Interfaces<'_>)
+where
+    V: VisitMut + ?Sized,
+{
+    for r#type in &mut interfaces.types {
+        visitor.visit_type_mut(r#type);
+    }
+
+    for import in &mut interfaces.imports {
+        visitor.visit_import_mut(import);
+    }
+
+    for adapter in &mut interfaces.adapters {
+        visitor.visit_adapter_mut(adapter);
+    }
+
+    for export in &mut interfaces.exports {
+        visitor.visit_export_mut(export);
+    }
+
+    for implementation in &mut interfaces.implementations {
+        visitor.visit_implementation_mut(implementation);
+    }
+}
+
+/// Visits the inputs and the outputs of a function type, or the
+/// record type.
+pub fn visit_type_mut<V>(visitor: &mut V, r#type: &mut Type)
+where
+    V: VisitMut + ?Sized,
+{
+    match r#type {
+        Type::Function { inputs, outputs } => {
+            for interface_type in inputs.iter_mut().chain(outputs) {
+                visitor.visit_interface_type_mut(interface_type);
+            }
+        }
+
+        Type::Record(record_type) => visitor.visit_record_type_mut(record_type),
+    }
+}
+
+/// Visits the record type of a record, or the element type of a
+/// list.
+pub fn visit_interface_type_mut<V>(visitor: &mut V, interface_type: &mut InterfaceType)
+where
+    V: VisitMut + ?Sized,
+{
+    match interface_type {
+        InterfaceType::Record(record_type) => visitor.visit_record_type_mut(record_type),
+        InterfaceType::List(element_type) => visitor.visit_interface_type_mut(element_type),
+        _ => {}
+    }
+}
+
+/// Visits the field types.
+pub fn visit_record_type_mut<V>(visitor: &mut V, record_type: &mut RecordType)
+where
+    V: VisitMut + ?Sized,
+{
+    for field in record_type.fields.iter_mut() {
+        visitor.visit_interface_type_mut(field);
+    }
+}
+
+/// Visits the instructions.
+pub fn visit_adapter_mut<V>(visitor: &mut V, adapter: &mut Adapter)
+where
+    V: VisitMut + ?Sized,
+{
+    for instruction in &mut adapter.instructions {
+        visitor.visit_instruction_mut(instruction);
+    }
+}
+
+/// Visits the block type of `block` and `if`, or the element type of
+/// `list.lift` and `for-each`.
+pub fn visit_instruction_mut<V>(visitor: &mut V, instruction: &mut Instruction)
+where
+    V: VisitMut + ?Sized,
+{
+    match instruction {
+        Instruction::Block { block_type } | Instruction::If { block_type } => {
+            visitor.visit_block_type_mut(block_type)
+        }
+
+        Instruction::ListLift { element_type } | Instruction::ForEach { element_type } => {
+            visitor.visit_interface_type_mut(element_type)
+        }
+
+        _ => {}
+    }
+}
+
+/// Visits the inputs and the outputs.
+pub fn visit_block_type_mut<V>(visitor: &mut V, block_type: &mut BlockType)
+where
+    V: VisitMut + ?Sized,
+{
+    for interface_type in block_type.inputs.iter_mut().chain(&mut block_type.outputs) {
+        visitor.visit_interface_type_mut(interface_type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::wat::{parse, Buffer};
+
+    const INPUT: &str = r#"(@interface type (func (param i32) (result string)))
+(@interface type (record (field string) (field record (field i32) (field f64))))
+(@interface import "ns" "foo" (func (type 0)))
+(@interface func (type 0) arg.get 0 block (result s64) list.lift u8 end call-core 0)
+(@interface export "bar" (func 0))
+(@interface implement (func 0) (func 0))"#;
+
+    #[test]
+    fn test_visit() {
+        #[derive(Default)]
+        struct UsedTypes<'ast> {
+            interface_types: Vec<&'ast InterfaceType>,
+            record_types: usize,
+            export_names: Vec<&'ast str>,
+        }
+
+        impl<'ast> Visit<'ast> for UsedTypes<'ast> {
+            fn visit_interface_type(&mut self, interface_type: &'ast InterfaceType) {
+                self.interface_types.push(interface_type);
+                visit_interface_type(self, interface_type);
+            }
+
+            fn visit_record_type(&mut self, record_type: &'ast RecordType) {
+                self.record_types += 1;
+                visit_record_type(self, record_type);
+            }
+
+            fn visit_export(&mut self, export: &'ast Export<'_>) {
+                self.export_names.push(&export.name);
+            }
+        }
+
+        let buffer = Buffer::new(INPUT).unwrap();
+        let interfaces = parse(&buffer).unwrap();
+        let mut used_types = UsedTypes::default();
+        used_types.visit_interfaces(&interfaces);
+
+        assert_eq!(
+            used_types.interface_types,
+            vec![
+                &InterfaceType::I32,
+                &InterfaceType::String,
+                &InterfaceType::String,
+                &InterfaceType::Record(RecordType {
+                    fields: crate::vec1![InterfaceType::I32, InterfaceType::F64],
+                }),
+                &InterfaceType::I32,
+                &InterfaceType::F64,
+                &InterfaceType::S64,
+                &InterfaceType::U8,
+            ],
+        );
+        assert_eq!(used_types.record_types, 2);
+        assert_eq!(used_types.export_names, vec!["bar"]);
+    }
+
+    #[test]
+    fn test_visit_mut() {
+        struct Rewrite;
+
+        impl VisitMut for Rewrite {
+            fn visit_interface_type_mut(&mut self, interface_type: &mut InterfaceType) {
+                if *interface_type == InterfaceType::I32 {
+                    *interface_type = InterfaceType::S32;
+                }
+
+                visit_interface_type_mut(self, interface_type);
+            }
+
+            fn visit_export_mut(&mut self, export: &mut Export<'_>) {
+                export.name = format!("{}_renamed", export.name).into();
+            }
+
+            fn visit_instruction_mut(&mut self, instruction: &mut Instruction) {
+                if let Instruction::CallCore { function_index } = instruction {
+                    *function_index += 1;
+                }
+
+                visit_instruction_mut(self, instruction);
+            }
+        }
+
+        let buffer = Buffer::new(INPUT).unwrap();
+        let mut interfaces = parse(&buffer).unwrap();
+        Rewrite.visit_interfaces_mut(&mut interfaces);
+
+        assert_eq!(
+            interfaces.types,
+            vec![
+                Type::Function {
+                    inputs: vec![InterfaceType::S32],
+                    outputs: vec![InterfaceType::String],
+                },
+                Type::Record(RecordType {
+                    fields: crate::vec1![
+                        InterfaceType::String,
+                        InterfaceType::Record(RecordType {
+                            fields: crate::vec1![InterfaceType::S32, InterfaceType::F64],
+                        }),
+                    ],
+                }),
+            ],
+        );
+        assert_eq!(
+            interfaces.adapters[0].instructions,
+            vec![
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::Block {
+                    block_type: BlockType {
+                        inputs: vec![],
+                        outputs: vec![InterfaceType::S64],
+                    },
+                },
+                Instruction::ListLift {
+                    element_type: InterfaceType::U8,
+                },
+                Instruction::End,
+                Instruction::CallCore { function_index: 1 },
+            ],
+        );
+        assert_eq!(interfaces.exports[0].name, "bar_renamed");
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    convert::TryInto,
+    ops::Deref,
+};
+use wasmer_interface_types::{
+    ast,
+    errors::HostError,
+    interpreter::{
+        wasm::structures::{self, LocalImportIndex, TypedIndex},
+        BlockType, ExecutionContext, Instruction, Interpreter,
+    },
+    types::InterfaceType,
+    values::InterfaceValue,
+};
+
+/// An allocator counting the allocations of each thread, so that the
+/// tests running in parallel don't disturb each other.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The counter is unavailable while the thread is destroyed.
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// A local or import function, which takes two `i32` and returns
+/// nothing, so that calling it doesn't allocate outputs.
+struct LocalImport {
+    inputs: Vec<InterfaceType>,
+}
+
+impl structures::LocalImport for LocalImport {
+    fn inputs_cardinality(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn outputs_cardinality(&self) -> usize {
+        0
+    }
+
+    fn inputs(&self) -> &[InterfaceType] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[InterfaceType] {
+        &[]
+    }
+
+    fn call(&self, _arguments: &[InterfaceValue]) -> Result<Vec<InterfaceValue>, HostError> {
+        Ok(vec![])
+    }
+}
+
+/// A memory view, with no bytes.
+struct MemoryView;
+
+impl structures::MemoryView for MemoryView {}
+
+impl Deref for MemoryView {
+    type Target = [Cell<u8>];
+
+    fn deref(&self) -> &Self::Target {
+        &[]
+    }
+}
+
+/// A memory, with no bytes.
+struct Memory;
+
+impl structures::Memory<MemoryView> for Memory {
+    fn view(&self) -> MemoryView {
+        MemoryView
+    }
+}
+
+/// An instance with a single local or import function, at index 0,
+/// and a single adapter deferring a call to it, at index 0.
+struct Instance {
+    ignore: LocalImport,
+    wit_types: Vec<ast::Type>,
+    wit_adapters: Vec<ast::Adapter>,
+}
+
+impl structures::Instance<(), LocalImport, Memory, MemoryView> for Instance {
+    fn export(&self, _export_name: &str) -> Option<&()> {
+        None
+    }
+
+    fn local_or_import<I: TypedIndex + LocalImportIndex>(
+        &mut self,
+        index: I,
+    ) -> Option<&LocalImport> {
+        if index.index() == 0 {
+            Some(&self.ignore)
+        } else {
+            None
+        }
+    }
+
+    fn memory(&self, _index: usize) -> Option<&Memory> {
+        None
+    }
+
+    fn wit_type(&self, index: u32) -> Option<&ast::Type> {
+        self.wit_types.get(index as usize)
+    }
+
+    fn wit_adapter(&self, index: u32) -> Option<&ast::Adapter> {
+        self.wit_adapters.get(index as usize)
+    }
+}
+
+fn instance() -> Instance {
+    Instance {
+        ignore: LocalImport {
+            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+        },
+        wit_types: vec![ast::Type::Function {
+            inputs: vec![InterfaceType::I32, InterfaceType::I32],
+            outputs: vec![InterfaceType::I32],
+        }],
+        wit_adapters: vec![ast::Adapter {
+            function_type: 0,
+            instructions: vec![
+                Instruction::ArgumentGet { index: 0 },
+                Instruction::ArgumentGet { index: 1 },
+                Instruction::DeferCallCore { function_index: 0 },
+                Instruction::ArgumentGet { index: 0 },
+            ],
+        }],
+    }
+}
+
+/// Runs the interpreter twice with the same execution context, so
+/// that its buffers are large enough, and returns the number of
+/// allocations of a third run.
+fn allocations_of_a_steady_state_run(
+    interpreter: &Interpreter<Instance, (), LocalImport, Memory, MemoryView>,
+    instance: &mut Instance,
+) -> usize {
+    let inputs = [InterfaceValue::I32(3), InterfaceValue::I32(4)];
+    let mut context = ExecutionContext::new();
+    let mut outputs = Vec::new();
+
+    for _ in 0..2 {
+        interpreter
+            .run_into(&inputs, instance, &mut context, &mut outputs)
+            .unwrap();
+    }
+
+    let allocations_before = ALLOCATIONS.with(Cell::get);
+
+    interpreter
+        .run_into(&inputs, instance, &mut context, &mut outputs)
+        .unwrap();
+
+    let allocations = ALLOCATIONS.with(Cell::get) - allocations_before;
+
+    assert_eq!(outputs, vec![InterfaceValue::I32(3)]);
+
+    allocations
+}
+
+/// Tests that the runs reusing an execution context allocate nothing
+/// once its buffers are large enough, with the calls, the deferred
+/// calls, the called adapters and the blocks.
+#[test]
+fn test_run_into_allocates_nothing_in_steady_state() {
+    let instructions = vec![
+        Instruction::ArgumentGet { index: 0 },
+        Instruction::ArgumentGet { index: 1 },
+        Instruction::CallCore { function_index: 0 },
+        Instruction::ArgumentGet { index: 0 },
+        Instruction::ArgumentGet { index: 1 },
+        Instruction::DeferCallCore { function_index: 0 },
+        Instruction::ArgumentGet { index: 0 },
+        Instruction::ArgumentGet { index: 1 },
+        Instruction::CallAdapter { adapter_index: 0 },
+        Instruction::Block {
+            block_type: BlockType {
+                inputs: vec![InterfaceType::I32],
+                outputs: vec![InterfaceType::I32],
+            },
+        },
+        Instruction::ArgumentGet { index: 1 },
+        Instruction::BrIf { relative_depth: 0 },
+        Instruction::End,
+    ];
+    let mut instance = instance();
+
+    let interpreter: Interpreter<Instance, (), LocalImport, Memory, MemoryView> =
+        (&instructions).try_into().unwrap();
+
+    assert_eq!(
+        allocations_of_a_steady_state_run(&interpreter, &mut instance),
+        0
+    );
+
+    let resolved_interpreter = Interpreter::with_instance(&instructions, &mut instance).unwrap();
+
+    assert_eq!(
+        allocations_of_a_steady_state_run(&resolved_interpreter, &mut instance),
+        0
+    );
+}
@@ -21,8 +21,8 @@ fn test_binary_encoding_decoding_roundtrip() {
             }),
         ],
         imports: vec![Import {
-            namespace: "a",
-            name: "b",
+            namespace: "a".into(),
+            name: "b".into(),
             signature_type: 0,
         }],
         adapters: vec![Adapter {
@@ -30,7 +30,7 @@ fn test_binary_encoding_decoding_roundtrip() {
             instructions: vec![Instruction::ArgumentGet { index: 1 }],
         }],
         exports: vec![Export {
-            name: "ab",
+            name: "ab".into(),
             function_type: 1,
         }],
         implementations: vec![Implementation {
@@ -50,4 +50,20 @@ fn test_binary_encoding_decoding_roundtrip() {
     assert!(remainder.is_empty());
 
     assert_eq!(original_ast, ast);
+
+    // The owned AST outlives the binary, and is encoded identically.
+    let owned_ast: OwnedInterfaces = ast.into_owned();
+    let original_binary = binary;
+
+    let mut binary = vec![];
+
+    owned_ast
+        .to_bytes(&mut binary)
+        .expect("Failed to encode the owned AST.");
+
+    assert_eq!(original_binary, binary);
+
+    drop(original_binary);
+
+    assert_eq!(original_ast, owned_ast);
 }